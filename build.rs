@@ -15,10 +15,18 @@ fn main() {
     );
     // Read feature flags detected by nginx-sys and pass to the compiler.
     println!("cargo::rerun-if-env-changed=DEP_NGINX_FEATURES");
-    if let Ok(features) = std::env::var("DEP_NGINX_FEATURES") {
-        for feature in features.split(',').map(str::trim) {
-            println!("cargo::rustc-cfg=ngx_feature=\"{feature}\"");
-        }
+    let features = std::env::var("DEP_NGINX_FEATURES").unwrap_or_default();
+    for feature in features.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+        println!("cargo::rustc-cfg=ngx_feature=\"{feature}\"");
+    }
+
+    // Modules intended for loading into an nginx binary built elsewhere require `--with-compat`
+    // in both builds. Fail early instead of producing a module with an incompatible signature.
+    println!("cargo::rerun-if-env-changed=NGX_COMPAT");
+    if std::env::var("NGX_COMPAT").is_ok_and(|x| !matches!(x.as_str(), "" | "0" | "false" | "no"))
+        && !features.split(',').map(str::trim).any(|x| x == "compat")
+    {
+        panic!("NGX_COMPAT is set, but \"compat\" is missing from DEP_NGINX_FEATURES");
     }
 
     // Specify acceptable values for `ngx_os`
@@ -34,23 +42,20 @@ fn main() {
     }
 
     // Generate cfg values for version checks
-    const VERSION_CHECKS: &[(u64, &str)] = &[
-        //
-        (1_021_001, "nginx1_21_1"),
-        (1_025_001, "nginx1_25_1"),
-    ];
-    VERSION_CHECKS
-        .iter()
-        .for_each(|check| println!("cargo::rustc-check-cfg=cfg({})", check.1));
-    println!("cargo::rerun-if-env-changed=DEP_NGINX_VERSION_NUMBER");
-    if let Ok(version) = std::env::var("DEP_NGINX_VERSION_NUMBER") {
-        let version: u64 = version.parse().unwrap();
+    //
+    // Each `nginxX_Y_Z` flag is set when the detected version is at least X.Y.Z, and allows the
+    // crate to branch on the changes in the nginx structures between the supported versions.
 
-        for check in VERSION_CHECKS {
-            if version >= check.0 {
-                println!("cargo::rustc-cfg={}", check.1);
-            }
-        }
+    // Specify acceptable version flags
+    println!("cargo::rerun-if-env-changed=DEP_NGINX_VERSIONS_CHECK");
+    if let Ok(versions) = std::env::var("DEP_NGINX_VERSIONS_CHECK") {
+        println!("cargo::rustc-check-cfg=cfg({versions})");
+    }
+    // Read version flags detected by nginx-sys and pass to the compiler.
+    println!("cargo::rerun-if-env-changed=DEP_NGINX_VERSIONS");
+    let versions = std::env::var("DEP_NGINX_VERSIONS").unwrap_or_default();
+    for version in versions.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+        println!("cargo::rustc-cfg={version}");
     }
 
     // Pass build directory to the tests
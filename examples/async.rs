@@ -107,6 +107,8 @@ unsafe extern "C" fn check_async_work_done(event: *mut ngx_event_t) {
         // this doesn't have have good performance but works as a simple thread-safe example and
         // doesn't causes segfault. The best method that provides both thread-safety and
         // performance requires an nginx patch.
+        // Blocking work that does not need a separate runtime can be offloaded to the nginx
+        // thread pool with `ngx::async_::spawn_blocking` instead.
         ngx_post_event(event, addr_of_mut!(ngx_posted_next_events));
     }
 }
@@ -166,8 +168,8 @@ http_request_handler!(async_access_handler, |request: &mut http::Request| {
 
     let ctx = unsafe { &mut *ctx };
     ctx.event.handler = Some(check_async_work_done);
-    ctx.event.data = request.connection().cast();
-    ctx.event.log = unsafe { (*request.connection()).log };
+    ctx.event.data = request.connection_ptr().cast();
+    ctx.event.log = request.log_ptr();
     unsafe { ngx_post_event(&mut ctx.event, addr_of_mut!(ngx_posted_next_events)) };
 
     // Request is no longer needed and can be converted to something movable to the async block
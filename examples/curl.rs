@@ -3,12 +3,12 @@ use std::ffi::{c_char, c_void};
 use ngx::core;
 use ngx::ffi::{
     ngx_array_push, ngx_command_t, ngx_conf_t, ngx_http_handler_pt, ngx_http_module_t,
-    ngx_http_phases_NGX_HTTP_ACCESS_PHASE, ngx_int_t, ngx_module_t, ngx_str_t, ngx_uint_t,
-    NGX_CONF_TAKE1, NGX_HTTP_LOC_CONF, NGX_HTTP_LOC_CONF_OFFSET, NGX_HTTP_MODULE, NGX_LOG_EMERG,
+    ngx_http_phases_NGX_HTTP_ACCESS_PHASE, ngx_int_t, ngx_module_t, NGX_CONF_TAKE1,
+    NGX_HTTP_LOC_CONF, NGX_HTTP_LOC_CONF_OFFSET, NGX_HTTP_MODULE, NGX_LOG_EMERG,
 };
 use ngx::http::{self, HttpModule, MergeConfigError};
 use ngx::http::{HttpModuleLocationConf, HttpModuleMainConf, NgxHttpCoreModule};
-use ngx::{http_request_handler, ngx_conf_log_error, ngx_log_debug_http, ngx_string};
+use ngx::{commands, http_request_handler, ngx_log_debug_http};
 
 struct Module;
 
@@ -43,17 +43,17 @@ unsafe impl HttpModuleLocationConf for Module {
     type LocationConf = ModuleConfig;
 }
 
-static mut NGX_HTTP_CURL_COMMANDS: [ngx_command_t; 2] = [
-    ngx_command_t {
-        name: ngx_string!("curl"),
-        type_: (NGX_HTTP_LOC_CONF | NGX_CONF_TAKE1) as ngx_uint_t,
-        set: Some(ngx_http_curl_commands_set_enable),
-        conf: NGX_HTTP_LOC_CONF_OFFSET,
-        offset: 0,
-        post: std::ptr::null_mut(),
-    },
-    ngx_command_t::empty(),
-];
+commands! {
+    static mut NGX_HTTP_CURL_COMMANDS = [
+        {
+            name: "curl",
+            type: NGX_HTTP_LOC_CONF | NGX_CONF_TAKE1,
+            set: ngx_http_curl_commands_set_enable,
+            conf: NGX_HTTP_LOC_CONF_OFFSET,
+            offset: 0,
+        },
+    ];
+}
 
 static NGX_HTTP_CURL_MODULE_CTX: ngx_http_module_t = ngx_http_module_t {
     preconfiguration: Some(Module::preconfiguration),
@@ -115,27 +115,18 @@ extern "C" fn ngx_http_curl_commands_set_enable(
     _cmd: *mut ngx_command_t,
     conf: *mut c_void,
 ) -> *mut c_char {
-    unsafe {
-        let conf = &mut *(conf as *mut ModuleConfig);
-        let args: &[ngx_str_t] = (*(*cf).args).as_slice();
-
-        let val = match args[1].to_str() {
-            Ok(s) => s,
-            Err(_) => {
-                ngx_conf_log_error!(NGX_LOG_EMERG, cf, "`curl` argument is not utf-8 encoded");
-                return ngx::core::NGX_CONF_ERROR;
-            }
-        };
-
-        // set default value optionally
-        conf.enable = false;
-
-        if val.len() == 2 && val.eq_ignore_ascii_case("on") {
-            conf.enable = true;
-        } else if val.len() == 3 && val.eq_ignore_ascii_case("off") {
-            conf.enable = false;
-        }
+    let cf = unsafe { core::NgxConfRef::from_ptr_mut(cf) };
+    let conf = unsafe { &mut *(conf as *mut ModuleConfig) };
+
+    let Some(enable) = cf.arg_parse::<bool>(1) else {
+        cf.log_error(
+            NGX_LOG_EMERG,
+            format_args!("`curl` argument must be \"on\" or \"off\""),
+        );
+        return ngx::core::NGX_CONF_ERROR;
     };
 
+    conf.enable = enable;
+
     ngx::core::NGX_CONF_OK
 }
@@ -0,0 +1,617 @@
+//! Tests for the parts of the `ngx` crate that can only run within nginx.
+//!
+//! The `selftest` directive sets a content handler that runs the test named in the `name` request
+//! argument, e.g. `/?name=header_parser`, and responds with `200 OK` or with
+//! `500 Internal Server Error` and the failed check. The tests are driven by
+//! `tests/selftest_test.rs`.
+//!
+//! The `request_body` test reads the request body first and runs once the whole body is read.
+//! The `body_bytes` test reads the request body asynchronously and responds with the error status
+//! if reading fails, e.g. `413 Request Entity Too Large`.
+//! The `request_guard` test responds with `500 Internal Server Error` from the dropped guard.
+//! The asynchronous tests run in a task spawned by the handler.
+use std::alloc::Layout;
+use std::cell::Cell;
+use std::ffi::{c_char, c_void};
+use std::future::{poll_fn, Future};
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::{pin, Pin};
+use std::ptr::NonNull;
+use std::rc::Rc;
+use std::task::Poll;
+use std::time::Duration;
+
+use ngx::allocator::{AllocError, Allocator, Global};
+use ngx::async_::{self, spawn};
+use ngx::collections::{NgxArray, RbTreeMap, SharedDict, Vec};
+use ngx::core::{
+    self, BufRef, Buffer, HashKeys, HashParams, HashTable, MutableBuffer, NgxStr, TemporaryBuffer,
+};
+use ngx::ffi::{
+    ngx_command_t, ngx_conf_t, ngx_http_module_t, ngx_http_read_client_request_body,
+    ngx_http_request_t, ngx_int_t, ngx_module_t, ngx_str_t, NGX_CONF_NOARGS, NGX_HTTP_LOC_CONF,
+    NGX_HTTP_LOC_CONF_OFFSET, NGX_HTTP_MODULE, NGX_HTTP_SPECIAL_RESPONSE,
+};
+use ngx::http::{self, HeaderParser, NgxHttpCoreModule, ParsedHeader, RequestGuard};
+use ngx::{commands, http_request_handler, ngx_string};
+
+commands! {
+    static mut NGX_HTTP_SELFTEST_COMMANDS = [
+        {
+            name: "selftest",
+            type: NGX_HTTP_LOC_CONF | NGX_CONF_NOARGS,
+            set: ngx_http_selftest_set,
+            conf: NGX_HTTP_LOC_CONF_OFFSET,
+            offset: 0,
+        },
+    ];
+}
+
+static NGX_HTTP_SELFTEST_MODULE_CTX: ngx_http_module_t = ngx_http_module_t {
+    preconfiguration: None,
+    postconfiguration: None,
+    create_main_conf: None,
+    init_main_conf: None,
+    create_srv_conf: None,
+    merge_srv_conf: None,
+    create_loc_conf: None,
+    merge_loc_conf: None,
+};
+
+// Generate the `ngx_modules` table with exported modules.
+// This feature is required to build a 'cdylib' dynamic module outside of the NGINX buildsystem.
+#[cfg(feature = "export-modules")]
+ngx::ngx_modules!(ngx_http_selftest_module);
+
+#[used]
+#[allow(non_upper_case_globals)]
+#[cfg_attr(not(feature = "export-modules"), no_mangle)]
+pub static mut ngx_http_selftest_module: ngx_module_t = ngx_module_t {
+    ctx: std::ptr::addr_of!(NGX_HTTP_SELFTEST_MODULE_CTX) as _,
+    commands: unsafe { &NGX_HTTP_SELFTEST_COMMANDS[0] as *const _ as *mut _ },
+    type_: NGX_HTTP_MODULE as _,
+    ..ngx_module_t::default()
+};
+
+extern "C" fn ngx_http_selftest_set(
+    cf: *mut ngx_conf_t,
+    _cmd: *mut ngx_command_t,
+    _conf: *mut c_void,
+) -> *mut c_char {
+    let cf = unsafe { core::NgxConfRef::from_ptr_mut(cf) };
+    match NgxHttpCoreModule::set_conf_content_handler(cf, Some(selftest_handler)) {
+        Some(()) => core::NGX_CONF_OK,
+        None => core::NGX_CONF_ERROR,
+    }
+}
+
+type TestResult = Result<(), String>;
+
+/// Returns from the test with an error describing the failed condition.
+macro_rules! check {
+    ($cond:expr) => {
+        if !$cond {
+            return Err(format!(
+                "{}:{}: check failed: {}",
+                file!(),
+                line!(),
+                stringify!($cond)
+            ));
+        }
+    };
+}
+
+static TESTS: &[(&str, fn(&mut http::Request) -> TestResult)] = &[
+    ("array_growth", test_array_growth),
+    ("buffer_from_vec", test_buffer_from_vec),
+    ("content_type", test_content_type),
+    ("hash", test_hash),
+    ("header_parser", test_header_parser),
+    ("rbtree_drain", test_rbtree_drain),
+    ("rbtree_insert_panic", test_rbtree_insert_panic),
+    ("shared_dict", test_shared_dict),
+    #[cfg(any(ngx_os = "linux", ngx_os = "freebsd"))]
+    ("tcp_nopush", test_tcp_nopush),
+];
+
+type AsyncTestFuture<'a> = Pin<Box<dyn Future<Output = TestResult> + 'a>>;
+
+static ASYNC_TESTS: &[(&str, fn(&mut http::Request) -> AsyncTestFuture<'_>)] = &[
+    ("scheduler_fairness", test_scheduler_fairness),
+    ("sleep_reset", test_sleep_reset),
+];
+
+http_request_handler!(selftest_handler, |request: &mut http::Request| {
+    let name = request
+        .arg("name")
+        .map(|x| x.as_bytes().to_vec())
+        .unwrap_or_default();
+
+    if name == b"request_body" {
+        return read_request_body(request);
+    }
+
+    if name == b"body_bytes" {
+        return read_body_bytes(request);
+    }
+
+    if name == b"request_guard" {
+        return test_request_guard(request);
+    }
+
+    if let Some((_, test)) = ASYNC_TESTS.iter().find(|(n, _)| n.as_bytes() == name) {
+        let mut guard = request.main_count_guard();
+        spawn(async move {
+            let result = test(guard.request()).await;
+            let rc = respond(guard.request(), result);
+            guard.finalize(rc);
+        })
+        .detach();
+        return core::Status::NGX_DONE;
+    }
+
+    let result = match TESTS.iter().find(|(n, _)| n.as_bytes() == name) {
+        Some((_, test)) => test(request),
+        None => Err("unknown test".to_string()),
+    };
+
+    respond(request, result)
+});
+
+fn respond(request: &mut http::Request, result: TestResult) -> core::Status {
+    let (status, body) = match result {
+        Ok(()) => (http::HTTPStatus::OK, "ok\n".to_string()),
+        Err(err) => (http::HTTPStatus::INTERNAL_SERVER_ERROR, err + "\n"),
+    };
+    request.send_response(status, "text/plain", body.as_bytes())
+}
+
+fn test_array_growth(request: &mut http::Request) -> TestResult {
+    const VALUES: [ngx_str_t; 5] = [
+        ngx_string!("zero"),
+        ngx_string!("one"),
+        ngx_string!("two"),
+        ngx_string!("three"),
+        ngx_string!("four"),
+    ];
+
+    let pool = request.pool();
+    let array = NgxArray::<ngx_str_t>::create(pool, 2).map_err(|_| "array allocation failed")?;
+    check!(array.capacity() == 2);
+
+    // the elements are copied to the new storage when the capacity is exhausted
+    for value in VALUES {
+        check!(array.push(value).is_ok());
+    }
+    check!(array.len() == VALUES.len());
+    check!(array.capacity() >= VALUES.len());
+
+    for (elt, value) in array.as_slice().iter().zip(VALUES.iter()) {
+        check!(elt.as_bytes() == value.as_bytes());
+    }
+
+    Ok(())
+}
+
+/// Yields to the scheduler once, rescheduling the current task.
+async fn yield_now() {
+    let mut yielded = false;
+    poll_fn(|cx| {
+        if yielded {
+            return Poll::Ready(());
+        }
+        yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    })
+    .await
+}
+
+/// Checks that a task rescheduling itself does not prevent a timer of another task from firing.
+fn test_scheduler_fairness(_request: &mut http::Request) -> AsyncTestFuture<'_> {
+    const MAX_YIELDS: usize = 100_000;
+
+    Box::pin(async {
+        let fired = Rc::new(Cell::new(false));
+
+        let timer = spawn({
+            let fired = fired.clone();
+            async move {
+                async_::sleep(Duration::from_millis(1)).await;
+                fired.set(true);
+            }
+        });
+
+        let busy = spawn({
+            let fired = fired.clone();
+            async move {
+                let mut yields = 0;
+                while !fired.get() {
+                    check!(yields < MAX_YIELDS);
+                    yields += 1;
+                    yield_now().await;
+                }
+                Ok(())
+            }
+        });
+
+        timer.await;
+        busy.await
+    })
+}
+
+/// Checks that resetting a sleep wakes the waiting task to poll the sleep again.
+fn test_sleep_reset(_request: &mut http::Request) -> AsyncTestFuture<'_> {
+    Box::pin(async {
+        let result = async_::timeout(Duration::from_secs(5), async {
+            let mut sleep = pin!(async_::sleep(Duration::from_secs(3600)));
+            // register the task waker with the timer
+            check!(poll_fn(|cx| Poll::Ready(sleep.as_mut().poll(cx)))
+                .await
+                .is_pending());
+
+            sleep.as_mut().reset(Duration::from_millis(1));
+            check!(!sleep.is_elapsed());
+
+            // wait for the wakeup without polling the sleep, as a combinator might do
+            let mut woken = false;
+            poll_fn(|_| {
+                if woken {
+                    return Poll::Ready(());
+                }
+                woken = true;
+                Poll::Pending
+            })
+            .await;
+
+            sleep.as_mut().await;
+            check!(sleep.is_elapsed());
+            Ok(())
+        })
+        .await;
+
+        result.map_err(|_| "the task was not woken by the reset".to_string())?
+    })
+}
+
+/// Starts reading the request body, running [`test_request_body`] from the post handler.
+fn read_request_body(request: &mut http::Request) -> core::Status {
+    let r: *mut ngx_http_request_t = request.as_mut();
+    let rc = unsafe { ngx_http_read_client_request_body(r, Some(request_body_post_handler)) };
+    if rc >= NGX_HTTP_SPECIAL_RESPONSE as ngx_int_t {
+        return core::Status(rc);
+    }
+    core::Status::NGX_DONE
+}
+
+extern "C" fn request_body_post_handler(r: *mut ngx_http_request_t) {
+    let request = unsafe { http::Request::from_ngx_http_request(r) };
+    let result = test_request_body(request);
+    let rc = respond(request, result);
+    request.finalize(rc);
+}
+
+/// Checks that the request body, generated by `tests/selftest_test.rs` as a repeated alphabet,
+/// is read completely, including the part written to the temporary file.
+fn test_request_body(request: &mut http::Request) -> TestResult {
+    let len = request.content_length().ok_or("no content length")? as usize;
+    let chain = request.request_body().ok_or("no request body")?;
+
+    // the body is larger than `client_body_buffer_size`
+    check!(request.request_body_in_file());
+    check!(chain.in_file());
+
+    let mut body = Vec::new();
+    for buf in chain.iter() {
+        if !buf.in_file() {
+            body.extend_from_slice(buf.as_bytes());
+            continue;
+        }
+
+        // read in small chunks to check the offset handling
+        let start = body.len();
+        let mut chunk = [0u8; 1000];
+        loop {
+            match buf.read_file(body.len() - start, &mut chunk) {
+                Ok(0) => break,
+                Ok(n) => body.extend_from_slice(&chunk[..n]),
+                Err(_) => return Err("reading request body file failed".to_string()),
+            }
+        }
+    }
+
+    check_request_body(&body, len)
+}
+
+fn check_request_body(body: &[u8], len: usize) -> TestResult {
+    check!(body.len() == len);
+    check!(body
+        .iter()
+        .enumerate()
+        .all(|(i, x)| *x == b'a' + (i % 26) as u8));
+    Ok(())
+}
+
+/// Reads the request body with [`http::Request::body_bytes`] in a spawned task.
+fn read_body_bytes(request: &mut http::Request) -> core::Status {
+    let mut guard = request.main_count_guard();
+    spawn(async move {
+        let rc = match guard.request().body_bytes().await {
+            Ok(body) => {
+                let result = match guard.request().content_length() {
+                    Some(len) => check_request_body(&body, len as usize),
+                    None => Err("no content length".to_string()),
+                };
+                respond(guard.request(), result)
+            }
+            // nginx has already responded with the error status
+            Err(err) if err.is_finalized() => core::Status::NGX_DONE,
+            Err(err) => err.status().into(),
+        };
+        guard.finalize(rc);
+    })
+    .detach();
+    core::Status::NGX_DONE
+}
+
+/// Drops an armed guard, which discards the request body and finalizes the request with `500`.
+fn test_request_guard(request: &mut http::Request) -> core::Status {
+    let guard = RequestGuard::new(request.main_count_guard());
+    drop(guard);
+
+    // the handler releases its reference, the guard released the one it owned; a leaked
+    // reference would keep the connection open
+    core::Status::NGX_DONE
+}
+
+fn test_buffer_from_vec(request: &mut http::Request) -> TestResult {
+    let pool = request.pool();
+
+    // an empty vector has no memory to reference
+    let buf = TemporaryBuffer::from_vec(Vec::new_in(pool.clone()))
+        .map_err(|_| "buffer allocation failed")?;
+    check!(buf.is_empty());
+    check!(buf.is_special());
+    check!(buf.as_bytes().is_empty());
+
+    let mut data = Vec::with_capacity_in(16, pool.clone());
+    data.extend_from_slice(b"hello");
+    let mut buf = TemporaryBuffer::from_vec(data).map_err(|_| "buffer allocation failed")?;
+    check!(!buf.is_special());
+    check!(buf.as_bytes() == b"hello");
+
+    buf.append(b", world");
+    check!(buf.as_bytes() == b"hello, world");
+
+    Ok(())
+}
+
+/// Looks up the extensions in the default `types` of the location: `html`, `gif` and `jpg`.
+fn test_content_type(request: &mut http::Request) -> TestResult {
+    // a static, already lowercase extension is looked up in place, without copying
+    let html = request.content_type_for_extension(NgxStr::from_bytes(b"html"));
+    check!(html.map(NgxStr::as_bytes) == Some(b"text/html".as_slice()));
+
+    let gif = request.content_type_for_extension(NgxStr::from_bytes(b"GiF"));
+    check!(gif.map(NgxStr::as_bytes) == Some(b"image/gif".as_slice()));
+
+    check!(request
+        .content_type_for_extension(NgxStr::from_bytes(b"unknown"))
+        .is_none());
+    Ok(())
+}
+
+fn test_hash(request: &mut http::Request) -> TestResult {
+    static VALUES: [u32; 4] = [0, 1, 2, 3];
+    let value = |i: usize| NonNull::from(&VALUES[i]).cast::<c_void>();
+
+    let pool = request.pool();
+    let params = HashParams {
+        name: c"selftest_hash",
+        max_size: 512,
+        bucket_size: 64,
+    };
+
+    // the keys are converted to lowercase, the lookup is case-sensitive
+    let table = HashTable::new(
+        pool,
+        pool,
+        &params,
+        &[
+            (NgxStr::from_bytes(b"Content-Type"), value(0)),
+            (NgxStr::from_bytes(b"host"), value(1)),
+        ],
+    )
+    .map_err(|_| "hash table initialization failed")?;
+    check!(table.find(b"content-type") == Some(value(0)));
+    check!(table.find(b"host") == Some(value(1)));
+    check!(table.find(b"Content-Type").is_none());
+    check!(table.find(b"accept").is_none());
+
+    let mut keys = HashKeys::new(pool, pool, false).map_err(|_| "hash keys allocation failed")?;
+    keys.add(NgxStr::from_bytes(b"Example.COM"), value(0), false)
+        .map_err(|_| "adding exact key failed")?;
+    keys.add(NgxStr::from_bytes(b"*.Example.org"), value(1), true)
+        .map_err(|_| "adding head wildcard failed")?;
+    keys.add(NgxStr::from_bytes(b"www.example.*"), value(2), true)
+        .map_err(|_| "adding tail wildcard failed")?;
+    check!(keys
+        .add(NgxStr::from_bytes(b"example.com"), value(3), false)
+        .is_err());
+
+    let table = keys
+        .build(&params)
+        .map_err(|_| "combined hash initialization failed")?;
+    check!(table.find(b"example.com") == Some(value(0)));
+    check!(table.find(b"www.example.org") == Some(value(1)));
+    check!(table.find(b"www.example.net") == Some(value(2)));
+    check!(table.find(b"example.net").is_none());
+
+    Ok(())
+}
+
+/// Converts a parsed header line into owned values, releasing the buffer borrow.
+fn header(parsed: ParsedHeader<'_>) -> Option<(Vec<u8>, Vec<u8>, bool)> {
+    match parsed {
+        ParsedHeader::Header {
+            name,
+            value,
+            invalid,
+        } => Some((name.as_bytes().to_vec(), value.as_bytes().to_vec(), invalid)),
+        _ => None,
+    }
+}
+
+fn test_header_parser(request: &mut http::Request) -> TestResult {
+    let mut tmp = request
+        .pool()
+        .create_buffer(64)
+        .ok_or("buffer allocation failed")?;
+    tmp.append(b"Host: example.com\r\nX_Foo: bar\r\nX-Par");
+    let buf = unsafe { BufRef::from_ptr_mut(tmp.as_ngx_buf_mut()) };
+
+    let mut parser = HeaderParser::new(request, false);
+    check!(header(parser.parse(buf)) == Some((b"Host".to_vec(), b"example.com".to_vec(), false)));
+    check!(header(parser.parse(buf)) == Some((b"X_Foo".to_vec(), b"bar".to_vec(), true)));
+    check!(matches!(parser.parse(buf), ParsedHeader::Again));
+
+    // a new parser continues the header line split between the reads
+    let mut parser = HeaderParser::new(request, false);
+    tmp.append(b"t: 1\r\n\r\n");
+    let buf = unsafe { BufRef::from_ptr_mut(tmp.as_ngx_buf_mut()) };
+    check!(header(parser.parse(buf)) == Some((b"X-Part".to_vec(), b"1".to_vec(), false)));
+    check!(matches!(parser.parse(buf), ParsedHeader::Done));
+    check!(buf.is_empty());
+
+    Ok(())
+}
+
+/// An allocator failing once the remaining number of allocations drops to zero.
+#[derive(Clone)]
+struct LimitedAlloc(Rc<Cell<usize>>);
+
+unsafe impl Allocator for LimitedAlloc {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let left = self.0.get().checked_sub(1).ok_or(AllocError)?;
+        self.0.set(left);
+        Global.allocate(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        self.0.set(self.0.get().saturating_add(1));
+        Global.deallocate(ptr, layout)
+    }
+}
+
+fn test_rbtree_drain(_request: &mut http::Request) -> TestResult {
+    let value = Rc::new(());
+    let mut map = RbTreeMap::try_new_in(Global).map_err(|_| "tree allocation failed")?;
+
+    for i in 0..10u32 {
+        check!(map.try_insert(i, value.clone()).is_ok());
+    }
+
+    let mut keys: Vec<u32> = map.drain().map(|(k, _)| k).collect();
+    keys.sort_unstable();
+    check!(keys == (0..10).collect::<Vec<_>>());
+    check!(map.is_empty());
+    check!(map.iter().count() == 0);
+    check!(Rc::strong_count(&value) == 1);
+
+    // dropping a partially consumed iterator removes the remaining entries
+    for i in 0..10u32 {
+        check!(map.try_insert(i, value.clone()).is_ok());
+    }
+
+    let mut drain = map.drain();
+    check!(drain.by_ref().take(3).count() == 3);
+    drop(drain);
+    check!(map.is_empty());
+    check!(map.iter().count() == 0);
+    check!(Rc::strong_count(&value) == 1);
+
+    // the tree remains usable
+    check!(map.try_insert(1, value.clone()).is_ok());
+    check!(map.get(&1).is_some());
+    check!(map.len() == 1);
+
+    Ok(())
+}
+
+fn test_rbtree_insert_panic(_request: &mut http::Request) -> TestResult {
+    let budget = Rc::new(Cell::new(usize::MAX));
+    let mut map: RbTreeMap<u32, u32, _> = RbTreeMap::try_new_in(LimitedAlloc(budget.clone()))
+        .map_err(|_| "tree allocation failed")?;
+    let allocated = budget.get();
+
+    // a panic in the value constructor does not leave an allocated entry behind
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let _ = map.get_or_insert_with(1, || panic!("value constructor"));
+    }));
+    check!(result.is_err());
+    check!(budget.get() == allocated);
+    check!(map.is_empty());
+
+    check!(map.get_or_insert_with(1, || 2).is_ok_and(|x| *x == 2));
+    check!(map.len() == 1);
+
+    Ok(())
+}
+
+fn test_shared_dict(_request: &mut http::Request) -> TestResult {
+    let budget = Rc::new(Cell::new(usize::MAX));
+    let mut dict: SharedDict<u32, _> = SharedDict::try_new_in(LimitedAlloc(budget.clone()))
+        .map_err(|_| "dictionary allocation failed")?;
+
+    let ttl = Duration::from_secs(60);
+    check!(dict.insert("a", 1, ttl).is_ok());
+    check!(dict.insert("b", 2, ttl).is_ok());
+
+    // replacing a value reuses the stored key and does not evict anything
+    budget.set(0);
+    check!(dict.insert("a", 3, ttl).is_ok());
+    check!(dict.get("a") == Some(&3));
+    check!(dict.get("b") == Some(&2));
+
+    // an entry with zero ttl expires immediately
+    budget.set(usize::MAX);
+    check!(dict.insert("c", 4, Duration::ZERO).is_ok());
+    check!(dict.get("c").is_none());
+
+    // the expired entry is evicted first
+    budget.set(0);
+    check!(dict.insert("d", 5, ttl).is_ok());
+    check!(dict.get("a") == Some(&3));
+    check!(dict.get("b") == Some(&2));
+    check!(dict.get("d") == Some(&5));
+    check!(dict.iter().count() == 3);
+
+    // the least recently used entry is evicted when nothing is expired
+    check!(dict.insert("e", 6, ttl).is_ok());
+    check!(dict.get("e") == Some(&6));
+    check!(dict.iter().count() == 3);
+
+    check!(dict.expire() == 0);
+    Ok(())
+}
+
+/// Checks the `TCP_CORK` or `TCP_NOPUSH` state changes, with `tcp_nopush on` in the location.
+#[cfg(any(ngx_os = "linux", ngx_os = "freebsd"))]
+fn test_tcp_nopush(request: &mut http::Request) -> TestResult {
+    // `ngx_connection_tcp_nopush_e` values
+    const UNSET: u32 = 0;
+    const SET: u32 = 1;
+
+    let c = request.connection();
+    check!(c.as_ref().tcp_nopush() == UNSET);
+
+    check!(c.tcp_nopush().is_ok());
+    check!(c.as_ref().tcp_nopush() == SET);
+    check!(c.tcp_nopush().is_ok());
+
+    check!(c.tcp_push().is_ok());
+    check!(c.as_ref().tcp_nopush() == UNSET);
+    check!(c.tcp_push().is_ok());
+
+    Ok(())
+}
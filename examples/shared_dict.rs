@@ -11,7 +11,9 @@ use nginx_sys::{
     NGX_HTTP_VAR_NOCACHEABLE, NGX_LOG_EMERG,
 };
 use ngx::collections::RbTreeMap;
-use ngx::core::{NgxStr, NgxString, Pool, SlabPool, Status, NGX_CONF_ERROR, NGX_CONF_OK};
+use ngx::core::{
+    NgxStr, NgxString, Pool, SlabPool, Status, VariableValue, NGX_CONF_ERROR, NGX_CONF_OK,
+};
 use ngx::http::{HttpModule, HttpModuleMainConf};
 use ngx::{ngx_conf_log_error, ngx_log_debug, ngx_string};
 
@@ -198,7 +200,7 @@ extern "C" fn ngx_http_shared_dict_add_variable(
 ) -> *mut c_char {
     // SAFETY: configuration handlers always receive a valid `cf` pointer.
     let cf = unsafe { cf.as_mut().unwrap() };
-    let mut pool = unsafe { Pool::from_ngx_pool(cf.pool) };
+    let pool = unsafe { Pool::from_ngx_pool(cf.pool) };
 
     let key = pool.calloc_type::<ngx_http_complex_value_t>();
     if key.is_null() {
@@ -256,7 +258,7 @@ extern "C" fn ngx_http_shared_dict_get_variable(
     data: usize,
 ) -> ngx_int_t {
     let r = unsafe { &mut *r };
-    let v = unsafe { &mut *v };
+    let v = unsafe { VariableValue::from_ptr_mut(v) };
     let smcf = HttpSharedDictModule::main_conf_mut(r).expect("shared dict main config");
 
     let mut key = ngx_str_t::empty();
@@ -284,17 +286,12 @@ extern "C" fn ngx_http_shared_dict_get_variable(
         unsafe { nginx_sys::ngx_pid },
     );
 
-    let Some(value) = value else {
-        v.set_not_found(1);
+    let Some(value) = value.and_then(VariableValue::from_ngx_str) else {
+        *v = VariableValue::not_found();
         return Status::NGX_ERROR.into();
     };
 
-    v.data = value.data;
-    v.set_len(value.len as _);
-
-    v.set_valid(1);
-    v.set_no_cacheable(0);
-    v.set_not_found(0);
+    *v = value;
 
     Status::NGX_OK.into()
 }
@@ -361,7 +358,7 @@ extern "C" fn ngx_http_shared_dict_get_entries(
     use core::fmt::Write;
 
     let r = unsafe { &mut *r };
-    let v = unsafe { &mut *v };
+    let v = unsafe { VariableValue::from_ptr_mut(v) };
     let pool = unsafe { Pool::from_ngx_pool(r.pool) };
     let smcf = HttpSharedDictModule::main_conf_mut(r).expect("shared dict main config");
 
@@ -406,12 +403,11 @@ extern "C" fn ngx_http_shared_dict_get_entries(
     // The string is allocated on the `ngx_pool_t` and will be freed with the request.
     let (data, len, _, _) = str.into_raw_parts();
 
-    v.data = data;
-    v.set_len(len as _);
+    let Some(value) = VariableValue::from_ngx_str(ngx_str_t { data, len }) else {
+        return Status::NGX_ERROR.into();
+    };
 
-    v.set_valid(1);
-    v.set_no_cacheable(1);
-    v.set_not_found(0);
+    *v = value.no_cacheable();
 
     Status::NGX_OK.into()
 }
@@ -6,7 +6,12 @@ use std::fs::{read_to_string, File};
 use std::io::Write;
 use std::path::{Path, PathBuf};
 
-const ENV_VARS_TRIGGERING_RECOMPILE: &[&str] = &["OUT_DIR", "NGINX_BUILD_DIR", "NGINX_SOURCE_DIR"];
+const ENV_VARS_TRIGGERING_RECOMPILE: &[&str] = &[
+    "OUT_DIR",
+    "NGINX_BUILD_DIR",
+    "NGINX_SOURCE_DIR",
+    "NGX_COMPAT",
+];
 
 /// The feature flags set by the nginx configuration script.
 ///
@@ -56,6 +61,25 @@ const NGX_CONF_OS: &[&str] = &[
     "darwin", "freebsd", "gnu_hurd", "hpux", "linux", "solaris", "tru64", "win32",
 ];
 
+/// The nginx versions with notable changes in the API or structure layout.
+///
+/// For each version not newer than the detected one, a `nginxX_Y_Z` cfg flag will be exposed to
+/// the buildscripts of _direct_ dependents of this crate in the `DEP_NGINX_VERSIONS` environment
+/// variable.
+/// The list of recognized flags will be exported as `DEP_NGINX_VERSIONS_CHECK`.
+const NGX_VERSION_CHECKS: &[(u64, &str)] = &[
+    (1_021_001, "nginx1_21_1"),
+    (1_022_000, "nginx1_22_0"),
+    (1_023_000, "nginx1_23_0"),
+    (1_024_000, "nginx1_24_0"),
+    (1_025_000, "nginx1_25_0"),
+    (1_025_001, "nginx1_25_1"),
+    (1_026_000, "nginx1_26_0"),
+    (1_027_000, "nginx1_27_0"),
+    (1_028_000, "nginx1_28_0"),
+    (1_029_000, "nginx1_29_0"),
+];
+
 type BoxError = Box<dyn StdError>;
 
 /// Function invoked when `cargo build` is executed.
@@ -348,6 +372,8 @@ pub fn print_cargo_metadata<T: AsRef<Path>>(
 
     let mut ngx_features: Vec<String> = vec![];
     let mut ngx_os = String::new();
+    let mut module_signature = String::new();
+    let mut version_number: u64 = 0;
 
     let expanded = expand_definitions(includes, defines)?;
     for line in String::from_utf8(expanded)?.lines() {
@@ -368,6 +394,9 @@ pub fn print_cargo_metadata<T: AsRef<Path>>(
             println!("cargo::metadata=version={}", unquote(value));
         } else if name == "nginx_version_number" {
             println!("cargo::metadata=version_number={value}");
+            version_number = value.parse()?;
+        } else if name == "module_signature" {
+            module_signature = unquote(value);
         } else if NGX_CONF_OS.contains(&name.as_str()) {
             ngx_os = name;
         } else if NGX_CONF_FEATURES.contains(&name.as_str()) && value != "0" {
@@ -375,6 +404,11 @@ pub fn print_cargo_metadata<T: AsRef<Path>>(
         }
     }
 
+    if compat_mode() {
+        check_compat(&ngx_features, &module_signature)?;
+    }
+    println!("cargo::metadata=module_signature={module_signature}");
+
     println!(
         "cargo::metadata=build_dir={}",
         nginx.build_dir.to_str().expect("Unicode build path")
@@ -420,6 +454,55 @@ pub fn print_cargo_metadata<T: AsRef<Path>>(
     println!("cargo::metadata=os={ngx_os}");
     println!("cargo::rustc-cfg=ngx_os=\"{ngx_os}\"");
 
+    // A list of all recognized version flags to be passed to rustc-check-cfg.
+    let values = NGX_VERSION_CHECKS
+        .iter()
+        .map(|x| x.1)
+        .collect::<Vec<_>>()
+        .join(",");
+    println!("cargo::metadata=versions_check={values}");
+    println!("cargo::rustc-check-cfg=cfg({values})");
+    // A list of version flags satisfied by the detected nginx version
+    let versions = NGX_VERSION_CHECKS
+        .iter()
+        .filter(|x| version_number >= x.0)
+        .map(|x| x.1)
+        .collect::<Vec<_>>();
+    println!("cargo::metadata=versions={}", versions.join(","));
+    for version in versions {
+        println!("cargo::rustc-cfg={version}");
+    }
+
+    Ok(())
+}
+
+/// Returns `true` if the `NGX_COMPAT` build mode is requested.
+///
+/// In this mode the module is expected to be loaded into an nginx binary built elsewhere, and
+/// the only supported way to achieve that is the `--with-compat` configuration of both builds.
+fn compat_mode() -> bool {
+    env::var("NGX_COMPAT").is_ok_and(|x| !matches!(x.as_str(), "" | "0" | "false" | "no"))
+}
+
+/// Verifies that the nginx build we are generating bindings for uses the compat layout.
+///
+/// `NGX_COMPAT` reserves space for optional fields in `ngx_event_t`, `ngx_connection_t`,
+/// `ngx_output_chain_ctx_t`, `ngx_http_upstream_conf_t` and other structures, making the layout
+/// independent of the set of modules. The last character of the module signature reflects
+/// the value of `NGX_COMPAT` and is checked by `ngx_load_module` in the host binary.
+fn check_compat(features: &[String], signature: &str) -> Result<(), BoxError> {
+    if !features.iter().any(|x| x == "compat") {
+        return Err("NGX_COMPAT is set, but nginx is not configured with --with-compat".into());
+    }
+
+    if !signature.ends_with('1') {
+        return Err(format!(
+            "NGX_COMPAT is set, but module signature {signature:?} does not have the compat bit \
+             set"
+        )
+        .into());
+    }
+
     Ok(())
 }
 
@@ -455,6 +538,7 @@ RUST_CONF_HTTP=1
 RUST_CONF_NGINX_BUILD=NGINX_VER_BUILD
 RUST_CONF_NGINX_VERSION=NGINX_VER
 RUST_CONF_NGINX_VERSION_NUMBER=nginx_version
+RUST_CONF_MODULE_SIGNATURE=NGX_MODULE_SIGNATURE
 "
     )?;
 
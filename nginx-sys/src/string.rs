@@ -60,6 +60,38 @@ impl ngx_str_t {
         }
     }
 
+    /// Creates an `ngx_str_t` instance referencing a static string.
+    ///
+    /// A trailing nul byte is kept in the memory but not included in the length, allowing to pass
+    /// the data to the functions expecting a C string. The data must never be written to.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the string contains a nul byte anywhere but the last position. The panic is
+    /// reported at compile time when the function is evaluated in a `const` context.
+    pub const fn from_static(s: &'static str) -> Self {
+        let bytes = s.as_bytes();
+        let mut len = bytes.len();
+
+        if len > 0 && bytes[len - 1] == b'\0' {
+            len -= 1;
+        }
+
+        let mut i = 0;
+        while i < len {
+            assert!(
+                bytes[i] != b'\0',
+                "ngx_str_t::from_static: interior nul byte"
+            );
+            i += 1;
+        }
+
+        ngx_str_t {
+            len,
+            data: bytes.as_ptr().cast_mut(),
+        }
+    }
+
     /// Create an `ngx_str_t` instance from a byte slice.
     ///
     /// # Safety
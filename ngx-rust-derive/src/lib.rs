@@ -0,0 +1,131 @@
+//! Derive macros for the [ngx](https://crates.io/crates/ngx) crate.
+//!
+//! The macros are re-exported by `ngx` with the `derive` feature and should be used from there.
+#![warn(missing_docs)]
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Data, DeriveInput, Expr, Index};
+
+/// Derives the `ngx::http::Merge` trait for a configuration struct.
+///
+/// Each field is merged according to its `#[merge(...)]` attribute:
+///
+///  * no attribute: the value is inherited from the previous level if the current value is
+///    unset, as defined by the `ngx::http::MergeField` trait.
+///  * `#[merge(default = EXPR)]`: same as above, and the field is set to `EXPR` if the value is
+///    still unset after inheriting.
+///  * `#[merge(inherit)]`: the field type implements `Merge`, and is merged recursively.
+///  * `#[merge(skip)]`: the field is not merged.
+///
+/// ```ignore
+/// use ngx::http::Merge;
+///
+/// #[derive(Default, Merge)]
+/// struct LocConf {
+///     #[merge(default = Some(false))]
+///     enable: Option<bool>,
+///     #[merge(default = 100)]
+///     max: isize,
+///     upstream: Option<ngx::ffi::ngx_str_t>,
+///     #[merge(skip)]
+///     cache: Option<usize>,
+/// }
+/// ```
+#[proc_macro_derive(Merge, attributes(merge))]
+pub fn derive_merge(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_merge(&input) {
+        Ok(x) => x.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+enum MergeAttr {
+    Unset,
+    Default(Expr),
+    Inherit,
+    Skip,
+}
+
+fn expand_merge(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new(
+            input.span(),
+            "Merge can only be derived for structs",
+        ));
+    };
+
+    let mut stmts = Vec::new();
+
+    for (i, field) in data.fields.iter().enumerate() {
+        let member = match &field.ident {
+            Some(ident) => quote!(#ident),
+            None => {
+                let index = Index::from(i);
+                quote!(#index)
+            }
+        };
+
+        let span = field.ty.span();
+        let stmt = match parse_merge_attr(&field.attrs)? {
+            MergeAttr::Unset => quote_spanned! {span=>
+                ::ngx::http::MergeField::merge_field(&mut self.#member, &prev.#member);
+            },
+            MergeAttr::Default(expr) => quote_spanned! {span=>
+                ::ngx::http::MergeField::merge_field(&mut self.#member, &prev.#member);
+                if ::ngx::http::MergeField::is_unset(&self.#member) {
+                    self.#member = #expr;
+                }
+            },
+            MergeAttr::Inherit => quote_spanned! {span=>
+                ::ngx::http::Merge::merge(&mut self.#member, &prev.#member)?;
+            },
+            MergeAttr::Skip => continue,
+        };
+
+        stmts.push(stmt);
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::ngx::http::Merge for #name #ty_generics #where_clause {
+            fn merge(
+                &mut self,
+                prev: &Self,
+            ) -> ::core::result::Result<(), ::ngx::http::MergeConfigError> {
+                #(#stmts)*
+                ::core::result::Result::Ok(())
+            }
+        }
+    })
+}
+
+fn parse_merge_attr(attrs: &[syn::Attribute]) -> syn::Result<MergeAttr> {
+    let mut result = MergeAttr::Unset;
+
+    for attr in attrs.iter().filter(|a| a.path().is_ident("merge")) {
+        attr.parse_nested_meta(|meta| {
+            if !matches!(result, MergeAttr::Unset) {
+                return Err(meta.error("duplicate merge attribute"));
+            }
+
+            if meta.path.is_ident("default") {
+                result = MergeAttr::Default(meta.value()?.parse()?);
+            } else if meta.path.is_ident("inherit") {
+                result = MergeAttr::Inherit;
+            } else if meta.path.is_ident("skip") {
+                result = MergeAttr::Skip;
+            } else {
+                return Err(meta.error("expected `default`, `inherit` or `skip`"));
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(result)
+}
@@ -17,7 +17,7 @@ pub use allocator_api2::alloc::{AllocError, Allocator};
 pub use allocator_api2::{alloc::Global, boxed::Box};
 
 /// Explicitly duplicate an object using the specified Allocator.
-pub trait TryCloneIn: Sized {
+pub trait TryCloneIn {
     /// Target type, generic over an allocator.
     type Target<A: Allocator + Clone>;
 
@@ -25,6 +25,28 @@ pub trait TryCloneIn: Sized {
     fn try_clone_in<A: Allocator + Clone>(&self, alloc: A) -> Result<Self::Target<A>, AllocError>;
 }
 
+macro_rules! impl_try_clone_in_copy {
+    ($($t:ty),+ $(,)?) => {
+        $(
+            impl TryCloneIn for $t {
+                type Target<A: Allocator + Clone> = $t;
+
+                #[inline]
+                fn try_clone_in<A: Allocator + Clone>(
+                    &self,
+                    _alloc: A,
+                ) -> Result<Self::Target<A>, AllocError> {
+                    Ok(*self)
+                }
+            }
+        )+
+    };
+}
+
+impl_try_clone_in_copy!(
+    bool, char, i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64,
+);
+
 /// Moves `value` to the memory backed by `alloc` and returns a pointer.
 ///
 /// This should be similar to `Box::into_raw(Box::try_new_in(value, alloc)?)`, except without
@@ -62,6 +84,7 @@ pub(crate) const fn dangling_for_layout(layout: &Layout) -> NonNull<u8> {
 #[cfg(feature = "alloc")]
 mod impls {
     use allocator_api2::boxed::Box;
+    use allocator_api2::vec::Vec;
 
     use super::*;
 
@@ -80,6 +103,29 @@ mod impls {
             Box::try_new_in(x, alloc)
         }
     }
+
+    impl<T, OA> TryCloneIn for Vec<T, OA>
+    where
+        T: TryCloneIn,
+        OA: Allocator,
+    {
+        type Target<A: Allocator + Clone> = Vec<<T as TryCloneIn>::Target<A>, A>;
+
+        fn try_clone_in<A: Allocator + Clone>(
+            &self,
+            alloc: A,
+        ) -> Result<Self::Target<A>, AllocError> {
+            let mut v = Vec::new_in(alloc.clone());
+            v.try_reserve_exact(self.len()).map_err(|_| AllocError)?;
+
+            for x in self.iter() {
+                // Cannot reallocate, as we reserved the exact capacity above
+                v.push(x.try_clone_in(alloc.clone())?);
+            }
+
+            Ok(v)
+        }
+    }
 }
 
 /// Allows turning a [`Box<T: Sized, A>`][Box] into a [`Box<U: ?Sized, A>`][Box] where `T` can be
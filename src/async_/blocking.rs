@@ -0,0 +1,181 @@
+use core::ffi::c_void;
+use core::future::Future;
+use core::mem;
+use core::pin::Pin;
+use core::ptr::{self, NonNull};
+use core::task::{self, Poll};
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::boxed::Box;
+#[cfg(feature = "std")]
+use std::boxed::Box;
+
+use nginx_sys::{
+    ngx_event_t, ngx_int_t, ngx_log_t, ngx_thread_task_post, ngx_thread_task_t, NGX_OK,
+};
+
+use crate::core::{Status, ThreadPool};
+use crate::log::ngx_cycle_log;
+use crate::ngx_log_debug;
+
+/// Runs a blocking function on a thread from the nginx thread pool.
+///
+/// The function is queued to the pool on the first poll of the returned future, and the future
+/// is woken from the event loop when the function completes. The task is posted to the pool with
+/// `ngx_thread_task_post`, thus a queue overflow is reported as `NGX_ERROR`.
+///
+/// Dropping the future does not cancel a queued or running function, but its result is
+/// discarded. A panic in the function aborts the process.
+///
+/// # Safety
+///
+/// `pool` must remain valid until the function completes, i.e. for the lifetime of the cycle.
+pub unsafe fn spawn_blocking<F, T>(pool: &ThreadPool, f: F) -> SpawnBlocking<F, T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let inner = Box::new(BlockingTask {
+        // SAFETY: an all-zeroes task is a valid initial state
+        task: unsafe { mem::zeroed() },
+        func: Some(f),
+        result: None,
+        waker: None,
+        state: TaskState::Idle,
+    });
+
+    SpawnBlocking {
+        pool: NonNull::from(pool),
+        inner: NonNull::from(Box::leak(inner)),
+    }
+}
+
+/// Future returned by [spawn_blocking].
+pub struct SpawnBlocking<F, T> {
+    pool: NonNull<ThreadPool>,
+    // Owned by the future, unless the task is queued and the future is dropped.
+    inner: NonNull<BlockingTask<F, T>>,
+}
+
+struct BlockingTask<F, T> {
+    task: ngx_thread_task_t,
+    func: Option<F>,
+    result: Option<T>,
+    waker: Option<task::Waker>,
+    state: TaskState,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TaskState {
+    Idle,
+    Queued,
+    Detached,
+    Completed,
+}
+
+impl<F, T> Future for SpawnBlocking<F, T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    type Output = Result<T, Status>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        // The task state is accessed via the field projections, as the pool thread may
+        // concurrently access `func` and `result` of the queued task.
+        let inner = self.inner.as_ptr();
+
+        // SAFETY: the fields other than `func` and `result` are only accessed from the event loop
+        // thread, and the latter are not accessed by the pool thread after completion.
+        unsafe {
+            match (*inner).state {
+                TaskState::Completed => {
+                    return Poll::Ready((*inner).result.take().ok_or(Status::NGX_ERROR));
+                }
+                TaskState::Queued => {
+                    match (*inner).waker {
+                        Some(ref mut waker) => waker.clone_from(cx.waker()),
+                        None => (*inner).waker = Some(cx.waker().clone()),
+                    }
+                    return Poll::Pending;
+                }
+                TaskState::Idle => {}
+                TaskState::Detached => unreachable!(),
+            }
+
+            let log = ngx_cycle_log().as_ptr();
+            let task = ptr::addr_of_mut!((*inner).task);
+
+            (*task).ctx = inner.cast();
+            (*task).handler = Some(blocking_task_handler::<F, T>);
+            (*task).event.data = inner.cast();
+            (*task).event.handler = Some(blocking_task_event_handler::<F, T>);
+            (*task).event.log = log;
+            (*inner).waker = Some(cx.waker().clone());
+
+            ngx_log_debug!(log, "async: spawn blocking task");
+
+            // The task is not freed until the completion event handler is called.
+            if ngx_thread_task_post(self.pool.as_ptr().cast(), task) != NGX_OK as ngx_int_t {
+                return Poll::Ready(Err(Status::NGX_ERROR));
+            }
+
+            (*inner).state = TaskState::Queued;
+        }
+
+        Poll::Pending
+    }
+}
+
+impl<F, T> Drop for SpawnBlocking<F, T> {
+    fn drop(&mut self) {
+        let inner = self.inner.as_ptr();
+
+        // SAFETY: the future owns the task state unless it is queued.
+        unsafe {
+            if (*inner).state == TaskState::Queued {
+                // The completion event handler frees the state.
+                (*inner).state = TaskState::Detached;
+                (*inner).waker = None;
+            } else {
+                drop(Box::from_raw(inner));
+            }
+        }
+    }
+}
+
+/// Runs the function on a pool thread.
+extern "C" fn blocking_task_handler<F, T>(data: *mut c_void, _log: *mut ngx_log_t)
+where
+    F: FnOnce() -> T,
+{
+    let inner = data.cast::<BlockingTask<F, T>>();
+
+    // SAFETY: `data` points to the queued task state. The event loop thread does not access
+    // `func` and `result` until the task is completed.
+    unsafe {
+        if let Some(func) = (*inner).func.take() {
+            (*inner).result = Some(func());
+        }
+    }
+}
+
+/// Completes the task on the event loop thread.
+extern "C" fn blocking_task_event_handler<F, T>(ev: *mut ngx_event_t) {
+    // SAFETY: the event data points to the queued task state.
+    // The pool thread has finished with the task at this point.
+    let inner = unsafe { &mut *(*ev).data.cast::<BlockingTask<F, T>>() };
+
+    ngx_log_debug!(inner.task.event.log, "async: blocking task completed");
+
+    if inner.state == TaskState::Detached {
+        // SAFETY: the future was dropped, and the state is no longer referenced.
+        drop(unsafe { Box::from_raw(inner) });
+        return;
+    }
+
+    inner.state = TaskState::Completed;
+    if let Some(waker) = inner.waker.take() {
+        waker.wake();
+    }
+}
@@ -0,0 +1,252 @@
+use core::ffi::c_int;
+use core::future::Future;
+use core::mem;
+use core::pin::Pin;
+use core::ptr::{self, NonNull};
+use core::task::{self, Poll};
+use core::time::Duration;
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::boxed::Box;
+#[cfg(feature = "std")]
+use std::boxed::Box;
+
+use nginx_sys::{
+    getsockopt, ngx_add_timer, ngx_addr_t, ngx_close_connection, ngx_connection_t, ngx_del_timer,
+    ngx_event_connect_peer, ngx_event_get_peer, ngx_event_t, ngx_log_t, ngx_msec_t,
+    ngx_peer_connection_t, ngx_str_t, socklen_t, SOL_SOCKET, SO_ERROR,
+};
+
+use crate::core::{Connection, NgxStr, Status, NGX_TIMER_DURATION_MAX};
+use crate::ngx_log_debug;
+
+/// Establishes a connection to the first reachable address from the list.
+///
+/// The addresses are tried in order: if the connection to an address fails or is not established
+/// within the `timeout`, the next address is tried. The index of the connected address is
+/// available via [`PeerConnection::addr_index`]. `NGX_ERROR` is returned when all addresses
+/// fail, or the list is empty.
+///
+/// The list is usually obtained from a [`Url`](crate::core::Url) or a resolver response.
+///
+/// # Safety
+///
+/// `log` must be a valid log pointer that outlives the returned future and the connection.
+pub unsafe fn connect(
+    addrs: &[ngx_addr_t],
+    timeout: Duration,
+    log: NonNull<ngx_log_t>,
+) -> Connect<'_> {
+    Connect {
+        addrs,
+        index: 0,
+        timeout,
+        log,
+        inner: None,
+    }
+}
+
+/// Future returned by [connect].
+pub struct Connect<'a> {
+    addrs: &'a [ngx_addr_t],
+    index: usize,
+    timeout: Duration,
+    log: NonNull<ngx_log_t>,
+    // Boxed to keep the address referenced by the connection stable.
+    inner: Option<Box<PeerState>>,
+}
+
+struct PeerState {
+    pc: ngx_peer_connection_t,
+    // The address text is allocated with the address, the struct is copied to outlive the list.
+    name: ngx_str_t,
+    waker: Option<task::Waker>,
+}
+
+impl PeerState {
+    fn close(&mut self) {
+        if !self.pc.connection.is_null() {
+            unsafe { ngx_close_connection(self.pc.connection) };
+            self.pc.connection = ptr::null_mut();
+        }
+    }
+}
+
+impl Drop for PeerState {
+    fn drop(&mut self) {
+        self.close()
+    }
+}
+
+impl Connect<'_> {
+    /// Starts a connection to the current address.
+    ///
+    /// Returns the connection state, or `None` if the attempt failed immediately.
+    fn start(&mut self) -> Option<Box<PeerState>> {
+        let addr = &self.addrs[self.index];
+
+        let mut state = Box::new(PeerState {
+            // SAFETY: an all-zeroes `ngx_peer_connection_t` is a valid initial state
+            pc: unsafe { mem::zeroed() },
+            name: addr.name,
+            waker: None,
+        });
+
+        state.pc.sockaddr = addr.sockaddr;
+        state.pc.socklen = addr.socklen;
+        state.pc.name = ptr::from_mut(&mut state.name);
+        state.pc.get = Some(ngx_event_get_peer);
+        state.pc.log = self.log.as_ptr();
+        state.pc.set_log_error(1); // NGX_ERROR_ERR
+
+        ngx_log_debug!(self.log.as_ptr(), "async: connect to {}", unsafe {
+            NgxStr::from_ngx_str(addr.name)
+        });
+
+        // The connection is closed and reset on error.
+        let rc = Status(unsafe { ngx_event_connect_peer(&mut state.pc) });
+        if rc != Status::NGX_OK && rc != Status::NGX_AGAIN {
+            return None;
+        }
+
+        let c = state.pc.connection;
+        // SAFETY: the connection is valid after a successful or pending connect
+        unsafe {
+            (*c).data = ptr::from_mut::<PeerState>(&mut *state).cast();
+            (*(*c).read).handler = Some(connect_handler);
+            (*(*c).write).handler = Some(connect_handler);
+
+            if rc == Status::NGX_AGAIN {
+                let msec = self.timeout.min(NGX_TIMER_DURATION_MAX).as_millis() as ngx_msec_t;
+                ngx_add_timer((*c).write, msec);
+            }
+        }
+
+        Some(state)
+    }
+}
+
+impl Future for Connect<'_> {
+    type Output = Result<PeerConnection, Status>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+
+        loop {
+            if let Some(state) = this.inner.as_mut() {
+                // SAFETY: the connection is valid while the state exists
+                let wev = unsafe { &mut *(*state.pc.connection).write };
+
+                if wev.timedout() != 0 {
+                    ngx_log_debug!(this.log.as_ptr(), "async: connect timed out");
+                    this.inner = None;
+                    this.index += 1;
+                    continue;
+                }
+
+                if wev.ready() == 0 {
+                    match state.waker {
+                        Some(ref mut waker) => waker.clone_from(cx.waker()),
+                        None => state.waker = Some(cx.waker().clone()),
+                    }
+                    return Poll::Pending;
+                }
+
+                if wev.timer_set() != 0 {
+                    unsafe { ngx_del_timer(wev) };
+                }
+
+                if !test_connect(unsafe { &*state.pc.connection }) {
+                    ngx_log_debug!(this.log.as_ptr(), "async: connect failed");
+                    this.inner = None;
+                    this.index += 1;
+                    continue;
+                }
+
+                let state = this.inner.take().unwrap();
+                return Poll::Ready(Ok(PeerConnection {
+                    state,
+                    index: this.index,
+                }));
+            }
+
+            if this.index >= this.addrs.len() {
+                return Poll::Ready(Err(Status::NGX_ERROR));
+            }
+
+            match this.start() {
+                Some(state) => this.inner = Some(state),
+                None => this.index += 1,
+            }
+        }
+    }
+}
+
+/// An outgoing connection established with [connect].
+///
+/// The connection is closed on drop. It has no memory pool and uses the log passed to
+/// [connect]. The event handlers only wake the connecting task, and should be replaced by the
+/// owner of the connection, e.g. with `TokioConnection`.
+pub struct PeerConnection {
+    state: Box<PeerState>,
+    index: usize,
+}
+
+impl PeerConnection {
+    /// Returns the index of the connected address in the list passed to [connect].
+    pub fn addr_index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the text representation of the connected address.
+    pub fn name(&self) -> &NgxStr {
+        unsafe { NgxStr::from_ngx_str(self.state.name) }
+    }
+
+    /// Returns a reference to the underlying connection.
+    pub fn connection(&self) -> &Connection {
+        unsafe { Connection::from_ptr(self.state.pc.connection) }
+    }
+
+    /// Returns a mutable reference to the underlying connection.
+    pub fn connection_mut(&mut self) -> &mut Connection {
+        unsafe { Connection::from_ptr_mut(self.state.pc.connection) }
+    }
+
+    /// Returns a raw pointer to the peer connection.
+    pub fn as_ptr(&self) -> *mut ngx_peer_connection_t {
+        ptr::from_ref(&self.state.pc).cast_mut()
+    }
+}
+
+/// Checks the result of a non-blocking connect.
+fn test_connect(c: &ngx_connection_t) -> bool {
+    let mut err: c_int = 0;
+    let mut len = mem::size_of::<c_int>() as socklen_t;
+
+    let rc = unsafe {
+        getsockopt(
+            c.fd,
+            SOL_SOCKET as c_int,
+            SO_ERROR as c_int,
+            ptr::from_mut(&mut err).cast(),
+            &mut len,
+        )
+    };
+
+    rc != -1 && err == 0
+}
+
+extern "C" fn connect_handler(ev: *mut ngx_event_t) {
+    // SAFETY: the event belongs to a connection with the `data` field set by `Connect`.
+    let state = unsafe {
+        let c: *mut ngx_connection_t = (*ev).data.cast();
+        &mut *(*c).data.cast::<PeerState>()
+    };
+
+    ngx_log_debug!(unsafe { (*ev).log }, "async: connect event");
+
+    if let Some(waker) = state.waker.take() {
+        waker.wake();
+    }
+}
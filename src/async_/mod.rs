@@ -1,6 +1,20 @@
 //! Async runtime and set of utilities on top of the NGINX event loop.
+#[cfg(ngx_feature = "threads")]
+pub use self::blocking::{spawn_blocking, SpawnBlocking};
+pub use self::connect::{connect, Connect, PeerConnection};
+pub use self::resolver::{resolve, Resolve};
 pub use self::sleep::{sleep, Sleep};
-pub use self::spawn::{spawn, Task};
+pub use self::spawn::{set_scheduler_batch_size, spawn, Task, DEFAULT_SCHEDULER_BATCH_SIZE};
+pub use self::timeout::{timeout, Elapsed, Timeout};
+#[cfg(feature = "tokio")]
+pub use self::tokio_io::TokioConnection;
 
+#[cfg(ngx_feature = "threads")]
+mod blocking;
+mod connect;
+mod resolver;
 mod sleep;
 mod spawn;
+mod timeout;
+#[cfg(feature = "tokio")]
+mod tokio_io;
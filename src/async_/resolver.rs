@@ -0,0 +1,169 @@
+use core::future::Future;
+use core::net::SocketAddr;
+use core::pin::Pin;
+use core::ptr::{self, NonNull};
+use core::task::{self, Poll};
+use core::time::Duration;
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::{boxed::Box, vec::Vec};
+#[cfg(feature = "std")]
+use std::{boxed::Box, vec::Vec};
+
+use nginx_sys::{
+    ngx_int_t, ngx_msec_t, ngx_resolve_name, ngx_resolve_name_done, ngx_resolve_start,
+    ngx_resolver_ctx_t, ngx_resolver_t, ngx_str_t, NGX_OK,
+};
+
+use crate::core::{sockaddr_to_socket_addr, NgxStr, Status, NGX_TIMER_DURATION_MAX};
+use crate::ngx_log_debug;
+
+/// Resolves a host name to a list of addresses using the specified nginx resolver.
+///
+/// The `timeout` is clamped to the maximum duration supported by the nginx timers.
+/// Resolution errors are reported as the resolver state: a DNS response code, `NGX_ERROR` or
+/// `NGX_RESOLVE_TIMEDOUT`. `NGX_DECLINED` is returned when the resolver has no name servers
+/// configured.
+///
+/// The resolver is usually obtained from the `ngx_http_core_loc_conf_t.resolver` field.
+///
+/// # Safety
+///
+/// `resolver` must be a valid resolver pointer that outlives the returned future.
+pub unsafe fn resolve(
+    name: impl AsRef<[u8]>,
+    resolver: NonNull<ngx_resolver_t>,
+    timeout: Duration,
+) -> Resolve {
+    let name: Vec<u8> = name.as_ref().into();
+    ngx_log_debug!(
+        (*resolver.as_ptr()).log,
+        "async: resolve \"{}\"",
+        NgxStr::from_bytes(&name)
+    );
+    Resolve {
+        resolver,
+        name,
+        timeout,
+        inner: None,
+    }
+}
+
+/// Future returned by [resolve].
+pub struct Resolve {
+    resolver: NonNull<ngx_resolver_t>,
+    name: Vec<u8>,
+    timeout: Duration,
+    // Boxed to keep the address referenced by the resolver context stable.
+    inner: Option<Box<ResolveInner>>,
+}
+
+struct ResolveInner {
+    ctx: *mut ngx_resolver_ctx_t,
+    waker: Option<task::Waker>,
+    result: Option<Result<Vec<SocketAddr>, Status>>,
+}
+
+impl Future for Resolve {
+    type Output = Result<Vec<SocketAddr>, Status>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+
+        if let Some(inner) = this.inner.as_mut() {
+            if let Some(result) = inner.result.take() {
+                return Poll::Ready(result);
+            }
+
+            if let Some(waker) = inner.waker.as_mut() {
+                waker.clone_from(cx.waker());
+            } else {
+                inner.waker = Some(cx.waker().clone());
+            }
+
+            return Poll::Pending;
+        }
+
+        let inner = this.inner.insert(Box::new(ResolveInner {
+            ctx: ptr::null_mut(),
+            waker: Some(cx.waker().clone()),
+            result: None,
+        }));
+
+        let ctx = unsafe { ngx_resolve_start(this.resolver.as_ptr(), ptr::null_mut()) };
+        if ctx.is_null() {
+            return Poll::Ready(Err(Status::NGX_ERROR));
+        }
+        // NGX_NO_RESOLVER
+        if ctx as isize == -1 {
+            return Poll::Ready(Err(Status::NGX_DECLINED));
+        }
+
+        let msec = this.timeout.min(NGX_TIMER_DURATION_MAX).as_millis() as ngx_msec_t;
+
+        // SAFETY: `ctx` is a valid resolver context. The name buffer and the boxed state outlive
+        // the context, as we always complete or cancel resolution before dropping them.
+        unsafe {
+            (*ctx).name = ngx_str_t {
+                data: this.name.as_mut_ptr(),
+                len: this.name.len(),
+            };
+            (*ctx).handler = Some(resolve_handler);
+            (*ctx).data = ptr::from_mut::<ResolveInner>(&mut **inner).cast();
+            (*ctx).timeout = msec;
+
+            inner.ctx = ctx;
+
+            // The context is freed on error
+            if ngx_resolve_name(ctx) != NGX_OK as ngx_int_t {
+                inner.ctx = ptr::null_mut();
+                return Poll::Ready(Err(Status::NGX_ERROR));
+            }
+        }
+
+        // The handler can be invoked synchronously for cached names
+        match inner.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl Drop for Resolve {
+    fn drop(&mut self) {
+        if let Some(inner) = self.inner.as_mut() {
+            if !inner.ctx.is_null() {
+                // Cancels the pending resolution
+                unsafe { ngx_resolve_name_done(inner.ctx) };
+                inner.ctx = ptr::null_mut();
+            }
+        }
+    }
+}
+
+unsafe extern "C" fn resolve_handler(ctx: *mut ngx_resolver_ctx_t) {
+    let inner = &mut *(*ctx).data.cast::<ResolveInner>();
+
+    let result = if (*ctx).state == NGX_OK as ngx_int_t {
+        let addrs = if (*ctx).naddrs > 0 {
+            core::slice::from_raw_parts((*ctx).addrs, (*ctx).naddrs)
+        } else {
+            &[]
+        };
+
+        Ok(addrs
+            .iter()
+            .filter_map(|a| sockaddr_to_socket_addr(a.sockaddr, a.socklen))
+            .collect())
+    } else {
+        Err(Status((*ctx).state))
+    };
+
+    ngx_resolve_name_done(ctx);
+    inner.ctx = ptr::null_mut();
+    inner.result = Some(result);
+
+    if let Some(waker) = inner.waker.take() {
+        waker.wake();
+    }
+}
@@ -5,14 +5,12 @@ use core::ptr::{self, NonNull};
 use core::task::{self, Poll};
 use core::time::Duration;
 
-use nginx_sys::{ngx_add_timer, ngx_del_timer, ngx_event_t, ngx_log_t, ngx_msec_int_t, ngx_msec_t};
+use nginx_sys::{ngx_add_timer, ngx_del_timer, ngx_event_t, ngx_log_t, ngx_msec_t};
 use pin_project_lite::pin_project;
 
+use crate::core::NGX_TIMER_DURATION_MAX;
 use crate::{ngx_container_of, ngx_log_debug};
 
-/// Maximum duration that can be achieved using [ngx_add_timer].
-const NGX_TIMER_DURATION_MAX: Duration = Duration::from_millis(ngx_msec_int_t::MAX as _);
-
 /// Puts the current task to sleep for at least the specified amount of time.
 ///
 /// The function is a shorthand for [Sleep::new] using the global logger for debug output.
@@ -23,6 +21,9 @@ pub fn sleep(duration: Duration) -> Sleep {
 
 pin_project! {
 /// Future returned by [sleep].
+///
+/// The future is neither `Send` nor `Sync`, as the timer is registered in the event loop of the
+/// current worker thread.
 pub struct Sleep {
     #[pin]
     timer: TimerEvent,
@@ -37,6 +38,30 @@ impl Sleep {
         ngx_log_debug!(timer.event.log, "async: sleep for {duration:?}");
         Sleep { timer, duration }
     }
+
+    /// Resets the sleep to expire after `duration`, reusing the timer event.
+    ///
+    /// The pending timer is removed, and the new one is set on the next poll. The task waiting
+    /// for the sleep is woken to poll it again.
+    pub fn reset(self: Pin<&mut Self>, duration: Duration) {
+        let this = self.project();
+        this.timer.reset();
+        *this.duration = duration;
+    }
+
+    /// Returns `true` if the sleep duration has elapsed.
+    #[cfg(not(target_pointer_width = "32"))]
+    pub fn is_elapsed(&self) -> bool {
+        self.timer.event.timedout() != 0
+    }
+
+    /// Returns `true` if the sleep duration has elapsed.
+    #[cfg(target_pointer_width = "32")]
+    pub fn is_elapsed(&self) -> bool {
+        // Long durations are completed in several steps, see `poll`.
+        self.duration.is_zero()
+            || (self.timer.event.timedout() != 0 && self.duration <= NGX_TIMER_DURATION_MAX)
+    }
 }
 
 impl Future for Sleep {
@@ -76,10 +101,6 @@ struct TimerEvent {
     waker: Option<task::Waker>,
 }
 
-// SAFETY: Timer will only be used in a single-threaded environment
-unsafe impl Send for TimerEvent {}
-unsafe impl Sync for TimerEvent {}
-
 impl TimerEvent {
     pub fn new(log: NonNull<ngx_log_t>) -> Self {
         static IDENT: [usize; 4] = [
@@ -120,6 +141,20 @@ impl TimerEvent {
         }
     }
 
+    /// Removes the pending timer and clears the expiration flag.
+    ///
+    /// The waiting task is woken, as it would not be notified by the removed timer otherwise.
+    pub fn reset(mut self: Pin<&mut Self>) {
+        if self.event.timer_set() != 0 {
+            unsafe { ngx_del_timer(ptr::addr_of_mut!(self.event)) };
+        }
+        self.event.set_timedout(0);
+
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
     unsafe extern "C" fn timer_handler(ev: *mut ngx_event_t) {
         let timer = ngx_container_of!(ev, Self, event);
 
@@ -1,6 +1,7 @@
 use core::cell::UnsafeCell;
 use core::future::Future;
 use core::mem;
+use core::num::NonZeroUsize;
 use core::ptr::{self, NonNull};
 
 #[cfg(all(not(feature = "std"), feature = "alloc"))]
@@ -19,6 +20,19 @@ use crate::{ngx_container_of, ngx_log_debug};
 
 static SCHEDULER: Scheduler = Scheduler::new();
 
+/// Default maximum number of tasks processed by the scheduler in a single event loop iteration.
+pub const DEFAULT_SCHEDULER_BATCH_SIZE: usize = 64;
+
+/// Sets the maximum number of tasks processed by the scheduler in a single event loop iteration.
+///
+/// The remaining tasks are deferred to the next iteration, letting the worker process the other
+/// events in between. The default is [DEFAULT_SCHEDULER_BATCH_SIZE].
+pub fn set_scheduler_batch_size(n: NonZeroUsize) {
+    // SAFETY: we have exclusive access due to being a single-threaded application.
+    let inner = unsafe { &mut *UnsafeCell::raw_get(&SCHEDULER.0) };
+    inner.batch_size = n.get();
+}
+
 struct Scheduler(UnsafeCell<SchedulerInner>);
 
 // SAFETY: Scheduler must only be used from the main thread of a worker process.
@@ -43,6 +57,7 @@ struct SchedulerInner {
     _ident: [usize; 4], // `ngx_event_ident` compatibility
     event: ngx_event_t,
     queue: VecDeque<Runnable>,
+    batch_size: usize,
 }
 
 impl SchedulerInner {
@@ -56,6 +71,7 @@ impl SchedulerInner {
             ],
             event,
             queue: VecDeque::new(),
+            batch_size: DEFAULT_SCHEDULER_BATCH_SIZE,
         })
     }
 
@@ -94,7 +110,8 @@ impl SchedulerInner {
 
             ngx_log_debug!(
                 this.event.log,
-                "async: processing {} deferred wakeups",
+                "async: processing {} of {} deferred wakeups",
+                this.queue.len().min(this.batch_size),
                 this.queue.len()
             );
 
@@ -102,7 +119,16 @@ impl SchedulerInner {
             // processing to already queued wakeups. This ensures that we correctly handle tasks
             // that keep scheduling themselves (e.g. using yield_now() in a loop).
             // We can't use drain() as it borrows from self and breaks aliasing rules.
-            mem::take(&mut this.queue)
+            let batch = take_batch(&mut this.queue, this.batch_size);
+
+            // Return to the event loop before processing the rest of the queue.
+            if !this.queue.is_empty() {
+                unsafe {
+                    ngx_post_event(&mut this.event, ptr::addr_of_mut!(ngx_posted_next_events))
+                }
+            }
+
+            batch
         };
 
         for runnable in runnables.drain(..) {
@@ -123,6 +149,16 @@ impl Drop for SchedulerInner {
     }
 }
 
+/// Removes up to `n` elements from the front of the queue.
+fn take_batch<T>(queue: &mut VecDeque<T>, n: usize) -> VecDeque<T> {
+    if queue.len() > n {
+        let rest = queue.split_off(n);
+        mem::replace(queue, rest)
+    } else {
+        mem::take(queue)
+    }
+}
+
 fn schedule(runnable: Runnable, info: ScheduleInfo) {
     if info.woken_while_running {
         SCHEDULER.schedule(runnable);
@@ -136,6 +172,13 @@ fn schedule(runnable: Runnable, info: ScheduleInfo) {
 }
 
 /// Creates a new task running on the NGINX event loop.
+///
+/// The task is polled for the first time before the function returns. Spawning cannot fail, but
+/// it must happen on the event loop thread of an initialized nginx process, e.g. from a request
+/// handler or an event handler.
+///
+/// Dropping the returned [`Task`] cancels it; use [`Task::detach`] to let the task run to
+/// completion in the background.
 pub fn spawn<F, T>(future: F) -> Task<T>
 where
     F: Future<Output = T> + 'static,
@@ -149,3 +192,21 @@ where
     runnable.schedule();
     task
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_batch() {
+        let mut queue: VecDeque<u32> = (0..5).collect();
+
+        let batch = take_batch(&mut queue, 3);
+        assert_eq!(batch, [0, 1, 2]);
+        assert_eq!(queue, [3, 4]);
+
+        let batch = take_batch(&mut queue, 3);
+        assert_eq!(batch, [3, 4]);
+        assert!(queue.is_empty());
+    }
+}
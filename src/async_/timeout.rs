@@ -0,0 +1,75 @@
+use core::error;
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{self, Poll};
+use core::time::Duration;
+
+use pin_project_lite::pin_project;
+
+use crate::async_::{sleep, Sleep};
+
+/// Requires a future to complete before the specified duration has elapsed.
+///
+/// The deadline is tracked with an nginx timer, see [sleep]. The future is polled first, so a
+/// future that is ready is not cancelled even if the deadline has passed. If the deadline elapses
+/// first, the future is dropped along with the returned [Timeout]; the timer is removed when the
+/// [Timeout] is dropped.
+#[inline]
+pub fn timeout<F: Future>(duration: Duration, future: F) -> Timeout<F> {
+    Timeout {
+        future,
+        sleep: sleep(duration),
+    }
+}
+
+pin_project! {
+/// Future returned by [timeout].
+pub struct Timeout<F> {
+    #[pin]
+    future: F,
+    #[pin]
+    sleep: Sleep,
+}
+}
+
+impl<F> Timeout<F> {
+    /// Returns a reference to the inner future.
+    pub fn get_ref(&self) -> &F {
+        &self.future
+    }
+
+    /// Consumes the `Timeout`, returning the inner future.
+    pub fn into_inner(self) -> F {
+        self.future
+    }
+}
+
+impl<F: Future> Future for Timeout<F> {
+    type Output = Result<F::Output, Elapsed>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+
+        if let Poll::Ready(output) = this.future.poll(cx) {
+            return Poll::Ready(Ok(output));
+        }
+
+        match this.sleep.poll(cx) {
+            Poll::Ready(()) => Poll::Ready(Err(Elapsed(()))),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// An error returned by [timeout] when the deadline has elapsed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Elapsed(());
+
+impl fmt::Display for Elapsed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("deadline has elapsed")
+    }
+}
+
+impl error::Error for Elapsed {}
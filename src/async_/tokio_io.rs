@@ -0,0 +1,187 @@
+use core::ffi::c_void;
+use core::pin::Pin;
+use core::ptr::{self, NonNull};
+use core::task::{self, Poll};
+use std::boxed::Box;
+use std::io;
+
+use nginx_sys::{
+    ngx_connection_t, ngx_event_handler_pt, ngx_event_t, ngx_handle_read_event,
+    ngx_handle_write_event, ngx_int_t, NGX_OK,
+};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::core::{Connection, Status};
+use crate::ngx_log_debug;
+
+/// An adapter implementing the [tokio] I/O traits over an nginx connection.
+///
+/// The connection read and write events are used to wake the tasks waiting for the socket
+/// readiness, so any tokio-based client can run on the nginx event loop with the runtime from
+/// [crate::async_].
+///
+/// The adapter takes over the `data` field and the event handlers of the connection, and restores
+/// the previous ones on drop. The connection is not closed on drop; [`AsyncWrite::poll_shutdown`]
+/// only flushes the pending output, as nginx does not buffer it.
+pub struct TokioConnection {
+    c: NonNull<ngx_connection_t>,
+    wakers: Box<ConnectionWakers>,
+    saved: SavedHandlers,
+}
+
+/// The connection `data` and event handlers replaced by the adapter.
+struct SavedHandlers {
+    data: *mut c_void,
+    read: ngx_event_handler_pt,
+    write: ngx_event_handler_pt,
+}
+
+#[derive(Default)]
+struct ConnectionWakers {
+    read: Option<task::Waker>,
+    write: Option<task::Waker>,
+}
+
+impl TokioConnection {
+    /// Creates a new adapter over the connection.
+    ///
+    /// # Safety
+    ///
+    /// `c` must be a valid connection pointer that outlives the adapter. The connection must not
+    /// be used by other means while the adapter exists.
+    pub unsafe fn new(c: NonNull<ngx_connection_t>) -> Self {
+        let mut wakers = Box::<ConnectionWakers>::default();
+
+        let conn = &mut *c.as_ptr();
+        let saved = SavedHandlers {
+            data: conn.data,
+            read: (*conn.read).handler,
+            write: (*conn.write).handler,
+        };
+
+        conn.data = ptr::from_mut(&mut *wakers).cast();
+        (*conn.read).handler = Some(tokio_connection_read_handler);
+        (*conn.write).handler = Some(tokio_connection_write_handler);
+
+        Self { c, wakers, saved }
+    }
+
+    /// Returns a reference to the underlying connection.
+    pub fn connection(&self) -> &Connection {
+        unsafe { Connection::from_ptr(self.c.as_ptr()) }
+    }
+
+    /// Returns a mutable reference to the underlying connection.
+    pub fn connection_mut(&mut self) -> &mut Connection {
+        unsafe { Connection::from_ptr_mut(self.c.as_ptr()) }
+    }
+}
+
+impl AsyncRead for TokioConnection {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let c = unsafe { Connection::from_ptr_mut(this.c.as_ptr()) };
+
+        // `recv()` with an empty buffer returns 0, which nginx treats as the end of stream.
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        match c.recv(buf.initialize_unfilled()) {
+            Ok(n) => {
+                buf.advance(n);
+                Poll::Ready(Ok(()))
+            }
+            Err(Status::NGX_AGAIN) => {
+                this.wakers.read = Some(cx.waker().clone());
+                let rev = c.as_ref().read;
+                if unsafe { ngx_handle_read_event(rev, 0) } != NGX_OK as ngx_int_t {
+                    return Poll::Ready(Err(io::Error::other("failed to add read event")));
+                }
+                Poll::Pending
+            }
+            Err(_) => Poll::Ready(Err(io::Error::other("recv() failed"))),
+        }
+    }
+}
+
+impl AsyncWrite for TokioConnection {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let c = unsafe { Connection::from_ptr_mut(this.c.as_ptr()) };
+
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        match c.send(buf) {
+            Ok(n) => Poll::Ready(Ok(n)),
+            Err(Status::NGX_AGAIN) => {
+                this.wakers.write = Some(cx.waker().clone());
+                let wev = c.as_ref().write;
+                if unsafe { ngx_handle_write_event(wev, 0) } != NGX_OK as ngx_int_t {
+                    return Poll::Ready(Err(io::Error::other("failed to add write event")));
+                }
+                Poll::Pending
+            }
+            Err(_) => Poll::Ready(Err(io::Error::other("send() failed"))),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Drop for TokioConnection {
+    fn drop(&mut self) {
+        // SAFETY: the connection outlives the adapter. Restoring the handlers ensures that later
+        // events do not reach the freed wakers.
+        unsafe {
+            let conn = &mut *self.c.as_ptr();
+            conn.data = self.saved.data;
+            (*conn.read).handler = self.saved.read;
+            (*conn.write).handler = self.saved.write;
+        }
+    }
+}
+
+extern "C" fn tokio_connection_read_handler(ev: *mut ngx_event_t) {
+    // SAFETY: the event belongs to a connection with the `data` field set by TokioConnection.
+    let wakers = unsafe {
+        let c: *mut ngx_connection_t = (*ev).data.cast();
+        &mut *(*c).data.cast::<ConnectionWakers>()
+    };
+
+    ngx_log_debug!(unsafe { (*ev).log }, "async: connection read event");
+
+    if let Some(waker) = wakers.read.take() {
+        waker.wake();
+    }
+}
+
+extern "C" fn tokio_connection_write_handler(ev: *mut ngx_event_t) {
+    // SAFETY: the event belongs to a connection with the `data` field set by TokioConnection.
+    let wakers = unsafe {
+        let c: *mut ngx_connection_t = (*ev).data.cast();
+        &mut *(*c).data.cast::<ConnectionWakers>()
+    };
+
+    ngx_log_debug!(unsafe { (*ev).log }, "async: connection write event");
+
+    if let Some(waker) = wakers.write.take() {
+        waker.wake();
+    }
+}
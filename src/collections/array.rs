@@ -0,0 +1,275 @@
+//! Types and utilities for working with [ngx_array_t].
+//!
+//! See <https://nginx.org/en/docs/dev/development_guide.html#array>.
+
+use core::marker::PhantomData;
+use core::{mem, ops, slice};
+
+use nginx_sys::{ngx_array_create, ngx_array_push, ngx_array_t, ngx_pool_t};
+
+use crate::allocator::AllocError;
+use crate::core::Pool;
+
+/// A wrapper over a raw [ngx_array_t], a growable array allocated from a memory pool.
+///
+/// The elements are never dropped, as the memory is owned by the pool. Thus, the type is only
+/// usable with the [Copy] types, matching the semantics of the NGINX code.
+///
+/// Any plain value type can be used as an element, e.g. `ngx_int_t`, `in_port_t` or a `#[repr(C)]`
+/// structure with the layout of the elements created by NGINX. The array dereferences to `[T]`,
+/// providing bounds-checked access with [`slice::get`] and indexing.
+///
+/// See <https://nginx.org/en/docs/dev/development_guide.html#array>.
+#[derive(Debug)]
+#[repr(transparent)]
+pub struct NgxArray<T> {
+    inner: ngx_array_t,
+    _type: PhantomData<T>,
+}
+
+impl<T> NgxArray<T>
+where
+    T: Copy,
+{
+    /// Creates an array reference from a pointer to [ngx_array_t].
+    ///
+    /// # Safety
+    ///
+    /// `array` is a valid pointer to an initialized array with elements of type `T`.
+    pub unsafe fn from_ptr<'a>(array: *const ngx_array_t) -> &'a Self {
+        debug_assert_eq!((*array).size, mem::size_of::<T>());
+        &*array.cast()
+    }
+
+    /// Creates a mutable array reference from a pointer to [ngx_array_t].
+    ///
+    /// # Safety
+    ///
+    /// `array` is a valid pointer to an initialized array with elements of type `T`.
+    pub unsafe fn from_ptr_mut<'a>(array: *mut ngx_array_t) -> &'a mut Self {
+        debug_assert_eq!((*array).size, mem::size_of::<T>());
+        &mut *array.cast()
+    }
+
+    /// Creates a new array with the specified capacity in the memory pool.
+    ///
+    /// The capacity is rounded up to 1, as an empty `ngx_array_t` is unable to grow.
+    pub fn create(pool: &Pool, capacity: usize) -> Result<&mut Self, AllocError> {
+        let capacity = capacity.max(1);
+        let pool: *const ngx_pool_t = pool.as_ref();
+        // SAFETY: the pool is not accessed concurrently, as nginx pools are single-threaded
+        let array = unsafe { ngx_array_create(pool.cast_mut(), capacity, mem::size_of::<T>()) };
+        if array.is_null() {
+            return Err(AllocError);
+        }
+
+        Ok(unsafe { Self::from_ptr_mut(array) })
+    }
+
+    /// Creates a new array in the memory pool and fills it with the elements of the iterator.
+    pub fn from_iter_in<I>(iter: I, pool: &Pool) -> Result<&mut Self, AllocError>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let iter = iter.into_iter();
+        let this = Self::create(pool, iter.size_hint().0)?;
+        this.try_extend(iter)?;
+        Ok(this)
+    }
+
+    /// Returns the number of elements in the array.
+    pub fn len(&self) -> usize {
+        self.inner.nelts
+    }
+
+    /// Returns `true` if the array contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.inner.nelts == 0
+    }
+
+    /// Returns the number of elements the array can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.inner.nalloc
+    }
+
+    /// Returns the contents of the array as a slice.
+    pub fn as_slice(&self) -> &[T] {
+        // SAFETY: the array contains elements of type `T`
+        unsafe { self.inner.as_slice() }
+    }
+
+    /// Returns the contents of the array as a mutable slice.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        // SAFETY: the array contains elements of type `T`
+        unsafe { self.inner.as_slice_mut() }
+    }
+
+    /// Appends an element to the back of the array.
+    ///
+    /// The array storage is reallocated from the pool when the capacity is exhausted, leaving the
+    /// old storage unused until the pool is destroyed.
+    pub fn push(&mut self, value: T) -> Result<&mut T, AllocError> {
+        let elt = unsafe { ngx_array_push(&mut self.inner) }.cast::<T>();
+        if elt.is_null() {
+            return Err(AllocError);
+        }
+
+        unsafe {
+            elt.write(value);
+            Ok(&mut *elt)
+        }
+    }
+
+    /// Appends all the elements of the iterator to the array.
+    pub fn try_extend<I>(&mut self, iter: I) -> Result<(), AllocError>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        for value in iter {
+            self.push(value)?;
+        }
+
+        Ok(())
+    }
+
+    /// Retains only the elements specified by the predicate.
+    ///
+    /// The retained elements are moved to the beginning of the array, preserving the order, and
+    /// the number of elements is adjusted. The capacity is not changed.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let elts = self.as_mut_slice();
+        let mut kept = 0;
+
+        for i in 0..elts.len() {
+            if f(&elts[i]) {
+                if i != kept {
+                    elts[kept] = elts[i];
+                }
+                kept += 1;
+            }
+        }
+
+        self.inner.nelts = kept;
+    }
+
+    /// Shortens the array, keeping the first `len` elements.
+    pub fn truncate(&mut self, len: usize) {
+        self.inner.nelts = self.inner.nelts.min(len);
+    }
+
+    /// Returns an iterator over the array.
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.as_slice().iter()
+    }
+
+    /// Returns a mutable iterator over the array.
+    pub fn iter_mut(&mut self) -> slice::IterMut<'_, T> {
+        self.as_mut_slice().iter_mut()
+    }
+}
+
+impl<T> AsRef<ngx_array_t> for NgxArray<T> {
+    fn as_ref(&self) -> &ngx_array_t {
+        &self.inner
+    }
+}
+
+impl<T> AsMut<ngx_array_t> for NgxArray<T> {
+    fn as_mut(&mut self) -> &mut ngx_array_t {
+        &mut self.inner
+    }
+}
+
+impl<T: Copy> ops::Deref for NgxArray<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl<T: Copy> ops::DerefMut for NgxArray<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.as_mut_slice()
+    }
+}
+
+impl<'a, T: Copy> IntoIterator for &'a NgxArray<T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T: Copy> IntoIterator for &'a mut NgxArray<T> {
+    type Item = &'a mut T;
+    type IntoIter = slice::IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::ptr;
+
+    use nginx_sys::{ngx_int_t, ngx_str_t};
+
+    use super::*;
+    use crate::ngx_string;
+
+    #[test]
+    fn test_retain() {
+        let mut elts = [
+            ngx_string!("a"),
+            ngx_string!("bb"),
+            ngx_string!("c"),
+            ngx_string!("dd"),
+            ngx_string!("ee"),
+        ];
+
+        let mut raw = ngx_array_t {
+            elts: elts.as_mut_ptr().cast(),
+            nelts: elts.len(),
+            size: mem::size_of::<ngx_str_t>(),
+            nalloc: elts.len(),
+            pool: ptr::null_mut(),
+        };
+
+        let array: &mut NgxArray<ngx_str_t> = unsafe { NgxArray::from_ptr_mut(&mut raw) };
+        array.retain(|x| x.len == 2);
+
+        assert_eq!(array.len(), 3);
+        assert_eq!(array.capacity(), 5);
+        assert_eq!(array[0].as_bytes(), b"bb");
+        assert_eq!(array[1].as_bytes(), b"dd");
+        assert_eq!(array[2].as_bytes(), b"ee");
+
+        array.truncate(1);
+        assert_eq!(array.iter().count(), 1);
+    }
+
+    #[test]
+    fn test_pod_elements() {
+        let mut elts: [ngx_int_t; 3] = [80, 443, 8080];
+
+        let raw = ngx_array_t {
+            elts: elts.as_mut_ptr().cast(),
+            nelts: elts.len(),
+            size: mem::size_of::<ngx_int_t>(),
+            nalloc: elts.len(),
+            pool: ptr::null_mut(),
+        };
+
+        let array: &NgxArray<ngx_int_t> = unsafe { NgxArray::from_ptr(&raw) };
+        assert_eq!(array.get(1), Some(&443));
+        assert_eq!(array.get(3), None);
+        assert_eq!(array.iter().sum::<ngx_int_t>(), 9003);
+    }
+}
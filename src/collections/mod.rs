@@ -10,8 +10,14 @@ pub use allocator_api2::{
     vec::Vec,
 };
 
+pub use array::NgxArray;
 pub use queue::Queue;
 pub use rbtree::RbTreeMap;
+#[cfg(feature = "alloc")]
+pub use shared_dict::SharedDict;
 
+pub mod array;
 pub mod queue;
 pub mod rbtree;
+#[cfg(feature = "alloc")]
+pub mod shared_dict;
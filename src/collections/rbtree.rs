@@ -17,7 +17,7 @@ use nginx_sys::{
     ngx_rbtree_key_t, ngx_rbtree_min, ngx_rbtree_next, ngx_rbtree_node_t, ngx_rbtree_t,
 };
 
-use crate::allocator::{self, AllocError, Allocator};
+use crate::allocator::{AllocError, Allocator};
 
 /// Trait for pointer conversions between the tree entry and its container.
 ///
@@ -171,6 +171,8 @@ where
 {
     tree: NgxRbTree<MapEntry<K, V>>,
     sentinel: NonNull<ngx_rbtree_node_t>,
+    len: usize,
+    memory_limit: Option<usize>,
     alloc: A,
 }
 
@@ -256,6 +258,51 @@ impl<'a, K: 'a, V: 'a> Iterator for MapIterMut<'a, K, V> {
     }
 }
 
+/// A draining iterator for the [RbTreeMap].
+///
+/// The entries not consumed by the iterator are removed when the iterator is dropped.
+pub struct MapDrain<'a, K: 'a, V: 'a, A: Allocator> {
+    map: &'a mut RbTreeMap<K, V, A>,
+    iter: NgxRbTreeIter<'a>,
+}
+
+impl<'a, K: 'a, V: 'a, A: Allocator> MapDrain<'a, K, V, A> {
+    /// Creates a draining iterator for the [RbTreeMap].
+    pub fn new(map: &'a mut RbTreeMap<K, V, A>) -> Self {
+        // msrv(1.89.0): NonNull::from_mut()
+        let rbtree = NonNull::from(&mut map.tree.inner);
+        // SAFETY: Drain borrows from the tree, ensuring that the tree would outlive it.
+        // The iterator tolerates removal of the last returned node.
+        let iter = unsafe { NgxRbTreeIter::new(rbtree) };
+        Self { map, iter }
+    }
+}
+
+impl<'a, K: 'a, V: 'a, A: Allocator> Iterator for MapDrain<'a, K, V, A> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut data = MapEntry::<K, V>::from_rbtree_node(self.iter.next()?);
+        let layout = Layout::new::<MapEntry<K, V>>();
+
+        unsafe {
+            ngx_rbtree_delete(&mut self.map.tree.inner, &mut data.as_mut().node);
+            // SAFETY: we make a bitwise copy of the node and dispose of the original value without
+            // dropping it.
+            let copy = data.as_ptr().read();
+            self.map.allocator().deallocate(data.cast(), layout);
+            self.map.len -= 1;
+            Some(copy.into_kv())
+        }
+    }
+}
+
+impl<'a, K: 'a, V: 'a, A: Allocator> Drop for MapDrain<'a, K, V, A> {
+    fn drop(&mut self) {
+        self.for_each(drop);
+    }
+}
+
 impl<K, V, A> RbTreeMap<K, V, A>
 where
     A: Allocator,
@@ -267,24 +314,148 @@ where
 
     /// Clears the tree, removing all elements.
     pub fn clear(&mut self) {
-        // SAFETY: the iter lives until the end of the scope
+        self.drop_all();
+    }
+
+    /// Drops and deallocates all the entries, leaving an empty tree.
+    ///
+    /// The nodes are detached from their parents and freed in a post-order walk, without the
+    /// rebalancing done by `ngx_rbtree_delete`, so the whole tree is released in linear time.
+    fn drop_all(&mut self) {
+        let sentinel = self.tree.inner.sentinel;
+        let root = self.tree.inner.root;
+        let layout = Layout::new::<MapEntry<K, V>>();
+        let mut node = root;
+
+        while !node.is_null() && !ptr::addr_eq(node, sentinel) {
+            // SAFETY: the node is a valid entry of the tree, and the links of the remaining
+            // nodes are kept consistent
+            unsafe {
+                let n = &mut *node;
+
+                if !ptr::addr_eq(n.left, sentinel) {
+                    node = n.left;
+                    continue;
+                }
+
+                if !ptr::addr_eq(n.right, sentinel) {
+                    node = n.right;
+                    continue;
+                }
+
+                let parent = n.parent;
+                if ptr::addr_eq(node, root) {
+                    node = sentinel;
+                } else {
+                    if ptr::addr_eq((*parent).left, node) {
+                        (*parent).left = sentinel;
+                    } else {
+                        (*parent).right = sentinel;
+                    }
+                    node = parent;
+                }
+
+                let mut data = MapEntry::<K, V>::from_rbtree_node(NonNull::from(n));
+                ptr::drop_in_place(data.as_mut());
+                self.allocator().deallocate(data.cast(), layout);
+            }
+        }
+
+        self.tree.inner.root = sentinel;
+        self.len = 0;
+    }
+
+    /// Returns true if the tree contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Returns the number of entries in the tree.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the memory allocated for the tree entries.
+    ///
+    /// This is the sum of the entry layouts, not including the allocator overhead, the sentinel
+    /// node, or the memory owned by the keys and values.
+    pub fn memory_used(&self) -> usize {
+        self.len * Layout::new::<MapEntry<K, V>>().size()
+    }
+
+    /// Returns the limit of the memory allocated for the tree entries, if set.
+    pub fn memory_limit(&self) -> Option<usize> {
+        self.memory_limit
+    }
+
+    /// Sets the limit of the memory allocated for the tree entries.
+    ///
+    /// An insertion of a new entry fails with [AllocError] without calling the allocator if
+    /// [RbTreeMap::memory_used] would exceed the limit. Lowering the limit below the current
+    /// usage does not remove any entries.
+    pub fn set_memory_limit(&mut self, limit: Option<usize>) {
+        self.memory_limit = limit;
+    }
+
+    /// Retains only the elements specified by the predicate.
+    ///
+    /// Removes all entries for which `f` returns `false` and returns the number of removed
+    /// entries.
+    pub fn retain<F>(&mut self, mut f: F) -> usize
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        // SAFETY: the iter lives until the end of the scope and tolerates removal of the last
+        // returned node
         let iter = unsafe { NgxRbTreeIter::new(NonNull::from(&self.tree.inner)) };
         let layout = Layout::new::<MapEntry<K, V>>();
+        let mut removed = 0;
 
         for node in iter {
             unsafe {
                 let mut data = MapEntry::<K, V>::from_rbtree_node(node);
+                let entry = data.as_mut();
+
+                if f(&entry.key, &mut entry.value) {
+                    continue;
+                }
 
-                ngx_rbtree_delete(&mut self.tree.inner, &mut data.as_mut().node);
+                ngx_rbtree_delete(&mut self.tree.inner, &mut entry.node);
                 ptr::drop_in_place(data.as_mut());
-                self.allocator().deallocate(data.cast(), layout)
+                self.allocator().deallocate(data.cast(), layout);
+                removed += 1;
             }
         }
+
+        self.len -= removed;
+
+        removed
     }
 
-    /// Returns true if the tree contains no entries.
-    pub fn is_empty(&self) -> bool {
-        self.tree.is_empty()
+    /// Removes the `n`-th entry in the iteration order and returns it.
+    pub(crate) fn remove_nth(&mut self, n: usize) -> Option<(K, V)> {
+        // SAFETY: the iter does not outlive the tree and the node is not used after the removal
+        let node = unsafe { NgxRbTreeIter::new(NonNull::from(&self.tree.inner)) }.nth(n)?;
+        let mut data = MapEntry::<K, V>::from_rbtree_node(node);
+        let layout = Layout::new::<MapEntry<K, V>>();
+
+        unsafe {
+            ngx_rbtree_delete(&mut self.tree.inner, &mut data.as_mut().node);
+            // SAFETY: we make a bitwise copy of the node and dispose of the original value without
+            // dropping it.
+            let copy = data.as_ptr().read();
+            self.allocator().deallocate(data.cast(), layout);
+            self.len -= 1;
+            Some(copy.into_kv())
+        }
+    }
+
+    /// Removes all entries from the tree, returning them as an iterator.
+    ///
+    /// The entries are removed even if the iterator is not fully consumed.
+    #[inline]
+    pub fn drain(&mut self) -> MapDrain<'_, K, V, A> {
+        MapDrain::new(self)
     }
 
     /// Returns an iterator over the entries of the tree.
@@ -318,6 +489,8 @@ where
         let mut this = RbTreeMap {
             tree,
             sentinel,
+            len: 0,
+            memory_limit: None,
             alloc,
         };
 
@@ -377,25 +550,79 @@ where
             // dropping it.
             let copy = node.as_ptr().read();
             self.allocator().deallocate(node.cast(), layout);
+            self.len -= 1;
             Some(copy.into_kv())
         }
     }
 
     /// Attempts to insert a new element into the tree.
     pub fn try_insert(&mut self, key: K, value: V) -> Result<&mut V, AllocError> {
+        self.try_insert_or_return(key, value)
+            .map_err(|_| AllocError)
+    }
+
+    /// Attempts to insert a new element into the tree, giving back the key and value if the
+    /// allocation fails.
+    pub(crate) fn try_insert_or_return(&mut self, key: K, value: V) -> Result<&mut V, (K, V)> {
         let mut node = if let Some(mut node) = self.lookup(&key) {
             unsafe { node.as_mut().value = value };
             node
         } else {
-            let node = MapEntry::new(key, value);
-            let mut node = allocator::allocate(node, self.allocator())?;
+            let Ok(mut node) = self.allocate_entry() else {
+                return Err((key, value));
+            };
+            // SAFETY: the allocator succeeded and gave us a correctly aligned pointer to an
+            // uninitialized data
+            unsafe { node.as_ptr().write(MapEntry::new(key, value)) };
             self.tree.insert(unsafe { node.as_mut() });
+            self.len += 1;
             node
         };
 
         Ok(unsafe { &mut node.as_mut().value })
     }
 
+    /// Returns a mutable reference to the value corresponding to the key, inserting the result of
+    /// `f` if the key is not present.
+    ///
+    /// The tree is searched once. `f` is not called if the key is present, and is called before
+    /// allocating the entry, so that a panic in `f` does not leak the memory. The value is dropped
+    /// if the allocation fails.
+    pub fn get_or_insert_with<F>(&mut self, key: K, f: F) -> Result<&mut V, AllocError>
+    where
+        F: FnOnce() -> V,
+    {
+        let mut node = if let Some(node) = self.lookup(&key) {
+            node
+        } else {
+            let value = f();
+            let mut node = self.allocate_entry()?;
+            // SAFETY: the allocator succeeded and gave us a correctly aligned pointer to an
+            // uninitialized data
+            unsafe { node.as_ptr().write(MapEntry::new(key, value)) };
+            self.tree.insert(unsafe { node.as_mut() });
+            self.len += 1;
+            node
+        };
+
+        // SAFETY: the entries are allocated separately and the rebalancing only updates the links
+        // between the nodes, so the entry address remains valid until it is removed from the tree.
+        Ok(unsafe { &mut node.as_mut().value })
+    }
+
+    /// Allocates memory for a new entry, checking the memory limit.
+    fn allocate_entry(&self) -> Result<NonNull<MapEntry<K, V>>, AllocError> {
+        let layout = Layout::new::<MapEntry<K, V>>();
+
+        if let Some(limit) = self.memory_limit {
+            if self.memory_used() + layout.size() > limit {
+                return Err(AllocError);
+            }
+        }
+
+        Ok(self.allocator().allocate(layout)?.cast())
+    }
+
     extern "C" fn insert(
         mut temp: *mut ngx_rbtree_node_t,
         node: *mut ngx_rbtree_node_t,
@@ -462,7 +689,7 @@ where
     A: Allocator,
 {
     fn drop(&mut self) {
-        self.clear();
+        self.drop_all();
 
         unsafe {
             self.allocator().deallocate(
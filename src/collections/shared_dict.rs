@@ -0,0 +1,234 @@
+//! A key-value store with per-entry expiration, suitable for the shared memory zones.
+//!
+//! See [SharedDict].
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::time::Duration;
+
+use nginx_sys::{ngx_current_msec, ngx_msec_int_t, ngx_msec_t};
+
+use crate::allocator::{AllocError, Allocator};
+use crate::collections::RbTreeMap;
+use crate::core::{NgxStr, NgxString, SlabPool, NGX_TIMER_DURATION_MAX};
+
+/// A map with byte string keys and per-entry expiration time, based on [RbTreeMap].
+///
+/// The type is modeled after the `ngx_http_limit_req_module` zones and is intended to be stored
+/// in a shared memory zone with a [SlabPool] allocator:
+///
+///  * Expired entries are treated as absent and are removed on the next modification.
+///  * When the allocator runs out of memory, [`SharedDict::insert`] evicts entries until the
+///    allocation succeeds, preferring the expired and then the least recently used ones among the
+///    first [`EVICT_SAMPLES`](Self::EVICT_SAMPLES) entries of the map.
+///
+/// The time is measured with `ngx_current_msec`, the time cached by each nginx process and
+/// updated once per event loop iteration. The expiration is thus approximate, and the processes
+/// sharing a dictionary may observe an entry expiring at slightly different moments.
+///
+/// The dictionary does not provide any synchronization; wrap it in a [`crate::sync::RwLock`]
+/// when sharing between the processes.
+#[derive(Debug)]
+pub struct SharedDict<V, A = SlabPool>
+where
+    A: Allocator + Clone,
+{
+    map: RbTreeMap<NgxString<A>, SharedDictEntry<V>, A>,
+}
+
+#[derive(Debug)]
+struct SharedDictEntry<V> {
+    value: V,
+    expires: ngx_msec_t,
+    // Updated from the readers, which may run concurrently in different processes.
+    accessed: AtomicUsize,
+}
+
+impl<V> SharedDictEntry<V> {
+    #[inline]
+    fn is_expired(&self, now: ngx_msec_t) -> bool {
+        self.expires.wrapping_sub(now) as ngx_msec_int_t <= 0
+    }
+
+    #[inline]
+    fn touch(&self, now: ngx_msec_t) {
+        self.accessed.store(now as usize, Ordering::Relaxed);
+    }
+
+    #[inline]
+    fn idle(&self, now: ngx_msec_t) -> ngx_msec_t {
+        now.wrapping_sub(self.accessed.load(Ordering::Relaxed) as ngx_msec_t)
+    }
+}
+
+impl<V, A> SharedDict<V, A>
+where
+    A: Allocator + Clone,
+{
+    /// The maximum number of entries examined to choose an entry to evict.
+    ///
+    /// The scan is bounded to keep the allocation retries cheap for large dictionaries, so the
+    /// eviction only approximates the least recently used policy.
+    pub const EVICT_SAMPLES: usize = 16;
+
+    /// Attempts to create a new empty dictionary with the specified allocator.
+    pub fn try_new_in(alloc: A) -> Result<Self, AllocError> {
+        Ok(Self {
+            map: RbTreeMap::try_new_in(alloc)?,
+        })
+    }
+
+    /// Returns a reference to the underlying allocator.
+    pub fn allocator(&self) -> &A {
+        self.map.allocator()
+    }
+
+    /// Returns true if the dictionary contains no entries, including the expired ones.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns a reference to the value corresponding to the key, if the entry is not expired.
+    pub fn get(&self, key: impl AsRef<[u8]>) -> Option<&V> {
+        self.get_at(key.as_ref(), now())
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key, if the entry is not
+    /// expired.
+    pub fn get_mut(&mut self, key: impl AsRef<[u8]>) -> Option<&mut V> {
+        self.get_mut_at(key.as_ref(), now())
+    }
+
+    /// Inserts a value with the specified time to live, replacing the existing entry.
+    ///
+    /// The `ttl` is clamped to the maximum duration supported by the nginx timers.
+    /// Returns [AllocError] if the value cannot be stored even after evicting all the other
+    /// entries.
+    pub fn insert(
+        &mut self,
+        key: impl AsRef<[u8]>,
+        value: V,
+        ttl: Duration,
+    ) -> Result<&mut V, AllocError> {
+        self.insert_at(key.as_ref(), value, ttl, now())
+    }
+
+    /// Removes the entry from the dictionary, returning the value if the entry was not expired.
+    pub fn remove(&mut self, key: impl AsRef<[u8]>) -> Option<V> {
+        let now = now();
+        let entry = self.map.remove(NgxStr::from_bytes(key.as_ref()))?;
+        (!entry.is_expired(now)).then_some(entry.value)
+    }
+
+    /// Removes all the expired entries and returns the number of removed entries.
+    pub fn expire(&mut self) -> usize {
+        self.expire_at(now())
+    }
+
+    /// Returns an iterator over the entries that are not expired.
+    pub fn iter(&self) -> impl Iterator<Item = (&NgxStr, &V)> + '_ {
+        let now = now();
+        self.map
+            .iter()
+            .filter(move |(_, entry)| !entry.is_expired(now))
+            .map(|(key, entry)| (key.as_ngx_str(), &entry.value))
+    }
+
+    fn get_at(&self, key: &[u8], now: ngx_msec_t) -> Option<&V> {
+        let entry = self.map.get(NgxStr::from_bytes(key))?;
+        if entry.is_expired(now) {
+            return None;
+        }
+
+        entry.touch(now);
+        Some(&entry.value)
+    }
+
+    fn get_mut_at(&mut self, key: &[u8], now: ngx_msec_t) -> Option<&mut V> {
+        let entry = self.map.get_mut(NgxStr::from_bytes(key))?;
+        if entry.is_expired(now) {
+            return None;
+        }
+
+        entry.touch(now);
+        Some(&mut entry.value)
+    }
+
+    fn insert_at(
+        &mut self,
+        key: &[u8],
+        value: V,
+        ttl: Duration,
+        now: ngx_msec_t,
+    ) -> Result<&mut V, AllocError> {
+        let ttl = ttl.min(NGX_TIMER_DURATION_MAX).as_millis() as ngx_msec_t;
+        let mut entry = SharedDictEntry {
+            value,
+            expires: now.wrapping_add(ttl),
+            accessed: AtomicUsize::new(now as usize),
+        };
+
+        if let Some(existing) = self.map.get_mut(NgxStr::from_bytes(key)) {
+            // reuse the stored key
+            *existing = entry;
+        } else {
+            let mut name = loop {
+                if let Ok(name) = NgxString::try_from_bytes_in(key, self.allocator().clone()) {
+                    break name;
+                }
+
+                if !self.evict(now) {
+                    return Err(AllocError);
+                }
+            };
+
+            loop {
+                match self.map.try_insert_or_return(name, entry) {
+                    Ok(_) => break,
+                    Err(x) => (name, entry) = x,
+                }
+
+                if !self.evict(now) {
+                    return Err(AllocError);
+                }
+            }
+        }
+
+        self.map
+            .get_mut(NgxStr::from_bytes(key))
+            .map(|x| &mut x.value)
+            .ok_or(AllocError)
+    }
+
+    fn expire_at(&mut self, now: ngx_msec_t) -> usize {
+        self.map.retain(|_, entry| !entry.is_expired(now))
+    }
+
+    /// Frees some memory by removing an entry, either expired or the least recently used one,
+    /// among the first [`EVICT_SAMPLES`](Self::EVICT_SAMPLES) entries of the map.
+    ///
+    /// Returns `false` if the dictionary is empty.
+    fn evict(&mut self, now: ngx_msec_t) -> bool {
+        let Some((n, _)) = self
+            .map
+            .iter()
+            .take(Self::EVICT_SAMPLES)
+            .enumerate()
+            .max_by_key(|(_, (_, entry))| {
+                if entry.is_expired(now) {
+                    ngx_msec_t::MAX
+                } else {
+                    entry.idle(now)
+                }
+            })
+        else {
+            return false;
+        };
+
+        self.map.remove_nth(n).is_some()
+    }
+}
+
+#[inline]
+fn now() -> ngx_msec_t {
+    unsafe { ngx_current_msec }
+}
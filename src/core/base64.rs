@@ -0,0 +1,111 @@
+use crate::core::Pool;
+use crate::ffi::{
+    ngx_decode_base64, ngx_decode_base64url, ngx_encode_base64, ngx_encode_base64url, ngx_int_t,
+    ngx_str_t, u_char, NGX_OK,
+};
+
+/// Returns the maximum length of the base64 encoding of `len` bytes, as
+/// `ngx_base64_encoded_length`.
+pub const fn base64_encoded_length(len: usize) -> usize {
+    len.div_ceil(3) * 4
+}
+
+/// Returns the maximum length of the data decoded from `len` base64 characters, as
+/// `ngx_base64_decoded_length`.
+pub const fn base64_decoded_length(len: usize) -> usize {
+    len.div_ceil(4) * 3
+}
+
+/// Encodes the data with `ngx_encode_base64`, allocating the result in the pool.
+///
+/// If `url_safe` is set, the URL and filename safe alphabet is used without padding, as in
+/// `ngx_encode_base64url`.
+///
+/// Returns `None` if the allocation fails.
+pub fn base64_encode(pool: &Pool, src: &[u8], url_safe: bool) -> Option<ngx_str_t> {
+    if src.is_empty() {
+        return Some(ngx_str_t::empty());
+    }
+
+    let len = base64_encoded_length(src.len());
+    let data = pool.alloc_unaligned(len).cast::<u_char>();
+    if data.is_null() {
+        return None;
+    }
+
+    let mut dst = ngx_str_t { data, len };
+    let mut src = ngx_str_t {
+        data: src.as_ptr().cast_mut(),
+        len: src.len(),
+    };
+
+    // SAFETY: the destination buffer is large enough for the encoded data, and the source is not
+    // modified
+    unsafe {
+        if url_safe {
+            ngx_encode_base64url(&mut dst, &mut src)
+        } else {
+            ngx_encode_base64(&mut dst, &mut src)
+        }
+    };
+
+    Some(dst)
+}
+
+/// Decodes the base64 data with `ngx_decode_base64`, allocating the result in the pool.
+///
+/// If `url_safe` is set, the URL and filename safe alphabet is expected, as in
+/// `ngx_decode_base64url`. The padding is optional in both cases.
+///
+/// Returns `None` if the data is not valid base64 or the allocation fails.
+pub fn base64_decode(pool: &Pool, src: &[u8], url_safe: bool) -> Option<ngx_str_t> {
+    if src.is_empty() {
+        return Some(ngx_str_t::empty());
+    }
+
+    let len = base64_decoded_length(src.len());
+    let data = pool.alloc_unaligned(len).cast::<u_char>();
+    if data.is_null() {
+        return None;
+    }
+
+    let mut dst = ngx_str_t { data, len };
+    let mut src = ngx_str_t {
+        data: src.as_ptr().cast_mut(),
+        len: src.len(),
+    };
+
+    // SAFETY: the destination buffer is large enough for the decoded data, and the source is not
+    // modified
+    let rc = unsafe {
+        if url_safe {
+            ngx_decode_base64url(&mut dst, &mut src)
+        } else {
+            ngx_decode_base64(&mut dst, &mut src)
+        }
+    };
+
+    if rc != NGX_OK as ngx_int_t {
+        return None;
+    }
+
+    Some(dst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64_length() {
+        assert_eq!(base64_encoded_length(0), 0);
+        assert_eq!(base64_encoded_length(1), 4);
+        assert_eq!(base64_encoded_length(3), 4);
+        assert_eq!(base64_encoded_length(4), 8);
+
+        assert_eq!(base64_decoded_length(0), 0);
+        assert_eq!(base64_decoded_length(2), 3);
+        assert_eq!(base64_decoded_length(4), 3);
+        assert_eq!(base64_decoded_length(6), 6);
+    }
+}
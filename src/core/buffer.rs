@@ -1,5 +1,16 @@
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem;
+use core::ops::Range;
+use core::ptr;
 use core::slice;
 
+use crate::allocator::AllocError;
+#[cfg(feature = "alloc")]
+use crate::collections::Vec;
+#[cfg(feature = "alloc")]
+use crate::core::NgxString;
+use crate::core::{Pool, Status};
 use crate::ffi::*;
 
 /// The `Buffer` trait provides methods for working with an nginx buffer (`ngx_buf_t`).
@@ -12,10 +23,17 @@ pub trait Buffer {
     /// Returns a mutable raw pointer to the underlying `ngx_buf_t` of the buffer.
     fn as_ngx_buf_mut(&mut self) -> *mut ngx_buf_t;
 
-    /// Returns the buffer contents as a byte slice.
+    /// Returns the buffer contents between `pos` and `last` as a byte slice.
+    ///
+    /// The slice is empty for the buffers without memory contents, e.g. special or file buffers.
     fn as_bytes(&self) -> &[u8] {
+        let len = self.len();
+        if len == 0 {
+            return &[];
+        }
+
         let buf = self.as_ngx_buf();
-        unsafe { slice::from_raw_parts((*buf).pos, self.len()) }
+        unsafe { slice::from_raw_parts((*buf).pos, len) }
     }
 
     /// Returns the length of the buffer contents.
@@ -34,6 +52,33 @@ pub trait Buffer {
         self.len() == 0
     }
 
+    /// Returns the size of the memory allocated for the buffer, or 0 for the buffers without
+    /// memory contents.
+    fn capacity(&self) -> usize {
+        let buf = self.as_ngx_buf();
+        unsafe {
+            let start = (*buf).start;
+            let end = (*buf).end;
+            if start.is_null() || end < start {
+                return 0;
+            }
+            usize::wrapping_sub(end as _, start as _)
+        }
+    }
+
+    /// Returns the number of bytes that can be appended to the buffer contents.
+    fn remaining(&self) -> usize {
+        let buf = self.as_ngx_buf();
+        unsafe {
+            let last = (*buf).last;
+            let end = (*buf).end;
+            if last.is_null() || end < last {
+                return 0;
+            }
+            usize::wrapping_sub(end as _, last as _)
+        }
+    }
+
     /// Sets the `last_buf` flag of the buffer.
     ///
     /// # Arguments
@@ -57,15 +102,84 @@ pub trait Buffer {
             (*buf).set_last_in_chain(if last { 1 } else { 0 });
         }
     }
+
+    /// Sets the `flush` flag of the buffer.
+    ///
+    /// The flag requests the filters to send all the buffered output without ending the response.
+    fn set_flush(&mut self, flush: bool) {
+        let buf = self.as_ngx_buf_mut();
+        unsafe {
+            (*buf).set_flush(if flush { 1 } else { 0 });
+        }
+    }
+
+    /// Sets the `sync` flag of the buffer.
+    ///
+    /// The flag marks a buffer that carries no data and only synchronizes the filter chain.
+    fn set_sync(&mut self, sync: bool) {
+        let buf = self.as_ngx_buf_mut();
+        unsafe {
+            (*buf).set_sync(if sync { 1 } else { 0 });
+        }
+    }
+
+    /// Returns `true` if the buffer is a special (control) buffer with no data, only flags.
+    ///
+    /// Matches the `ngx_buf_special` macro.
+    fn is_special(&self) -> bool {
+        let buf = self.as_ngx_buf();
+        unsafe {
+            ((*buf).flush() != 0 || (*buf).last_buf() != 0 || (*buf).sync() != 0)
+                && (*buf).temporary() == 0
+                && (*buf).memory() == 0
+                && (*buf).mmap() == 0
+                && (*buf).in_file() == 0
+        }
+    }
+}
+
+/// The kind of a special buffer created with [`BufRef::special`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpecialBuf {
+    /// A buffer with the `flush` flag, sending the buffered output without ending the response.
+    Flush,
+    /// A buffer with the `sync` flag.
+    Sync,
+    /// A buffer with the `last_buf` flag, ending the response.
+    LastBuf,
+    /// A buffer with the `last_in_chain` flag, ending the output of a subrequest.
+    LastInChain,
 }
 
 /// The `MutableBuffer` trait extends the `Buffer` trait and provides methods for working with a
 /// mutable buffer.
 pub trait MutableBuffer: Buffer {
-    /// Returns a mutable reference to the buffer contents as a byte slice.
+    /// Returns the buffer contents between `pos` and `last` as a mutable byte slice.
     fn as_bytes_mut(&mut self) -> &mut [u8] {
+        let len = self.len();
+        if len == 0 {
+            return &mut [];
+        }
+
+        let buf = self.as_ngx_buf_mut();
+        unsafe { slice::from_raw_parts_mut((*buf).pos, len) }
+    }
+
+    /// Appends the data to the buffer contents, stopping at the end of the buffer memory.
+    ///
+    /// Returns the number of bytes copied.
+    fn append(&mut self, data: &[u8]) -> usize {
+        let n = data.len().min(self.remaining());
+        if n == 0 {
+            return 0;
+        }
+
         let buf = self.as_ngx_buf_mut();
-        unsafe { slice::from_raw_parts_mut((*buf).pos, self.len()) }
+        unsafe {
+            ptr::copy_nonoverlapping(data.as_ptr(), (*buf).last, n);
+            (*buf).last = (*buf).last.add(n);
+        }
+        n
     }
 }
 
@@ -81,6 +195,52 @@ impl TemporaryBuffer {
         assert!(!buf.is_null());
         TemporaryBuffer(buf)
     }
+
+    /// Creates a buffer over the contents of a pool-allocated vector, without copying.
+    ///
+    /// The buffer descriptor is allocated from the same pool. The vector memory is released with
+    /// the pool, and the unused capacity remains available for [`MutableBuffer::append`].
+    ///
+    /// A vector without allocated memory results in an empty special buffer with the `sync` flag
+    /// set, which nginx passes through the filter chain without data.
+    #[cfg(feature = "alloc")]
+    pub fn from_vec(data: Vec<u8, Pool>) -> Result<TemporaryBuffer, AllocError> {
+        let (start, len, capacity, pool) = data.into_raw_parts_with_alloc();
+
+        let buf = pool.calloc_type::<ngx_buf_t>();
+        if buf.is_null() {
+            // SAFETY: the parts were obtained from the vector above
+            drop(unsafe { Vec::from_raw_parts_in(start, len, capacity, pool) });
+            return Err(AllocError);
+        }
+
+        if capacity == 0 {
+            // The vector pointer is dangling and must not be passed to nginx.
+            unsafe { (*buf).set_sync(1) };
+            return Ok(TemporaryBuffer(buf));
+        }
+
+        // SAFETY: the vector memory is valid for `capacity` bytes, with `len` initialized
+        unsafe {
+            (*buf).start = start;
+            (*buf).pos = start;
+            (*buf).last = start.add(len);
+            (*buf).end = start.add(capacity);
+            (*buf).set_temporary(1);
+        }
+
+        Ok(TemporaryBuffer(buf))
+    }
+
+    /// Creates a buffer over the contents of a pool-allocated string, without copying.
+    ///
+    /// See [`TemporaryBuffer::from_vec`].
+    #[cfg(feature = "alloc")]
+    pub fn from_string(data: NgxString<Pool>) -> Result<TemporaryBuffer, AllocError> {
+        let (start, len, capacity, pool) = data.into_raw_parts();
+        // SAFETY: the parts were obtained from the string's vector
+        Self::from_vec(unsafe { Vec::from_raw_parts_in(start, len, capacity, pool) })
+    }
 }
 
 impl Buffer for TemporaryBuffer {
@@ -95,12 +255,7 @@ impl Buffer for TemporaryBuffer {
     }
 }
 
-impl MutableBuffer for TemporaryBuffer {
-    /// Returns a mutable reference to the buffer contents as a byte slice.
-    fn as_bytes_mut(&mut self) -> &mut [u8] {
-        unsafe { slice::from_raw_parts_mut((*self.0).pos, self.len()) }
-    }
-}
+impl MutableBuffer for TemporaryBuffer {}
 
 /// Wrapper struct for a memory buffer, providing methods for working with an `ngx_buf_t`.
 pub struct MemoryBuffer(*mut ngx_buf_t);
@@ -127,3 +282,716 @@ impl Buffer for MemoryBuffer {
         self.0
     }
 }
+
+/// Wrapper for a borrowed [`ngx_buf_t`], providing methods for working with an nginx buffer.
+///
+/// See <https://nginx.org/en/docs/dev/development_guide.html#buffer>
+#[repr(transparent)]
+pub struct BufRef(ngx_buf_t);
+
+impl BufRef {
+    /// Creates a `BufRef` reference from an `ngx_buf_t` pointer.
+    ///
+    /// # Safety
+    ///
+    /// The caller must provide a valid non-null pointer to an `ngx_buf_t`, which remains valid and
+    /// is not modified by other means for the lifetime of the returned reference.
+    pub unsafe fn from_ptr<'a>(buf: *const ngx_buf_t) -> &'a Self {
+        &*buf.cast()
+    }
+
+    /// Creates a mutable `BufRef` reference from an `ngx_buf_t` pointer.
+    ///
+    /// # Safety
+    ///
+    /// The caller must provide a valid non-null pointer to an `ngx_buf_t`, which remains valid and
+    /// is not accessed by other means for the lifetime of the returned reference.
+    pub unsafe fn from_ptr_mut<'a>(buf: *mut ngx_buf_t) -> &'a mut Self {
+        &mut *buf.cast()
+    }
+
+    /// Creates a zero-length special buffer in the memory pool.
+    ///
+    /// See [`SpecialBuf`] for the supported kinds.
+    pub fn special(pool: &Pool, kind: SpecialBuf) -> Result<&mut Self, AllocError> {
+        let pool: *const ngx_pool_t = pool.as_ref();
+        // SAFETY: the pool is not accessed concurrently, as nginx pools are single-threaded
+        let buf = unsafe { ngx_pcalloc(pool.cast_mut(), mem::size_of::<ngx_buf_t>()) };
+        if buf.is_null() {
+            return Err(AllocError);
+        }
+
+        let buf = unsafe { Self::from_ptr_mut(buf.cast()) };
+        match kind {
+            SpecialBuf::Flush => buf.set_flush(true),
+            SpecialBuf::Sync => buf.set_sync(true),
+            SpecialBuf::LastBuf => buf.set_last_buf(true),
+            SpecialBuf::LastInChain => buf.set_last_in_chain(true),
+        }
+
+        Ok(buf)
+    }
+
+    /// Returns the buffer contents as a mutable byte slice, if the buffer is `temporary`.
+    ///
+    /// Other buffers, e.g. `memory` or `mmap` ones, may alias static data or cached file contents
+    /// and must not be modified.
+    pub fn as_bytes_mut(&mut self) -> Option<&mut [u8]> {
+        if self.0.temporary() == 0 {
+            return None;
+        }
+
+        let len = self.len();
+        if len == 0 {
+            return Some(&mut []);
+        }
+
+        // SAFETY: the memory between `pos` and `last` of a temporary buffer is writable
+        Some(unsafe { slice::from_raw_parts_mut(self.0.pos, len) })
+    }
+
+    /// Returns `true` if the buffer contents are stored in a file, e.g. when a request body was
+    /// spilled to a temporary file.
+    ///
+    /// Such buffers may have no memory contents, and the data should be read with
+    /// [`BufRef::read_file`].
+    pub fn in_file(&self) -> bool {
+        self.0.in_file() != 0 && !self.0.file.is_null()
+    }
+
+    /// Returns the range of the buffer contents in the file, or an empty range if the buffer is
+    /// not file-backed.
+    pub fn file_range(&self) -> Range<off_t> {
+        if self.in_file() {
+            self.0.file_pos..self.0.file_last
+        } else {
+            0..0
+        }
+    }
+
+    /// Reads the file-backed buffer contents starting at `offset` from the beginning of
+    /// [`BufRef::file_range`].
+    ///
+    /// Returns the number of bytes read, which is `0` at the end of the range, or `NGX_ERROR` if
+    /// the buffer is not file-backed or the read fails. The error is logged by nginx.
+    pub fn read_file(&self, offset: usize, dst: &mut [u8]) -> Result<usize, Status> {
+        if !self.in_file() {
+            return Err(Status::NGX_ERROR);
+        }
+
+        let range = self.file_range();
+        let pos = range.start.saturating_add(offset as off_t);
+        let size = dst.len().min(range.end.saturating_sub(pos).max(0) as usize);
+        if size == 0 {
+            return Ok(0);
+        }
+
+        // SAFETY: the file of a file-backed buffer is a valid open file
+        let n = unsafe { ngx_read_file(self.0.file, dst.as_mut_ptr(), size, pos) };
+        usize::try_from(n).map_err(|_| Status::NGX_ERROR)
+    }
+}
+
+impl AsRef<ngx_buf_t> for BufRef {
+    fn as_ref(&self) -> &ngx_buf_t {
+        &self.0
+    }
+}
+
+impl AsMut<ngx_buf_t> for BufRef {
+    fn as_mut(&mut self) -> &mut ngx_buf_t {
+        &mut self.0
+    }
+}
+
+impl Buffer for BufRef {
+    fn as_ngx_buf(&self) -> *const ngx_buf_t {
+        &self.0
+    }
+
+    fn as_ngx_buf_mut(&mut self) -> *mut ngx_buf_t {
+        &mut self.0
+    }
+}
+
+impl fmt::Debug for BufRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let b = &self.0;
+
+        // Avoid the `Buffer::len` assertion for the buffers without memory contents.
+        let len = if b.pos.is_null() || b.last < b.pos {
+            0
+        } else {
+            usize::wrapping_sub(b.last as _, b.pos as _)
+        };
+
+        let flags = [
+            ("temporary", b.temporary()),
+            ("memory", b.memory()),
+            ("mmap", b.mmap()),
+            ("in_file", b.in_file()),
+            ("flush", b.flush()),
+            ("sync", b.sync()),
+            ("last_buf", b.last_buf()),
+            ("last_in_chain", b.last_in_chain()),
+        ];
+
+        let mut dbg = f.debug_struct("BufRef");
+        dbg.field("len", &len);
+        if b.in_file() != 0 {
+            dbg.field("file", &(b.file_pos..b.file_last));
+        }
+        dbg.field(
+            "flags",
+            &DebugFlags(flags.iter().filter(|(_, v)| *v != 0).map(|(k, _)| *k)),
+        )
+        .finish()
+    }
+}
+
+struct DebugFlags<I>(I);
+
+impl<I> fmt::Debug for DebugFlags<I>
+where
+    I: Iterator<Item = &'static str> + Clone,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.0.clone()).finish()
+    }
+}
+
+/// Wrapper for a borrowed buffer chain ([`ngx_chain_t`]), as passed to the body filters and
+/// output functions.
+///
+/// See <https://nginx.org/en/docs/dev/development_guide.html#buffer>
+#[repr(transparent)]
+pub struct Chain(ngx_chain_t);
+
+impl Chain {
+    /// Creates a `Chain` reference from a pointer to the first link of a buffer chain.
+    ///
+    /// # Safety
+    ///
+    /// The caller must provide a valid non-null pointer to an `ngx_chain_t`. All the links and
+    /// buffers reachable from it must remain valid for the lifetime of the returned reference.
+    pub unsafe fn from_ptr<'a>(cl: *const ngx_chain_t) -> &'a Self {
+        &*cl.cast()
+    }
+
+    /// Creates a mutable `Chain` reference from a pointer to the first link of a buffer chain.
+    ///
+    /// # Safety
+    ///
+    /// In addition to the requirements of [`Chain::from_ptr`], the links and buffers must not be
+    /// accessed by other means for the lifetime of the returned reference, and no buffer may be
+    /// referenced by more than one link.
+    pub unsafe fn from_ptr_mut<'a>(cl: *mut ngx_chain_t) -> &'a mut Self {
+        &mut *cl.cast()
+    }
+
+    /// Returns an iterator over the buffers in the chain.
+    pub fn iter(&self) -> ChainIter<'_> {
+        ChainIter {
+            link: &self.0,
+            _lifetime: PhantomData,
+        }
+    }
+
+    /// Returns an iterator over the buffers in the chain that allows modifying each buffer.
+    pub fn iter_mut(&mut self) -> ChainIterMut<'_> {
+        ChainIterMut {
+            link: &mut self.0,
+            _lifetime: PhantomData,
+        }
+    }
+
+    /// Returns `true` if any buffer in the chain has the `last_buf` flag set.
+    pub fn last_buf(&self) -> bool {
+        self.iter().any(|b| b.as_ref().last_buf() != 0)
+    }
+
+    /// Returns `true` if any buffer in the chain is backed by a file.
+    ///
+    /// The file contents are not included in [`Chain::total_len`].
+    pub fn in_file(&self) -> bool {
+        self.iter().any(BufRef::in_file)
+    }
+
+    /// Returns the total length of the in-memory contents of the buffers in the chain.
+    pub fn total_len(&self) -> usize {
+        self.iter().map(Buffer::len).sum()
+    }
+
+    /// Returns a reader over the in-memory contents of the buffers in the chain.
+    pub fn reader(&self) -> ChainReader<'_> {
+        ChainReader::new(self)
+    }
+}
+
+impl AsRef<ngx_chain_t> for Chain {
+    fn as_ref(&self) -> &ngx_chain_t {
+        &self.0
+    }
+}
+
+impl AsMut<ngx_chain_t> for Chain {
+    fn as_mut(&mut self) -> &mut ngx_chain_t {
+        &mut self.0
+    }
+}
+
+impl<'a> IntoIterator for &'a Chain {
+    type Item = &'a BufRef;
+    type IntoIter = ChainIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Chain {
+    type Item = &'a mut BufRef;
+    type IntoIter = ChainIterMut<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// Iterator over the buffers of a [`Chain`].
+///
+/// Links without a buffer are skipped.
+pub struct ChainIter<'a> {
+    link: *const ngx_chain_t,
+    _lifetime: PhantomData<&'a ngx_chain_t>,
+}
+
+impl<'a> Iterator for ChainIter<'a> {
+    type Item = &'a BufRef;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // SAFETY: the link is either null or a valid pointer within the chain
+            let link = unsafe { self.link.as_ref()? };
+            self.link = link.next;
+
+            if !link.buf.is_null() {
+                // SAFETY: non-null buffer pointers in a valid chain point to valid buffers
+                return Some(unsafe { BufRef::from_ptr(link.buf) });
+            }
+        }
+    }
+}
+
+/// Mutable iterator over the buffers of a [`Chain`].
+///
+/// Links without a buffer are skipped.
+pub struct ChainIterMut<'a> {
+    link: *mut ngx_chain_t,
+    _lifetime: PhantomData<&'a mut ngx_chain_t>,
+}
+
+impl<'a> Iterator for ChainIterMut<'a> {
+    type Item = &'a mut BufRef;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // SAFETY: the link is either null or a valid pointer within the chain
+            let link = unsafe { self.link.as_mut()? };
+            self.link = link.next;
+
+            if !link.buf.is_null() {
+                // SAFETY: non-null buffer pointers in a valid chain point to valid buffers and each
+                // buffer is only referenced once.
+                return Some(unsafe { BufRef::from_ptr_mut(link.buf) });
+            }
+        }
+    }
+}
+
+/// Builder for a chain of temporary pool buffers, coalescing small writes.
+///
+/// The data is copied into buffers of a fixed size, so that a stream of small frames results in a
+/// few full buffers instead of a buffer and a chain link per frame. The full buffers can be taken
+/// with [`ChainBuilder::take_full`] and passed to the output filters while the writes continue,
+/// and the rest of the data is returned by [`ChainBuilder::finish`].
+///
+/// ```rust,ignore
+/// let mut builder = ChainBuilder::new(&pool, 8192);
+///
+/// for frame in frames {
+///     builder.write(frame)?;
+///
+///     if let Some(out) = builder.take_full()? {
+///         request.output_filter(out.as_mut());
+///     }
+/// }
+///
+/// if let Some(out) = builder.finish(true)? {
+///     request.output_filter(out.as_mut());
+/// }
+/// ```
+///
+/// The buffers and the links are allocated from the pool and released with it.
+pub struct ChainBuilder<'a> {
+    pool: Pool,
+    buf_size: usize,
+    head: *mut ngx_chain_t,
+    tail: *mut ngx_chain_t,
+    current: *mut ngx_buf_t,
+    _lifetime: PhantomData<&'a Pool>,
+}
+
+impl<'a> ChainBuilder<'a> {
+    /// Creates a new builder allocating buffers of `buf_size` bytes from the pool.
+    ///
+    /// A zero `buf_size` is treated as `1`.
+    pub fn new(pool: &'a Pool, buf_size: usize) -> Self {
+        Self {
+            pool: pool.clone(),
+            buf_size: buf_size.max(1),
+            head: ptr::null_mut(),
+            tail: ptr::null_mut(),
+            current: ptr::null_mut(),
+            _lifetime: PhantomData,
+        }
+    }
+
+    /// Returns `true` if no data was written since the builder was created or the chain was
+    /// taken.
+    pub fn is_empty(&self) -> bool {
+        self.head.is_null() && self.current.is_null()
+    }
+
+    /// Copies the data to the buffers, allocating new buffers as the current one fills up.
+    pub fn write(&mut self, mut data: &[u8]) -> Result<(), AllocError> {
+        while !data.is_empty() {
+            if self.current.is_null() {
+                let mut buf = self.pool.create_buffer(self.buf_size).ok_or(AllocError)?;
+                self.current = buf.as_ngx_buf_mut();
+            }
+
+            // the current buffer is a temporary buffer allocated by the builder
+            let mut buf = TemporaryBuffer::from_ngx_buf(self.current);
+            let n = buf.append(data);
+            data = &data[n..];
+
+            if buf.remaining() == 0 {
+                self.link_current()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Takes the chain of the full buffers, keeping the partially filled buffer for the following
+    /// writes.
+    ///
+    /// Returns `None` if there are no full buffers.
+    pub fn take_full(&mut self) -> Option<&'a mut Chain> {
+        let head = mem::replace(&mut self.head, ptr::null_mut());
+        self.tail = ptr::null_mut();
+        // SAFETY: the links and the buffers are allocated by the builder and are no longer
+        // referenced by it
+        unsafe { head.as_mut().map(|cl| Chain::from_ptr_mut(cl)) }
+    }
+
+    /// Takes the chain of all the written data.
+    ///
+    /// If `last_buf` is set, the last buffer of the chain is marked with the `last_buf` and
+    /// `last_in_chain` flags, and an empty special buffer is added if no data was written.
+    /// Otherwise, returns `None` if there is no data.
+    pub fn finish(mut self, last_buf: bool) -> Result<Option<&'a mut Chain>, AllocError> {
+        if !self.current.is_null() {
+            self.link_current()?;
+        }
+
+        if last_buf {
+            if self.tail.is_null() {
+                let buf = BufRef::special(&self.pool, SpecialBuf::LastBuf)?;
+                self.current = buf.as_mut();
+                self.link_current()?;
+            }
+
+            // SAFETY: the tail link and its buffer are allocated by the builder
+            let buf = unsafe { &mut *(*self.tail).buf };
+            buf.set_last_buf(1);
+            buf.set_last_in_chain(1);
+        }
+
+        Ok(self.take_full())
+    }
+
+    /// Appends the current buffer to the chain of the full buffers.
+    fn link_current(&mut self) -> Result<(), AllocError> {
+        let cl = self.pool.calloc_type::<ngx_chain_t>();
+        if cl.is_null() {
+            return Err(AllocError);
+        }
+
+        // SAFETY: the link is allocated above and the tail is either null or a link allocated by
+        // the builder
+        unsafe {
+            (*cl).buf = mem::replace(&mut self.current, ptr::null_mut());
+            match self.tail.as_mut() {
+                Some(tail) => tail.next = cl,
+                None => self.head = cl,
+            }
+        }
+        self.tail = cl;
+
+        Ok(())
+    }
+}
+
+/// Reader over the in-memory contents of a buffer chain, e.g. a request body or an upstream
+/// response received in memory.
+///
+/// Implements [`std::io::Read`] and [`std::io::BufRead`], and [`bytes::Buf`] with the `bytes`
+/// feature. The buffers are not modified, and the contents of the file buffers are skipped, as in
+/// [`Chain::total_len`].
+///
+/// ```rust,ignore
+/// let body: serde_json::Value = serde_json::from_reader(ChainReader::new(chain))?;
+/// ```
+pub struct ChainReader<'a> {
+    iter: ChainIter<'a>,
+    chunk: &'a [u8],
+    remaining: usize,
+}
+
+impl<'a> ChainReader<'a> {
+    /// Creates a reader over the buffers of the chain.
+    pub fn new(chain: &'a Chain) -> Self {
+        let mut this = Self {
+            iter: chain.iter(),
+            chunk: &[],
+            remaining: chain.total_len(),
+        };
+        this.next_chunk();
+        this
+    }
+
+    /// Returns the number of bytes left to read.
+    pub fn len(&self) -> usize {
+        self.remaining
+    }
+
+    /// Returns `true` if all the contents were read.
+    pub fn is_empty(&self) -> bool {
+        self.remaining == 0
+    }
+
+    /// Skips `n` bytes, crossing the buffer boundaries as needed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is greater than the number of bytes left.
+    fn skip(&mut self, mut n: usize) {
+        assert!(n <= self.remaining, "advance past the end of the chain");
+        self.remaining -= n;
+
+        while n > 0 {
+            let len = n.min(self.chunk.len());
+            self.chunk = &self.chunk[len..];
+            n -= len;
+            self.next_chunk();
+        }
+    }
+
+    /// Moves to the next non-empty buffer if the current one is exhausted.
+    fn next_chunk(&mut self) {
+        while self.chunk.is_empty() {
+            match self.iter.next() {
+                Some(buf) => self.chunk = buf.as_bytes(),
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::io::Read for ChainReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = buf.len().min(self.chunk.len());
+        buf[..n].copy_from_slice(&self.chunk[..n]);
+        self.skip(n);
+        Ok(n)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::io::BufRead for ChainReader<'_> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        Ok(self.chunk)
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.skip(amt.min(self.chunk.len()))
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl bytes::Buf for ChainReader<'_> {
+    fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    fn chunk(&self) -> &[u8] {
+        self.chunk
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        self.skip(cnt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+    use alloc::format;
+    use core::mem;
+
+    use super::*;
+
+    #[test]
+    fn test_chain() {
+        let mut data = *b"Hello world!";
+        let mut bufs: [ngx_buf_t; 2] = unsafe { mem::zeroed() };
+        let p = data.as_mut_ptr();
+        unsafe {
+            bufs[0].pos = p;
+            bufs[0].last = p.add(6);
+            bufs[1].pos = p.add(6);
+            bufs[1].last = p.add(12);
+        }
+        bufs[1].set_last_buf(1);
+
+        let mut links: [ngx_chain_t; 3] = unsafe { mem::zeroed() };
+        links[0].buf = ptr::addr_of_mut!(bufs[0]);
+        links[0].next = ptr::addr_of_mut!(links[1]);
+        // links[1] has no buffer
+        links[1].next = ptr::addr_of_mut!(links[2]);
+        links[2].buf = ptr::addr_of_mut!(bufs[1]);
+
+        let chain = unsafe { Chain::from_ptr(&links[0]) };
+        assert_eq!(chain.iter().count(), 2);
+        assert_eq!(chain.total_len(), 12);
+        assert!(chain.last_buf());
+        assert!(!chain.in_file());
+
+        let mut it = chain.iter();
+        assert_eq!(it.next().map(Buffer::as_bytes), Some(&b"Hello "[..]));
+        assert_eq!(it.next().map(Buffer::as_bytes), Some(&b"world!"[..]));
+        assert!(it.next().is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_chain_reader() {
+        use std::io::Read;
+
+        let mut data = *b"Hello world!";
+        let mut bufs: [ngx_buf_t; 3] = unsafe { mem::zeroed() };
+        let p = data.as_mut_ptr();
+        unsafe {
+            bufs[0].pos = p;
+            bufs[0].last = p.add(4);
+            // bufs[1] is empty
+            bufs[2].pos = p.add(4);
+            bufs[2].last = p.add(12);
+        }
+
+        let mut links: [ngx_chain_t; 3] = unsafe { mem::zeroed() };
+        links[0].buf = ptr::addr_of_mut!(bufs[0]);
+        links[0].next = ptr::addr_of_mut!(links[1]);
+        links[1].buf = ptr::addr_of_mut!(bufs[1]);
+        links[1].next = ptr::addr_of_mut!(links[2]);
+        links[2].buf = ptr::addr_of_mut!(bufs[2]);
+
+        let chain = unsafe { Chain::from_ptr(&links[0]) };
+        let mut reader = chain.reader();
+        assert_eq!(reader.len(), 12);
+
+        let mut out = [0u8; 6];
+        reader.read_exact(&mut out).unwrap();
+        assert_eq!(&out, b"Hello ");
+        assert_eq!(reader.len(), 6);
+
+        let mut rest = std::vec::Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, b"world!");
+        assert!(reader.is_empty());
+        assert_eq!(reader.read(&mut out).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_buf_debug() {
+        let mut data = *b"Hello";
+        let mut buf: ngx_buf_t = unsafe { mem::zeroed() };
+
+        let dbg = format!("{:?}", unsafe { BufRef::from_ptr(&buf) });
+        assert_eq!(dbg, "BufRef { len: 0, flags: {} }");
+
+        buf.pos = data.as_mut_ptr();
+        buf.last = unsafe { buf.pos.add(data.len()) };
+        buf.set_memory(1);
+        buf.set_last_buf(1);
+
+        let dbg = format!("{:?}", unsafe { BufRef::from_ptr(&buf) });
+        assert_eq!(dbg, r#"BufRef { len: 5, flags: {"memory", "last_buf"} }"#);
+    }
+
+    #[test]
+    fn test_append() {
+        let mut data = [0u8; 8];
+        let mut raw: ngx_buf_t = unsafe { mem::zeroed() };
+        let mut buf = TemporaryBuffer::from_ngx_buf(&mut raw);
+        assert_eq!(buf.append(b"ignored"), 0);
+
+        let range = data.as_mut_ptr_range();
+        (raw.start, raw.pos, raw.last, raw.end) =
+            (range.start, range.start, range.start, range.end);
+        raw.set_temporary(1);
+
+        let mut buf = TemporaryBuffer::from_ngx_buf(&mut raw);
+        assert_eq!(buf.capacity(), 8);
+        assert_eq!(buf.append(b"Hello"), 5);
+        assert_eq!(buf.remaining(), 3);
+        assert_eq!(buf.append(b" world"), 3);
+        assert_eq!(buf.remaining(), 0);
+        assert_eq!(buf.as_bytes(), b"Hello wo");
+    }
+
+    #[test]
+    fn test_special() {
+        let mut buf: ngx_buf_t = unsafe { mem::zeroed() };
+        let buf = unsafe { BufRef::from_ptr_mut(&mut buf) };
+        assert!(!buf.is_special());
+
+        buf.set_flush(true);
+        assert!(buf.is_special());
+        assert!(buf.as_bytes().is_empty());
+        assert!(buf.as_bytes_mut().is_none());
+
+        buf.as_mut().set_memory(1);
+        assert!(!buf.is_special());
+    }
+
+    #[test]
+    fn test_as_bytes_mut() {
+        let mut data = *b"Hello";
+        let mut buf: ngx_buf_t = unsafe { mem::zeroed() };
+        buf.pos = data.as_mut_ptr();
+        buf.last = unsafe { buf.pos.add(data.len()) };
+        buf.set_memory(1);
+
+        let buf = unsafe { BufRef::from_ptr_mut(&mut buf) };
+        assert!(buf.as_bytes_mut().is_none());
+
+        buf.as_mut().set_memory(0);
+        buf.as_mut().set_temporary(1);
+        buf.as_bytes_mut().unwrap().make_ascii_uppercase();
+        assert_eq!(buf.as_bytes(), b"HELLO");
+    }
+}
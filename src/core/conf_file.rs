@@ -0,0 +1,485 @@
+use core::ffi::{c_char, c_void};
+use core::fmt;
+use core::mem::MaybeUninit;
+use core::ptr;
+use core::time::Duration;
+
+use crate::core::{NgxStr, Pool, Status, NGX_CONF_ERROR, NGX_CONF_OK};
+use crate::ffi::{
+    ngx_command_t, ngx_conf_log_error, ngx_conf_parse, ngx_conf_t, ngx_int_t, ngx_log_t,
+    ngx_msec_t, ngx_parse_offset, ngx_parse_size, ngx_parse_time, ngx_str_t, ngx_uint_t, off_t,
+    NGX_ERROR,
+};
+use crate::log::{write_fmt, LOG_BUFFER_SIZE};
+
+/// Wrapper for a borrowed [`ngx_conf_t`], the configuration parser state passed to the directive
+/// handlers.
+///
+/// See <https://nginx.org/en/docs/dev/development_guide.html#config_directives>
+#[repr(transparent)]
+pub struct NgxConfRef(ngx_conf_t);
+
+impl NgxConfRef {
+    /// Creates an `NgxConfRef` reference from an `ngx_conf_t` pointer.
+    ///
+    /// # Safety
+    ///
+    /// The caller must provide a valid non-null pointer to an `ngx_conf_t`, which remains valid
+    /// for the lifetime of the returned reference.
+    pub unsafe fn from_ptr<'a>(cf: *const ngx_conf_t) -> &'a Self {
+        &*cf.cast()
+    }
+
+    /// Creates a mutable `NgxConfRef` reference from an `ngx_conf_t` pointer.
+    ///
+    /// # Safety
+    ///
+    /// The caller must provide a valid non-null pointer to an `ngx_conf_t`, which remains valid
+    /// and is not accessed by other means for the lifetime of the returned reference.
+    pub unsafe fn from_ptr_mut<'a>(cf: *mut ngx_conf_t) -> &'a mut Self {
+        &mut *cf.cast()
+    }
+
+    /// Returns a raw pointer to the configuration parser state.
+    pub fn as_ptr(&self) -> *mut ngx_conf_t {
+        core::ptr::from_ref(&self.0).cast_mut()
+    }
+
+    /// Returns the configuration pool.
+    pub fn pool(&self) -> Pool {
+        // SAFETY: configuration parser state always has a valid pool
+        unsafe { Pool::from_ngx_pool(self.0.pool) }
+    }
+
+    /// Returns a pointer to the configuration log.
+    pub fn log(&self) -> *mut ngx_log_t {
+        self.0.log
+    }
+
+    /// Writes a message at the specified level, with the context of the currently processed
+    /// configuration file.
+    ///
+    /// ```ignore
+    /// cf.log_error(NGX_LOG_EMERG, format_args!("invalid value \"{}\"", value));
+    /// ```
+    pub fn log_error(&self, level: u32, args: fmt::Arguments<'_>) {
+        let level = level as ngx_uint_t;
+        // SAFETY: configuration parser state always has a valid log
+        if level > unsafe { (*self.0.log).log_level } {
+            return;
+        }
+
+        let mut buf = [const { MaybeUninit::<u8>::uninit() }; LOG_BUFFER_SIZE];
+        let message = write_fmt(&mut buf, args);
+        let mut message = ngx_str_t {
+            data: message.as_ptr().cast_mut(),
+            len: message.len(),
+        };
+
+        unsafe { ngx_conf_log_error(level, self.as_ptr(), 0, c"%V".as_ptr(), &mut message) };
+    }
+
+    /// Returns the arguments of the current directive, including the directive name.
+    pub fn args(&self) -> &[ngx_str_t] {
+        match unsafe { self.0.args.as_ref() } {
+            // SAFETY: the arguments array always contains `ngx_str_t` elements
+            Some(args) => unsafe { args.as_slice() },
+            None => &[],
+        }
+    }
+
+    /// Returns the `n`-th argument of the current directive.
+    ///
+    /// The argument `0` is the directive name.
+    pub fn arg(&self, n: usize) -> Option<&NgxStr> {
+        self.args()
+            .get(n)
+            .map(|x| unsafe { NgxStr::from_ngx_str(*x) })
+    }
+
+    /// Parses the `n`-th argument of the current directive.
+    ///
+    /// Returns `None` if the argument is missing or cannot be parsed as `T`.
+    pub fn arg_parse<T: FromNgxStr>(&self, n: usize) -> Option<T> {
+        T::from_ngx_str(self.arg(n)?)
+    }
+
+    /// Parses the contents of the current block directive, calling `handler` for each directive
+    /// in the block.
+    ///
+    /// The parser state is saved before parsing and restored afterwards, as in the block handlers
+    /// of the nginx modules. Within the `handler`, [`args`](Self::args) returns the arguments of
+    /// the nested directive. Returning anything but `NGX_OK` from the handler stops the parsing.
+    ///
+    /// ```ignore
+    /// extern "C" fn cmd_block(cf: *mut ngx_conf_t, _: *mut ngx_command_t, conf: *mut c_void)
+    ///     -> *mut c_char
+    /// {
+    ///     let cf = unsafe { NgxConfRef::from_ptr_mut(cf) };
+    ///     let rc = cf.parse_block(|cf| match cf.arg(0) { ... });
+    ///     if rc == Status::NGX_OK { NGX_CONF_OK } else { NGX_CONF_ERROR }
+    /// }
+    /// ```
+    pub fn parse_block<F>(&mut self, mut handler: F) -> Status
+    where
+        F: FnMut(&mut NgxConfRef) -> Status,
+    {
+        let saved = self.0;
+
+        self.0.handler = Some(parse_block_handler::<F>);
+        self.0.handler_conf = ptr::from_mut(&mut handler).cast();
+
+        // SAFETY: the parser state is valid, and a NULL file name continues parsing the current
+        // block
+        let rv = unsafe { ngx_conf_parse(&mut self.0, ptr::null_mut()) };
+
+        self.0 = saved;
+
+        if rv == NGX_CONF_OK {
+            Status::NGX_OK
+        } else {
+            Status::NGX_ERROR
+        }
+    }
+}
+
+unsafe extern "C" fn parse_block_handler<F>(
+    cf: *mut ngx_conf_t,
+    _dummy: *mut ngx_command_t,
+    _conf: *mut c_void,
+) -> *mut c_char
+where
+    F: FnMut(&mut NgxConfRef) -> Status,
+{
+    // SAFETY: `handler_conf` was set by `NgxConfRef::parse_block` and points to the handler, which
+    // outlives the parsing
+    let handler = &mut *(*cf).handler_conf.cast::<F>();
+
+    if handler(NgxConfRef::from_ptr_mut(cf)) == Status::NGX_OK {
+        NGX_CONF_OK
+    } else {
+        NGX_CONF_ERROR
+    }
+}
+
+impl AsRef<ngx_conf_t> for NgxConfRef {
+    fn as_ref(&self) -> &ngx_conf_t {
+        &self.0
+    }
+}
+
+impl AsMut<ngx_conf_t> for NgxConfRef {
+    fn as_mut(&mut self) -> &mut ngx_conf_t {
+        &mut self.0
+    }
+}
+
+/// Parsing a value from a configuration argument, following the nginx syntax for the type.
+pub trait FromNgxStr: Sized {
+    /// Parses a value from the string, returning `None` if the string is not valid.
+    fn from_ngx_str(s: &NgxStr) -> Option<Self>;
+}
+
+/// A flag value, `on` or `off`.
+impl FromNgxStr for bool {
+    fn from_ngx_str(s: &NgxStr) -> Option<Self> {
+        if s.eq_ignore_ascii_case(b"on") {
+            Some(true)
+        } else if s.eq_ignore_ascii_case(b"off") {
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
+
+/// A time interval with an optional unit suffix, e.g. `500ms`, `30s` or `1h 30m`.
+///
+/// The value without a suffix is interpreted as seconds, similar to `ngx_conf_set_sec_slot`.
+impl FromNgxStr for Duration {
+    fn from_ngx_str(s: &NgxStr) -> Option<Self> {
+        parse_time(s, false).map(|ms| Duration::from_millis(ms as u64))
+    }
+}
+
+/// A size in bytes with an optional `k` or `m` suffix.
+///
+/// See `ngx_conf_set_size_slot`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NgxSize(pub usize);
+
+impl FromNgxStr for NgxSize {
+    fn from_ngx_str(s: &NgxStr) -> Option<Self> {
+        parse_size(s).map(NgxSize)
+    }
+}
+
+/// An offset in bytes with an optional `k`, `m` or `g` suffix.
+///
+/// See `ngx_conf_set_off_slot`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NgxOffset(pub off_t);
+
+impl FromNgxStr for NgxOffset {
+    fn from_ngx_str(s: &NgxStr) -> Option<Self> {
+        parse_offset(s).map(NgxOffset)
+    }
+}
+
+/// Parses a size in bytes with an optional `k` or `m` suffix, using `ngx_parse_size`.
+///
+/// Returns `None` if the string is not a valid size.
+pub fn parse_size(s: &NgxStr) -> Option<usize> {
+    let mut line = borrowed_ngx_str(s);
+    // `ngx_parse_size` does not modify the string
+    let size = unsafe { ngx_parse_size(&mut line) };
+    usize::try_from(size).ok()
+}
+
+/// Parses an offset in bytes with an optional `k`, `m` or `g` suffix, using `ngx_parse_offset`.
+///
+/// Returns `None` if the string is not a valid offset.
+pub fn parse_offset(s: &NgxStr) -> Option<off_t> {
+    let mut line = borrowed_ngx_str(s);
+    // `ngx_parse_offset` does not modify the string
+    let off = unsafe { ngx_parse_offset(&mut line) };
+    (off >= 0).then_some(off)
+}
+
+/// Parses a time interval with optional unit suffixes, e.g. `500ms`, `30s` or `1h 30m`, using
+/// `ngx_parse_time`.
+///
+/// The result is in milliseconds, or in seconds if `is_sec` is set. A value without a suffix is
+/// interpreted as seconds in both cases. Returns `None` if the string is not a valid interval,
+/// or if `is_sec` is set and the interval contains milliseconds.
+pub fn parse_time(s: &NgxStr, is_sec: bool) -> Option<ngx_msec_t> {
+    let mut line = borrowed_ngx_str(s);
+    // `ngx_parse_time` does not modify the string
+    let value = unsafe { ngx_parse_time(&mut line, is_sec as ngx_uint_t) };
+    if value == NGX_ERROR as ngx_int_t {
+        return None;
+    }
+    ngx_msec_t::try_from(value).ok()
+}
+
+/// Returns an `ngx_str_t` referencing the string, for the functions taking a mutable pointer.
+fn borrowed_ngx_str(s: &NgxStr) -> ngx_str_t {
+    ngx_str_t {
+        data: s.as_bytes().as_ptr().cast_mut(),
+        len: s.len(),
+    }
+}
+
+macro_rules! impl_from_ngx_str_int {
+    ($($t:ty),+) => {
+        $(
+            impl FromNgxStr for $t {
+                fn from_ngx_str(s: &NgxStr) -> Option<Self> {
+                    s.to_str().ok()?.parse().ok()
+                }
+            }
+        )+
+    };
+}
+
+impl_from_ngx_str_int!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+/// Value of an unset `ngx_uint_t` configuration field, see [`MergeField`].
+pub const NGX_CONF_UNSET_UINT: ngx_uint_t = ngx_uint_t::MAX;
+/// Value of an unset `size_t` configuration field, see [`MergeField`].
+pub const NGX_CONF_UNSET_SIZE: usize = usize::MAX;
+/// Value of an unset `ngx_msec_t` configuration field, see [`MergeField`].
+pub const NGX_CONF_UNSET_MSEC: ngx_msec_t = ngx_msec_t::MAX;
+/// Value of an unset pointer configuration field, see [`MergeField`].
+pub const NGX_CONF_UNSET_PTR: *mut c_void = ptr::null_mut::<c_void>().wrapping_offset(-1);
+
+/// A configuration value with a distinct "unset" state.
+///
+/// The fields are set to the unset value when the configuration is created, and an unset value
+/// is replaced with the value from the previous configuration level or with the default on merge,
+/// similar to the `ngx_conf_merge_*_value` macros. The integers use the `NGX_CONF_UNSET*`
+/// conventions: `-1` for the signed types and the maximum value for the unsigned ones, and the
+/// pointers use [`NGX_CONF_UNSET_PTR`].
+///
+/// The trait is used by the [`Merge`](crate::http::Merge) derive macro,
+/// [`conf_unset!`](crate::conf_unset), [`conf_init!`](crate::conf_init) and [`merge_value`].
+pub trait MergeField: Clone {
+    /// The unset value.
+    const UNSET: Self;
+
+    /// Returns `true` if the value was not set at the current configuration level.
+    fn is_unset(&self) -> bool;
+
+    /// Replaces an unset value with the value from the previous level.
+    fn merge_field(&mut self, prev: &Self) {
+        if self.is_unset() {
+            self.clone_from(prev);
+        }
+    }
+}
+
+impl<T: Clone> MergeField for Option<T> {
+    const UNSET: Self = None;
+
+    fn is_unset(&self) -> bool {
+        self.is_none()
+    }
+}
+
+impl MergeField for ngx_str_t {
+    const UNSET: Self = ngx_str_t {
+        len: 0,
+        data: ptr::null_mut(),
+    };
+
+    fn is_unset(&self) -> bool {
+        self.data.is_null()
+    }
+}
+
+#[cfg(feature = "std")]
+impl MergeField for std::string::String {
+    const UNSET: Self = std::string::String::new();
+
+    fn is_unset(&self) -> bool {
+        self.is_empty()
+    }
+}
+
+macro_rules! impl_merge_field_int {
+    ($unset:expr; $($t:ty),+) => {
+        $(
+            impl MergeField for $t {
+                const UNSET: Self = $unset;
+
+                fn is_unset(&self) -> bool {
+                    *self == Self::UNSET
+                }
+            }
+        )+
+    };
+}
+
+impl_merge_field_int!(-1; i8, i16, i32, i64, isize);
+impl_merge_field_int!(Self::MAX; u8, u16, u32, u64, usize);
+
+impl<T> MergeField for *mut T {
+    const UNSET: Self = ptr::null_mut::<T>().wrapping_byte_offset(-1);
+
+    fn is_unset(&self) -> bool {
+        *self == Self::UNSET
+    }
+}
+
+impl<T> MergeField for *const T {
+    const UNSET: Self = ptr::null::<T>().wrapping_byte_offset(-1);
+
+    fn is_unset(&self) -> bool {
+        *self == Self::UNSET
+    }
+}
+
+/// Sets the configuration fields to the unset value, as `conf->field = NGX_CONF_UNSET*` in the
+/// `create_*_conf` handlers.
+///
+/// ```rust,ignore
+/// ngx::conf_unset!(conf.enable, conf.timeout, conf.buffer_size);
+/// ```
+#[macro_export]
+macro_rules! conf_unset {
+    ($($field:expr),+ $(,)?) => {
+        $( $field = <_ as $crate::core::MergeField>::UNSET; )+
+    };
+}
+
+/// Replaces an unset configuration field with the default value, as `ngx_conf_init_value` and
+/// similar macros of nginx, e.g. in the `init_main_conf` handler.
+///
+/// ```rust,ignore
+/// ngx::conf_init!(conf.workers, 4);
+/// ```
+#[macro_export]
+macro_rules! conf_init {
+    ($field:expr, $default:expr) => {
+        if $crate::core::MergeField::is_unset(&$field) {
+            $field = $default;
+        }
+    };
+}
+
+/// Merges a configuration field with the value from the previous level, as
+/// `ngx_conf_merge_value`: an unset field is set to the previous value, or to the default if the
+/// previous value is unset as well.
+pub fn merge_value<T: MergeField>(conf: &mut T, prev: T, default: T) {
+    if conf.is_unset() {
+        *conf = if prev.is_unset() { default } else { prev };
+    }
+}
+
+/// Merges an `ngx_uint_t` configuration field, as `ngx_conf_merge_uint_value`.
+pub fn merge_uint_value(conf: &mut ngx_uint_t, prev: ngx_uint_t, default: ngx_uint_t) {
+    merge_value(conf, prev, default)
+}
+
+/// Merges a `size_t` configuration field, as `ngx_conf_merge_size_value`.
+pub fn merge_size_value(conf: &mut usize, prev: usize, default: usize) {
+    merge_value(conf, prev, default)
+}
+
+/// Merges an `ngx_msec_t` configuration field, as `ngx_conf_merge_msec_value`.
+pub fn merge_msec_value(conf: &mut ngx_msec_t, prev: ngx_msec_t, default: ngx_msec_t) {
+    merge_value(conf, prev, default)
+}
+
+/// Merges a pointer configuration field, as `ngx_conf_merge_ptr_value`.
+pub fn merge_ptr_value<T>(conf: &mut *mut T, prev: *mut T, default: *mut T) {
+    merge_value(conf, prev, default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_ngx_str() {
+        assert_eq!(bool::from_ngx_str(NgxStr::from_bytes(b"on")), Some(true));
+        assert_eq!(bool::from_ngx_str(NgxStr::from_bytes(b"OFF")), Some(false));
+        assert_eq!(bool::from_ngx_str(NgxStr::from_bytes(b"yes")), None);
+
+        assert_eq!(u32::from_ngx_str(NgxStr::from_bytes(b"42")), Some(42));
+        assert_eq!(u32::from_ngx_str(NgxStr::from_bytes(b"-1")), None);
+        assert_eq!(i32::from_ngx_str(NgxStr::from_bytes(b"-1")), Some(-1));
+    }
+
+    #[test]
+    fn test_conf_value() {
+        struct Conf {
+            enable: isize,
+            size: usize,
+            ptr: *mut c_void,
+        }
+
+        let mut conf = Conf {
+            enable: 0,
+            size: 0,
+            ptr: ptr::null_mut(),
+        };
+        crate::conf_unset!(conf.enable, conf.size, conf.ptr);
+        assert_eq!(conf.enable, -1);
+        assert_eq!(conf.size, NGX_CONF_UNSET_SIZE);
+        assert_eq!(conf.ptr, NGX_CONF_UNSET_PTR);
+
+        crate::conf_init!(conf.size, 16);
+        assert_eq!(conf.size, 16);
+        crate::conf_init!(conf.size, 32);
+        assert_eq!(conf.size, 16);
+
+        merge_value(&mut conf.enable, -1, 1);
+        assert_eq!(conf.enable, 1);
+        merge_value(&mut conf.enable, 0, 1);
+        assert_eq!(conf.enable, 1);
+
+        merge_ptr_value(&mut conf.ptr, ptr::null_mut(), NGX_CONF_UNSET_PTR);
+        assert!(conf.ptr.is_null());
+    }
+}
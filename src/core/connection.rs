@@ -0,0 +1,314 @@
+use core::fmt;
+use core::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use core::ptr;
+use core::time::Duration;
+
+use crate::core::{EventRef, NgxStr, Pool, Status};
+use crate::ffi::{
+    ngx_close_connection, ngx_connection_local_sockaddr, ngx_connection_t, ngx_err_t, ngx_int_t,
+    ngx_log_t, ngx_socket_errno, ngx_socket_t, ngx_tcp_nodelay, sockaddr, sockaddr_in,
+    sockaddr_in6, socklen_t, AF_INET, AF_INET6, NGX_OK,
+};
+#[cfg(any(ngx_os = "linux", ngx_os = "freebsd"))]
+use crate::ffi::{ngx_tcp_nopush, ngx_tcp_push};
+use crate::log::LogRef;
+
+/// Wrapper for a borrowed [`ngx_connection_t`], providing methods for working with client and
+/// upstream connections.
+///
+/// See <https://nginx.org/en/docs/dev/development_guide.html#connection>
+///
+/// The type is neither `Send` nor `Sync`: connections are reused from the per-worker free list
+/// and their events are only processed on the event loop thread.
+#[repr(transparent)]
+pub struct Connection(ngx_connection_t);
+
+impl Connection {
+    /// Creates a `Connection` reference from an `ngx_connection_t` pointer.
+    ///
+    /// # Safety
+    ///
+    /// The caller must provide a valid non-null pointer to an `ngx_connection_t`, which remains
+    /// valid for the lifetime of the returned reference.
+    pub unsafe fn from_ptr<'a>(c: *const ngx_connection_t) -> &'a Self {
+        &*c.cast()
+    }
+
+    /// Creates a mutable `Connection` reference from an `ngx_connection_t` pointer.
+    ///
+    /// # Safety
+    ///
+    /// The caller must provide a valid non-null pointer to an `ngx_connection_t`, which remains
+    /// valid and is not accessed by other means for the lifetime of the returned reference.
+    pub unsafe fn from_ptr_mut<'a>(c: *mut ngx_connection_t) -> &'a mut Self {
+        &mut *c.cast()
+    }
+
+    /// Returns a raw pointer to the connection.
+    pub fn as_ptr(&self) -> *mut ngx_connection_t {
+        core::ptr::from_ref(&self.0).cast_mut()
+    }
+
+    /// Returns the connection socket descriptor.
+    pub fn fd(&self) -> ngx_socket_t {
+        self.0.fd
+    }
+
+    /// Returns the address of the peer, i.e. the client for the accepted connections.
+    ///
+    /// Returns `None` for the address families other than IPv4 and IPv6, e.g. UNIX sockets.
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        if self.0.sockaddr.is_null() {
+            return None;
+        }
+        // SAFETY: `sockaddr` points to an address of `socklen` bytes
+        unsafe { sockaddr_to_socket_addr(self.0.sockaddr, self.0.socklen) }
+    }
+
+    /// Returns the local address of the connection.
+    ///
+    /// For the connections accepted on a wildcard address, the actual address is obtained with
+    /// `getsockname` on the first call and saved in the connection.
+    ///
+    /// Returns `None` if the address cannot be obtained, or for the address families other than
+    /// IPv4 and IPv6.
+    pub fn local_addr(&mut self) -> Option<SocketAddr> {
+        // SAFETY: the connection is valid; the address is allocated from the connection pool
+        let rc = unsafe { ngx_connection_local_sockaddr(&mut self.0, ptr::null_mut(), 0) };
+        if rc != NGX_OK as ngx_int_t || self.0.local_sockaddr.is_null() {
+            return None;
+        }
+        // SAFETY: `local_sockaddr` points to an address of `local_socklen` bytes
+        unsafe { sockaddr_to_socket_addr(self.0.local_sockaddr, self.0.local_socklen) }
+    }
+
+    /// Returns the connection pool.
+    pub fn pool(&self) -> Pool {
+        // SAFETY: an active connection always has a valid pool
+        unsafe { Pool::from_ngx_pool(self.0.pool) }
+    }
+
+    /// Returns the connection log.
+    pub fn log(&self) -> &LogRef {
+        // SAFETY: an active connection always has a valid log
+        unsafe { LogRef::from_ptr(self.0.log) }
+    }
+
+    /// Returns a raw pointer to the connection log.
+    pub fn log_ptr(&self) -> *mut ngx_log_t {
+        self.0.log
+    }
+
+    /// Returns `true` if an unrecoverable error has occurred on the connection.
+    pub fn error(&self) -> bool {
+        self.0.error() != 0
+    }
+
+    /// Marks the connection as failed, preventing further I/O on it.
+    pub fn set_error(&mut self) {
+        self.0.set_error(1)
+    }
+
+    /// Returns `true` if the connection was closed by a timeout.
+    pub fn timedout(&self) -> bool {
+        self.0.timedout() != 0
+    }
+
+    /// Returns `true` if the connection was destroyed.
+    pub fn destroyed(&self) -> bool {
+        self.0.destroyed() != 0
+    }
+
+    /// Returns the read event of the connection.
+    pub fn read_event(&mut self) -> &mut EventRef {
+        // SAFETY: an active connection always has valid read and write events
+        unsafe { EventRef::from_ptr_mut(self.0.read) }
+    }
+
+    /// Returns the write event of the connection.
+    pub fn write_event(&mut self) -> &mut EventRef {
+        // SAFETY: an active connection always has valid read and write events
+        unsafe { EventRef::from_ptr_mut(self.0.write) }
+    }
+
+    /// Sets a timeout for the read event, rearming the timer if it is already set.
+    ///
+    /// The read event handler is called with the `timedout` flag set when the timer expires.
+    pub fn set_read_timeout(&mut self, timeout: Duration) {
+        self.read_event().add_timer_duration(timeout)
+    }
+
+    /// Sets a timeout for the write event, rearming the timer if it is already set.
+    ///
+    /// The write event handler is called with the `timedout` flag set when the timer expires.
+    pub fn set_write_timeout(&mut self, timeout: Duration) {
+        self.write_event().add_timer_duration(timeout)
+    }
+
+    /// Deletes the read event timer, if any.
+    pub fn clear_read_timeout(&mut self) {
+        self.read_event().del_timer()
+    }
+
+    /// Deletes the write event timer, if any.
+    pub fn clear_write_timeout(&mut self) {
+        self.write_event().del_timer()
+    }
+
+    /// Receives data from the connection into the buffer.
+    ///
+    /// Returns the number of bytes received, `0` at the end of stream, `Err(NGX_AGAIN)` if the
+    /// socket is not ready, or `Err(NGX_ERROR)` on error.
+    pub fn recv(&mut self, buf: &mut [u8]) -> Result<usize, Status> {
+        let recv = self.0.recv.ok_or(Status::NGX_ERROR)?;
+        let n = unsafe { recv(&mut self.0, buf.as_mut_ptr(), buf.len()) };
+        io_result(n)
+    }
+
+    /// Sends data from the buffer to the connection.
+    ///
+    /// Returns the number of bytes sent, `Err(NGX_AGAIN)` if the socket is not ready, or
+    /// `Err(NGX_ERROR)` on error.
+    pub fn send(&mut self, buf: &[u8]) -> Result<usize, Status> {
+        let send = self.0.send.ok_or(Status::NGX_ERROR)?;
+        let n = unsafe { send(&mut self.0, buf.as_ptr().cast_mut(), buf.len()) };
+        io_result(n)
+    }
+
+    /// Enables `TCP_NODELAY` on the socket with `ngx_tcp_nodelay`, unless it is already enabled.
+    ///
+    /// The option is not set for the UNIX-domain sockets. The error is logged to the connection
+    /// log and returned as the socket error code.
+    pub fn tcp_nodelay(&mut self) -> Result<(), ngx_err_t> {
+        if unsafe { ngx_tcp_nodelay(&mut self.0) } != NGX_OK as ngx_int_t {
+            return Err(ngx_socket_errno());
+        }
+        Ok(())
+    }
+
+    /// Enables `TCP_CORK` on Linux or `TCP_NOPUSH` on FreeBSD, delaying the partial frames until
+    /// [`Connection::tcp_push`] is called.
+    ///
+    /// Does nothing if the option is already enabled, or if it was disabled for the connection.
+    ///
+    /// Only available on the systems where nginx implements `ngx_tcp_nopush`; elsewhere it is a
+    /// C macro without a symbol to call.
+    #[cfg(any(ngx_os = "linux", ngx_os = "freebsd"))]
+    pub fn tcp_nopush(&mut self) -> Result<(), ngx_err_t> {
+        if self.0.tcp_nopush() != NGX_TCP_NOPUSH_UNSET {
+            return Ok(());
+        }
+
+        if unsafe { ngx_tcp_nopush(self.0.fd) } == -1 {
+            return Err(ngx_socket_errno());
+        }
+
+        self.0.set_tcp_nopush(NGX_TCP_NOPUSH_SET);
+        Ok(())
+    }
+
+    /// Disables `TCP_CORK` or `TCP_NOPUSH` enabled by [`Connection::tcp_nopush`], sending the
+    /// pending partial frames.
+    #[cfg(any(ngx_os = "linux", ngx_os = "freebsd"))]
+    pub fn tcp_push(&mut self) -> Result<(), ngx_err_t> {
+        if self.0.tcp_nopush() != NGX_TCP_NOPUSH_SET {
+            return Ok(());
+        }
+
+        if unsafe { ngx_tcp_push(self.0.fd) } == -1 {
+            return Err(ngx_socket_errno());
+        }
+
+        self.0.set_tcp_nopush(NGX_TCP_NOPUSH_UNSET);
+        Ok(())
+    }
+
+    /// Closes the connection, releasing the socket and the associated events.
+    ///
+    /// The connection pool is not destroyed.
+    ///
+    /// # Safety
+    ///
+    /// The connection must not be accessed after this call, as the memory may be reused for
+    /// another connection.
+    pub unsafe fn close(&mut self) {
+        ngx_close_connection(&mut self.0)
+    }
+}
+
+impl AsRef<ngx_connection_t> for Connection {
+    fn as_ref(&self) -> &ngx_connection_t {
+        &self.0
+    }
+}
+
+impl AsMut<ngx_connection_t> for Connection {
+    fn as_mut(&mut self) -> &mut ngx_connection_t {
+        &mut self.0
+    }
+}
+
+impl fmt::Debug for Connection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let addr_text = if self.0.addr_text.data.is_null() {
+            NgxStr::from_bytes(&[])
+        } else {
+            unsafe { NgxStr::from_ngx_str(self.0.addr_text) }
+        };
+
+        f.debug_struct("Connection")
+            .field("fd", &self.0.fd)
+            .field("number", &self.0.number)
+            .field("addr_text", &addr_text)
+            .finish()
+    }
+}
+
+/// Converts a socket address to [SocketAddr].
+///
+/// Returns `None` for unsupported address families.
+///
+/// # Safety
+///
+/// `sa` must be a valid pointer to a socket address of at least `len` bytes.
+pub(crate) unsafe fn sockaddr_to_socket_addr(
+    sa: *const sockaddr,
+    len: socklen_t,
+) -> Option<SocketAddr> {
+    match (*sa).sa_family as u32 {
+        AF_INET if len as usize >= core::mem::size_of::<sockaddr_in>() => {
+            let sin = &*sa.cast::<sockaddr_in>();
+            let ip = Ipv4Addr::from(u32::from_be(sin.sin_addr.s_addr));
+            Some(SocketAddrV4::new(ip, u16::from_be(sin.sin_port)).into())
+        }
+        AF_INET6 if len as usize >= core::mem::size_of::<sockaddr_in6>() => {
+            let sin6 = &*sa.cast::<sockaddr_in6>();
+            // `in6_addr` layout is platform-specific, but always starts with 16 address bytes
+            let ip = Ipv6Addr::from(ptr::addr_of!(sin6.sin6_addr).cast::<[u8; 16]>().read());
+            Some(
+                SocketAddrV6::new(
+                    ip,
+                    u16::from_be(sin6.sin6_port),
+                    sin6.sin6_flowinfo,
+                    sin6.sin6_scope_id,
+                )
+                .into(),
+            )
+        }
+        _ => None,
+    }
+}
+
+// `ngx_connection_tcp_nopush_e` values.
+#[cfg(any(ngx_os = "linux", ngx_os = "freebsd"))]
+const NGX_TCP_NOPUSH_UNSET: u32 = 0;
+#[cfg(any(ngx_os = "linux", ngx_os = "freebsd"))]
+const NGX_TCP_NOPUSH_SET: u32 = 1;
+
+fn io_result(n: isize) -> Result<usize, Status> {
+    if n >= 0 {
+        Ok(n as usize)
+    } else {
+        Err(Status(n as ngx_int_t))
+    }
+}
@@ -0,0 +1,104 @@
+use core::ffi::c_void;
+
+use crate::core::Pool;
+use crate::ffi::{
+    ngx_cycle, ngx_cycle_t, ngx_log_t, ngx_process, ngx_uint_t, NGX_PROCESS_MASTER,
+    NGX_PROCESS_SINGLE, NGX_PROCESS_WORKER,
+};
+use crate::log::LogRef;
+
+/// Wrapper for a borrowed [`ngx_cycle_t`], the runtime context created from the configuration.
+///
+/// See <https://nginx.org/en/docs/dev/development_guide.html#cycle>
+#[repr(transparent)]
+pub struct Cycle(ngx_cycle_t);
+
+impl Cycle {
+    /// Returns the current cycle.
+    ///
+    /// The returned reference is invalidated by a configuration reload in the master process or
+    /// in a single-process mode. Avoid storing it outside of the objects tied to the cycle
+    /// lifetime, such as module configuration or connection/request data.
+    ///
+    /// The function may panic if you call it before the main() in nginx creates an initial cycle.
+    #[inline]
+    pub fn current() -> &'static Self {
+        let cycle = unsafe { ngx_cycle };
+        assert!(!cycle.is_null(), "current cycle");
+        unsafe { Self::from_ptr(cycle) }
+    }
+
+    /// Creates a `Cycle` reference from an `ngx_cycle_t` pointer.
+    ///
+    /// # Safety
+    ///
+    /// The caller must provide a valid non-null pointer to an `ngx_cycle_t`, which remains valid
+    /// for the lifetime of the returned reference.
+    pub unsafe fn from_ptr<'a>(cycle: *const ngx_cycle_t) -> &'a Self {
+        &*cycle.cast()
+    }
+
+    /// Returns a raw pointer to the cycle.
+    pub fn as_ptr(&self) -> *mut ngx_cycle_t {
+        core::ptr::from_ref(&self.0).cast_mut()
+    }
+
+    /// Returns the cycle log.
+    pub fn log(&self) -> &LogRef {
+        // SAFETY: a cycle always has a valid log
+        unsafe { LogRef::from_ptr(self.0.log) }
+    }
+
+    /// Returns a raw pointer to the cycle log.
+    pub fn log_ptr(&self) -> *mut ngx_log_t {
+        self.0.log
+    }
+
+    /// Returns the cycle pool.
+    ///
+    /// The memory allocated from this pool is released on configuration reload.
+    pub fn pool(&self) -> Pool {
+        // SAFETY: a cycle always has a valid pool
+        unsafe { Pool::from_ngx_pool(self.0.pool) }
+    }
+
+    /// Creates a new memory pool using the cycle log.
+    ///
+    /// See [`Pool::create_with_log`].
+    pub fn new_pool(&self, size: usize) -> Option<Pool> {
+        Pool::create_with_log(size, self.log())
+    }
+
+    /// Returns the array of the core module configurations, indexed by the module `index`.
+    pub fn conf_ctx(&self) -> *mut *mut *mut *mut c_void {
+        self.0.conf_ctx
+    }
+
+    /// Returns the number of connections configured with the `worker_connections` directive.
+    pub fn connection_n(&self) -> ngx_uint_t {
+        self.0.connection_n
+    }
+
+    /// Returns the number of unused connections.
+    pub fn free_connection_n(&self) -> ngx_uint_t {
+        self.0.free_connection_n
+    }
+
+    /// Returns `true` if the current process is a worker process, or nginx is running in a
+    /// single-process mode.
+    pub fn is_worker() -> bool {
+        let process = unsafe { ngx_process };
+        process == NGX_PROCESS_WORKER as ngx_uint_t || process == NGX_PROCESS_SINGLE as ngx_uint_t
+    }
+
+    /// Returns `true` if the current process is the master process.
+    pub fn is_master() -> bool {
+        unsafe { ngx_process == NGX_PROCESS_MASTER as ngx_uint_t }
+    }
+}
+
+impl AsRef<ngx_cycle_t> for Cycle {
+    fn as_ref(&self) -> &ngx_cycle_t {
+        &self.0
+    }
+}
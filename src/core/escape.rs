@@ -0,0 +1,166 @@
+use core::ptr;
+
+use crate::core::Pool;
+use crate::ffi::{
+    ngx_escape_html, ngx_escape_json, ngx_escape_uri, ngx_str_t, ngx_uint_t, ngx_unescape_uri,
+    u_char, NGX_ESCAPE_ARGS, NGX_ESCAPE_HTML, NGX_ESCAPE_MAIL_AUTH, NGX_ESCAPE_MEMCACHED,
+    NGX_ESCAPE_REFRESH, NGX_ESCAPE_URI, NGX_ESCAPE_URI_COMPONENT, NGX_UNESCAPE_REDIRECT,
+    NGX_UNESCAPE_URI,
+};
+
+/// The set of characters to escape with [`escape_uri`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EscapeKind {
+    /// A URI path, `NGX_ESCAPE_URI`.
+    Uri,
+    /// Query string arguments, `NGX_ESCAPE_ARGS`.
+    Args,
+    /// A single URI component, e.g. an argument value, `NGX_ESCAPE_URI_COMPONENT`.
+    UriComponent,
+    /// A URI in an HTML attribute, `NGX_ESCAPE_HTML`.
+    Html,
+    /// A URI in the `Refresh` header, `NGX_ESCAPE_REFRESH`.
+    Refresh,
+    /// A memcached key, `NGX_ESCAPE_MEMCACHED`.
+    Memcached,
+    /// A mail authentication parameter, `NGX_ESCAPE_MAIL_AUTH`.
+    MailAuth,
+}
+
+impl EscapeKind {
+    fn as_ngx_uint(self) -> ngx_uint_t {
+        (match self {
+            Self::Uri => NGX_ESCAPE_URI,
+            Self::Args => NGX_ESCAPE_ARGS,
+            Self::UriComponent => NGX_ESCAPE_URI_COMPONENT,
+            Self::Html => NGX_ESCAPE_HTML,
+            Self::Refresh => NGX_ESCAPE_REFRESH,
+            Self::Memcached => NGX_ESCAPE_MEMCACHED,
+            Self::MailAuth => NGX_ESCAPE_MAIL_AUTH,
+        }) as ngx_uint_t
+    }
+}
+
+/// The decoding mode of [`unescape_uri`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnescapeKind {
+    /// Decodes all escaped characters.
+    #[default]
+    All,
+    /// Decodes a URI path, stopping at the `?` character, `NGX_UNESCAPE_URI`.
+    Uri,
+    /// Decodes a redirect URI, leaving the unsafe characters escaped, `NGX_UNESCAPE_REDIRECT`.
+    Redirect,
+}
+
+impl UnescapeKind {
+    fn as_ngx_uint(self) -> ngx_uint_t {
+        (match self {
+            Self::All => 0,
+            Self::Uri => NGX_UNESCAPE_URI,
+            Self::Redirect => NGX_UNESCAPE_REDIRECT,
+        }) as ngx_uint_t
+    }
+}
+
+/// Escapes the URI characters with `ngx_escape_uri`, allocating the result in the pool.
+///
+/// Returns `None` if the allocation fails.
+pub fn escape_uri(pool: &Pool, src: &[u8], kind: EscapeKind) -> Option<ngx_str_t> {
+    let src_ptr = src.as_ptr().cast_mut();
+    let kind = kind.as_ngx_uint();
+
+    // SAFETY: `ngx_escape_uri` only counts the characters if `dst` is NULL, and does not modify
+    // the source
+    let n = unsafe { ngx_escape_uri(ptr::null_mut(), src_ptr, src.len(), kind) };
+    let len = src.len() + 2 * n;
+
+    escape_into(pool, src, len, |dst| unsafe {
+        ngx_escape_uri(dst, src_ptr, src.len(), kind);
+    })
+}
+
+/// Decodes the escaped URI characters with `ngx_unescape_uri`, allocating the result in the pool.
+///
+/// Returns `None` if the allocation fails.
+pub fn unescape_uri(pool: &Pool, src: &[u8], kind: UnescapeKind) -> Option<ngx_str_t> {
+    if src.is_empty() {
+        return Some(ngx_str_t::empty());
+    }
+
+    let data = pool.alloc_unaligned(src.len()).cast::<u_char>();
+    if data.is_null() {
+        return None;
+    }
+
+    let mut dst = data;
+    let mut s = src.as_ptr().cast_mut();
+    // SAFETY: the destination buffer is large enough, as the result is never longer than the
+    // source. The source is not modified.
+    unsafe { ngx_unescape_uri(&mut dst, &mut s, src.len(), kind.as_ngx_uint()) };
+
+    Some(ngx_str_t {
+        data,
+        len: dst as usize - data as usize,
+    })
+}
+
+/// Escapes the `<`, `>`, `&` and `"` characters with HTML entities using `ngx_escape_html`,
+/// allocating the result in the pool.
+///
+/// Returns `None` if the allocation fails.
+pub fn escape_html(pool: &Pool, src: &[u8]) -> Option<ngx_str_t> {
+    let src_ptr = src.as_ptr().cast_mut();
+
+    // SAFETY: `ngx_escape_html` only calculates the additional length if `dst` is NULL
+    let n = unsafe { ngx_escape_html(ptr::null_mut(), src_ptr, src.len()) };
+    let len = src.len() + n;
+
+    escape_into(pool, src, len, |dst| unsafe {
+        ngx_escape_html(dst, src_ptr, src.len());
+    })
+}
+
+/// Escapes a JSON string value with `ngx_escape_json`, allocating the result in the pool.
+///
+/// The surrounding quotes are not added.
+///
+/// Returns `None` if the allocation fails.
+pub fn escape_json(pool: &Pool, src: &[u8]) -> Option<ngx_str_t> {
+    let src_ptr = src.as_ptr().cast_mut();
+
+    // SAFETY: `ngx_escape_json` only calculates the additional length if `dst` is NULL
+    let n = unsafe { ngx_escape_json(ptr::null_mut(), src_ptr, src.len()) };
+    let len = src.len() + n;
+
+    escape_into(pool, src, len, |dst| unsafe {
+        ngx_escape_json(dst, src_ptr, src.len());
+    })
+}
+
+/// Allocates `len` bytes in the pool and fills them with `escape`, or with a copy of `src` if no
+/// characters need escaping.
+fn escape_into(
+    pool: &Pool,
+    src: &[u8],
+    len: usize,
+    escape: impl FnOnce(*mut u_char),
+) -> Option<ngx_str_t> {
+    if len == 0 {
+        return Some(ngx_str_t::empty());
+    }
+
+    let data = pool.alloc_unaligned(len).cast::<u_char>();
+    if data.is_null() {
+        return None;
+    }
+
+    if len == src.len() {
+        // SAFETY: the allocated buffer has `len` bytes
+        unsafe { ptr::copy_nonoverlapping(src.as_ptr(), data, len) };
+    } else {
+        escape(data);
+    }
+
+    Some(ngx_str_t { data, len })
+}
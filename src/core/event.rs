@@ -0,0 +1,92 @@
+use core::time::Duration;
+
+use crate::ffi::{
+    ngx_add_timer, ngx_current_msec, ngx_del_timer, ngx_event_t, ngx_msec_int_t, ngx_msec_t,
+};
+
+/// Maximum duration that can be achieved using [ngx_add_timer].
+pub(crate) const NGX_TIMER_DURATION_MAX: Duration = Duration::from_millis(ngx_msec_int_t::MAX as _);
+
+/// Wrapper for a borrowed [`ngx_event_t`], providing methods for working with event timers.
+///
+/// See <https://nginx.org/en/docs/dev/development_guide.html#events>
+///
+/// Timers and posted events are not synchronized, thus the type is neither `Send` nor `Sync`.
+#[repr(transparent)]
+pub struct EventRef(ngx_event_t);
+
+impl EventRef {
+    /// Creates an `EventRef` reference from an `ngx_event_t` pointer.
+    ///
+    /// # Safety
+    ///
+    /// The caller must provide a valid non-null pointer to an `ngx_event_t`, which remains valid
+    /// for the lifetime of the returned reference.
+    pub unsafe fn from_ptr<'a>(ev: *const ngx_event_t) -> &'a Self {
+        &*ev.cast()
+    }
+
+    /// Creates a mutable `EventRef` reference from an `ngx_event_t` pointer.
+    ///
+    /// # Safety
+    ///
+    /// The caller must provide a valid non-null pointer to an `ngx_event_t`, which remains valid
+    /// and is not accessed by other means for the lifetime of the returned reference.
+    pub unsafe fn from_ptr_mut<'a>(ev: *mut ngx_event_t) -> &'a mut Self {
+        &mut *ev.cast()
+    }
+
+    /// Sets a timeout for the event, in milliseconds.
+    ///
+    /// A timer that is already set is rearmed.
+    pub fn add_timer(&mut self, msec: ngx_msec_t) {
+        unsafe { ngx_add_timer(&mut self.0, msec) }
+    }
+
+    /// Sets a timeout for the event.
+    ///
+    /// The duration is clamped to the maximum value supported by the nginx timers.
+    pub fn add_timer_duration(&mut self, duration: Duration) {
+        let msec = duration.min(NGX_TIMER_DURATION_MAX).as_millis() as ngx_msec_t;
+        self.add_timer(msec)
+    }
+
+    /// Deletes the timeout for the event, if any.
+    pub fn del_timer(&mut self) {
+        if self.timer_set() {
+            unsafe { ngx_del_timer(&mut self.0) }
+        }
+    }
+
+    /// Returns `true` if the event has a pending timer.
+    pub fn timer_set(&self) -> bool {
+        self.0.timer_set() != 0
+    }
+
+    /// Returns the time remaining until the timer expires, or `None` if no timer is set.
+    ///
+    /// The value is computed from the cached time and saturates to zero for expired timers that
+    /// have not been processed yet.
+    pub fn timer_remaining(&self) -> Option<Duration> {
+        if !self.timer_set() {
+            return None;
+        }
+
+        // SAFETY: the cached time is only updated from the event loop in the current thread
+        let now = unsafe { ngx_current_msec };
+        let diff = self.0.timer.key.wrapping_sub(now) as ngx_msec_int_t;
+        Some(Duration::from_millis(diff.max(0) as u64))
+    }
+}
+
+impl AsRef<ngx_event_t> for EventRef {
+    fn as_ref(&self) -> &ngx_event_t {
+        &self.0
+    }
+}
+
+impl AsMut<ngx_event_t> for EventRef {
+    fn as_mut(&mut self) -> &mut ngx_event_t {
+        &mut self.0
+    }
+}
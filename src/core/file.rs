@@ -0,0 +1,108 @@
+use core::fmt;
+
+use crate::allocator::AllocError;
+use crate::core::{NgxStr, Pool};
+use crate::ffi::{ngx_fd_t, ngx_file_t, ngx_str_t};
+use crate::log::LogRef;
+
+/// Wrapper for an [`ngx_file_t`], an open file used for the file-backed buffers.
+///
+/// The wrapper does not own the file descriptor. The caller is responsible for closing it, e.g.
+/// with the `ngx_pool_cleanup_file` pool cleanup handler, after the file buffers referencing it
+/// are sent.
+#[repr(transparent)]
+pub struct NgxFile(ngx_file_t);
+
+impl NgxFile {
+    /// Creates an `NgxFile` in the memory pool for an open file descriptor.
+    ///
+    /// The file name is only used in the error messages, and should be allocated from the same
+    /// pool or outlive it. The file keeps a reference to `log` for the error messages, so the log
+    /// object must outlive the pool as well.
+    pub fn create<'a>(
+        pool: &'a Pool,
+        fd: ngx_fd_t,
+        name: &'a NgxStr,
+        log: &LogRef,
+    ) -> Result<&'a mut Self, AllocError> {
+        let file = pool.calloc_type::<ngx_file_t>();
+        if file.is_null() {
+            return Err(AllocError);
+        }
+
+        // SAFETY: the zero-initialized file is allocated from the pool
+        let file = unsafe { &mut *file };
+        file.fd = fd;
+        file.name = ngx_str_t {
+            data: name.as_bytes().as_ptr().cast_mut(),
+            len: name.len(),
+        };
+        file.log = log.as_ptr();
+
+        Ok(unsafe { Self::from_ptr_mut(file) })
+    }
+
+    /// Creates an `NgxFile` reference from an `ngx_file_t` pointer.
+    ///
+    /// # Safety
+    ///
+    /// The caller must provide a valid non-null pointer to an `ngx_file_t`, which remains valid
+    /// for the lifetime of the returned reference.
+    pub unsafe fn from_ptr<'a>(file: *const ngx_file_t) -> &'a Self {
+        &*file.cast()
+    }
+
+    /// Creates a mutable `NgxFile` reference from an `ngx_file_t` pointer.
+    ///
+    /// # Safety
+    ///
+    /// The caller must provide a valid non-null pointer to an `ngx_file_t`, which remains valid
+    /// and is not accessed by other means for the lifetime of the returned reference.
+    pub unsafe fn from_ptr_mut<'a>(file: *mut ngx_file_t) -> &'a mut Self {
+        &mut *file.cast()
+    }
+
+    /// Returns a raw pointer to the file.
+    pub fn as_ptr(&self) -> *mut ngx_file_t {
+        core::ptr::from_ref(&self.0).cast_mut()
+    }
+
+    /// Returns the file descriptor.
+    pub fn fd(&self) -> ngx_fd_t {
+        self.0.fd
+    }
+
+    /// Returns the file name.
+    pub fn name(&self) -> &NgxStr {
+        if self.0.name.data.is_null() {
+            return NgxStr::from_bytes(&[]);
+        }
+        unsafe { NgxStr::from_ngx_str(self.0.name) }
+    }
+
+    /// Enables direct I/O for the file reads, see the `directio` directive.
+    pub fn set_directio(&mut self, directio: bool) {
+        self.0.set_directio(if directio { 1 } else { 0 })
+    }
+}
+
+impl AsRef<ngx_file_t> for NgxFile {
+    fn as_ref(&self) -> &ngx_file_t {
+        &self.0
+    }
+}
+
+impl AsMut<ngx_file_t> for NgxFile {
+    fn as_mut(&mut self) -> &mut ngx_file_t {
+        &mut self.0
+    }
+}
+
+impl fmt::Debug for NgxFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NgxFile")
+            .field("fd", &self.0.fd)
+            .field("name", &self.name())
+            .finish()
+    }
+}
@@ -0,0 +1,368 @@
+use core::cmp::Ordering;
+use core::ffi::{c_void, CStr};
+use core::mem;
+use core::ptr::{self, NonNull};
+use core::slice;
+
+use crate::core::{NgxStr, Pool, Status};
+use crate::ffi::{
+    ngx_dns_strcmp, ngx_hash_add_key, ngx_hash_combined_t, ngx_hash_find, ngx_hash_find_combined,
+    ngx_hash_init, ngx_hash_init_t, ngx_hash_key, ngx_hash_key_lc, ngx_hash_key_t,
+    ngx_hash_keys_array_init, ngx_hash_keys_arrays_t, ngx_hash_t, ngx_hash_wildcard_init,
+    ngx_hash_wildcard_t, ngx_int_t, ngx_murmur_hash2, ngx_str_t, ngx_uint_t, NGX_HASH_LARGE,
+    NGX_HASH_SMALL, NGX_HASH_WILDCARD_KEY, NGX_OK,
+};
+
+/// Calculates the CRC32 checksum of the data, matching `ngx_crc32_long` and `ngx_crc32_short`.
+///
+/// The result is consistent with the hashes calculated by nginx, e.g. for the `hash` directive of
+/// the upstream module.
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, &b| {
+        CRC32_TABLE[((crc ^ b as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Calculates the MurmurHash2 of the data with `ngx_murmur_hash2`.
+pub fn murmur_hash2(data: &[u8]) -> u32 {
+    // SAFETY: the function does not modify the data
+    unsafe { ngx_murmur_hash2(data.as_ptr().cast_mut(), data.len()) }
+}
+
+/// Calculates the hash table key of the data with `ngx_hash_key`.
+///
+/// The data is not converted to lowercase, unlike with `ngx_hash_key_lc`.
+pub fn hash_key(data: &[u8]) -> ngx_uint_t {
+    // SAFETY: the function does not modify the data
+    unsafe { ngx_hash_key(data.as_ptr().cast_mut(), data.len()) }
+}
+
+/// The lookup table for the reflected CRC32 polynomial, same as `ngx_crc32_table256`.
+static CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb88320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Parameters of a hash table, usually set with the `*_hash_max_size` and `*_hash_bucket_size`
+/// directives.
+///
+/// See <https://nginx.org/en/docs/hash.html>
+#[derive(Clone, Copy, Debug)]
+pub struct HashParams<'a> {
+    /// The table name, used in the error messages.
+    pub name: &'a CStr,
+    /// The maximum number of buckets.
+    pub max_size: ngx_uint_t,
+    /// The bucket size in bytes.
+    pub bucket_size: ngx_uint_t,
+}
+
+impl HashParams<'_> {
+    fn init(&self, pool: &mut Pool) -> ngx_hash_init_t {
+        // SAFETY: an all-zeroes `ngx_hash_init_t` is a valid initial state
+        let mut hinit: ngx_hash_init_t = unsafe { mem::zeroed() };
+        hinit.key = Some(ngx_hash_key_lc);
+        hinit.max_size = self.max_size;
+        hinit.bucket_size = self.bucket_size;
+        hinit.name = self.name.as_ptr().cast_mut();
+        hinit.pool = pool.as_mut();
+        hinit
+    }
+}
+
+/// A static hash table with case-insensitive string keys, built with `ngx_hash_init`.
+///
+/// The table is built once at configuration time and is read-only afterwards. The keys and
+/// buckets are allocated from the pool, and remain valid until the pool is destroyed.
+///
+/// See <https://nginx.org/en/docs/dev/development_guide.html#hash>
+pub struct HashTable(ngx_hash_t);
+
+impl HashTable {
+    /// Builds a hash table from the list of keys and values.
+    ///
+    /// The keys are converted to lowercase. `temp_pool` is only used for the duration of the
+    /// call, and may be destroyed afterwards.
+    pub fn new(
+        pool: &Pool,
+        temp_pool: &Pool,
+        params: &HashParams<'_>,
+        keys: &[(&NgxStr, NonNull<c_void>)],
+    ) -> Result<Self, Status> {
+        let mut pool = pool.clone();
+        let mut temp_pool = temp_pool.clone();
+
+        // SAFETY: an all-zeroes `ngx_hash_t` is a valid empty table
+        let mut hash: ngx_hash_t = unsafe { mem::zeroed() };
+        if keys.is_empty() {
+            return Ok(Self(hash));
+        }
+
+        let names = temp_pool
+            .alloc(keys.len() * mem::size_of::<ngx_hash_key_t>())
+            .cast::<ngx_hash_key_t>();
+        if names.is_null() {
+            return Err(Status::NGX_ERROR);
+        }
+
+        for (i, (key, value)) in keys.iter().enumerate() {
+            // `ngx_hash_init` copies the keys to the table and does not modify the source.
+            let key = ngx_str_t {
+                data: key.as_bytes().as_ptr().cast_mut(),
+                len: key.len(),
+            };
+            let key_hash = unsafe { ngx_hash_key_lc(key.data, key.len) };
+            unsafe {
+                names.add(i).write(ngx_hash_key_t {
+                    key,
+                    key_hash,
+                    value: value.as_ptr(),
+                })
+            };
+        }
+
+        let mut hinit = params.init(&mut pool);
+        hinit.hash = &mut hash;
+        hinit.temp_pool = temp_pool.as_mut();
+
+        if unsafe { ngx_hash_init(&mut hinit, names, keys.len() as ngx_uint_t) }
+            != NGX_OK as ngx_int_t
+        {
+            return Err(Status::NGX_ERROR);
+        }
+
+        Ok(Self(hash))
+    }
+
+    /// Returns the value for the key.
+    ///
+    /// The lookup is case-sensitive: the key must already be in lowercase to match.
+    pub fn find(&self, key: &[u8]) -> Option<NonNull<c_void>> {
+        if self.0.buckets.is_null() {
+            return None;
+        }
+
+        let name = key.as_ptr().cast_mut();
+        let value = unsafe {
+            let key_hash = ngx_hash_key(name, key.len());
+            ngx_hash_find(ptr::from_ref(&self.0).cast_mut(), key_hash, name, key.len())
+        };
+        NonNull::new(value)
+    }
+}
+
+impl AsRef<ngx_hash_t> for HashTable {
+    fn as_ref(&self) -> &ngx_hash_t {
+        &self.0
+    }
+}
+
+/// A hash table with exact and wildcard keys, built with `ngx_hash_init` and
+/// `ngx_hash_wildcard_init`.
+///
+/// Wildcard keys match the domain names as in the `server_name` or `map` directive with the
+/// `hostnames` parameter: `*.example.com` or `.example.com` match subdomains, and
+/// `www.example.*` matches the top-level domains. The exact matches have precedence, followed
+/// by the leading and the trailing wildcards.
+///
+/// Use [`HashKeys`] to build the table.
+pub struct CombinedHashTable(ngx_hash_combined_t);
+
+impl CombinedHashTable {
+    /// Returns the value for the key, trying the exact match first.
+    ///
+    /// The lookup is case-sensitive: the key must already be in lowercase to match.
+    pub fn find(&self, key: &[u8]) -> Option<NonNull<c_void>> {
+        let name = key.as_ptr().cast_mut();
+        let value = unsafe {
+            let key_hash = ngx_hash_key(name, key.len());
+            ngx_hash_find_combined(ptr::from_ref(&self.0).cast_mut(), key_hash, name, key.len())
+        };
+        NonNull::new(value)
+    }
+}
+
+impl AsRef<ngx_hash_combined_t> for CombinedHashTable {
+    fn as_ref(&self) -> &ngx_hash_combined_t {
+        &self.0
+    }
+}
+
+/// A collection of keys for a [`CombinedHashTable`], wrapping `ngx_hash_keys_arrays_t`.
+///
+/// ```ignore
+/// let mut keys = HashKeys::new(&cf.pool(), &temp_pool, false)?;
+/// keys.add(NgxStr::from_bytes(b"example.com"), value, false)?;
+/// keys.add(NgxStr::from_bytes(b"*.example.com"), value, true)?;
+/// let table = keys.build(&params)?;
+/// ```
+pub struct HashKeys {
+    keys: ngx_hash_keys_arrays_t,
+    pool: Pool,
+    temp_pool: Pool,
+}
+
+impl HashKeys {
+    /// Creates an empty key collection.
+    ///
+    /// `large` selects the preallocated size of the collection, `NGX_HASH_LARGE` or
+    /// `NGX_HASH_SMALL`. The keys are stored in `temp_pool`, which must outlive the collection.
+    pub fn new(pool: &Pool, temp_pool: &Pool, large: bool) -> Result<Self, Status> {
+        let mut pool = pool.clone();
+        let mut temp_pool = temp_pool.clone();
+
+        // SAFETY: an all-zeroes `ngx_hash_keys_arrays_t` is a valid initial state
+        let mut keys: ngx_hash_keys_arrays_t = unsafe { mem::zeroed() };
+        keys.pool = pool.as_mut();
+        keys.temp_pool = temp_pool.as_mut();
+
+        let kind = if large {
+            NGX_HASH_LARGE
+        } else {
+            NGX_HASH_SMALL
+        };
+        if unsafe { ngx_hash_keys_array_init(&mut keys, kind as ngx_uint_t) } != NGX_OK as ngx_int_t
+        {
+            return Err(Status::NGX_ERROR);
+        }
+
+        Ok(Self {
+            keys,
+            pool,
+            temp_pool,
+        })
+    }
+
+    /// Adds a key to the collection.
+    ///
+    /// The key is converted to lowercase.
+    ///
+    /// Returns `Err(NGX_BUSY)` for a duplicate key, and `Err(NGX_DECLINED)` for an invalid
+    /// wildcard key.
+    pub fn add(
+        &mut self,
+        key: &NgxStr,
+        value: NonNull<c_void>,
+        wildcard: bool,
+    ) -> Result<(), Status> {
+        // The collection references the key string until the table is built, and converts it to
+        // lowercase in place.
+        let data = self.temp_pool.alloc_unaligned(key.len()).cast::<u8>();
+        if data.is_null() {
+            return Err(Status::NGX_ERROR);
+        }
+        unsafe { ptr::copy_nonoverlapping(key.as_bytes().as_ptr(), data, key.len()) };
+
+        let mut name = ngx_str_t {
+            data,
+            len: key.len(),
+        };
+        let flags = if wildcard { NGX_HASH_WILDCARD_KEY } else { 0 };
+
+        match Status(unsafe {
+            ngx_hash_add_key(
+                &mut self.keys,
+                &mut name,
+                value.as_ptr(),
+                flags as ngx_uint_t,
+            )
+        }) {
+            Status::NGX_OK => Ok(()),
+            rc => Err(rc),
+        }
+    }
+
+    /// Builds the hash table from the collected keys.
+    pub fn build(mut self, params: &HashParams<'_>) -> Result<CombinedHashTable, Status> {
+        // SAFETY: an all-zeroes `ngx_hash_combined_t` is a valid empty table
+        let mut combined: ngx_hash_combined_t = unsafe { mem::zeroed() };
+
+        if self.keys.keys.nelts > 0 {
+            let mut hinit = params.init(&mut self.pool);
+            hinit.hash = &mut combined.hash;
+
+            if unsafe {
+                ngx_hash_init(&mut hinit, self.keys.keys.elts.cast(), self.keys.keys.nelts)
+            } != NGX_OK as ngx_int_t
+            {
+                return Err(Status::NGX_ERROR);
+            }
+        }
+
+        combined.wc_head = self.wildcard_init(params, WildcardKind::Head)?;
+        combined.wc_tail = self.wildcard_init(params, WildcardKind::Tail)?;
+
+        Ok(CombinedHashTable(combined))
+    }
+
+    fn wildcard_init(
+        &mut self,
+        params: &HashParams<'_>,
+        kind: WildcardKind,
+    ) -> Result<*mut ngx_hash_wildcard_t, Status> {
+        let array = match kind {
+            WildcardKind::Head => &mut self.keys.dns_wc_head,
+            WildcardKind::Tail => &mut self.keys.dns_wc_tail,
+        };
+        if array.nelts == 0 {
+            return Ok(ptr::null_mut());
+        }
+
+        // SAFETY: the array contains `nelts` initialized `ngx_hash_key_t` elements
+        let names = unsafe { slice::from_raw_parts_mut(array.elts.cast(), array.nelts) };
+        names.sort_unstable_by(cmp_dns_wildcards);
+
+        let mut hinit = params.init(&mut self.pool);
+        hinit.temp_pool = self.temp_pool.as_mut();
+
+        if unsafe { ngx_hash_wildcard_init(&mut hinit, names.as_mut_ptr(), names.len()) }
+            != NGX_OK as ngx_int_t
+        {
+            return Err(Status::NGX_ERROR);
+        }
+
+        // `ngx_hash_wildcard_init` allocates the table if `hinit.hash` is not set.
+        Ok(hinit.hash.cast())
+    }
+}
+
+enum WildcardKind {
+    Head,
+    Tail,
+}
+
+/// Orders the wildcard keys for `ngx_hash_wildcard_init`, as `ngx_http_map_cmp_dns_wildcards`.
+fn cmp_dns_wildcards(one: &ngx_hash_key_t, two: &ngx_hash_key_t) -> Ordering {
+    // The keys are null-terminated by `ngx_hash_add_key`.
+    unsafe { ngx_dns_strcmp(one.key.data.cast(), two.key.data.cast()) }.cmp(&0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+        assert_eq!(
+            crc32(b"The quick brown fox jumps over the lazy dog"),
+            0x414fa339
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use core::fmt;
+
+use crate::allocator::AllocError;
+use crate::collections::NgxArray;
+use crate::core::{NgxStr, Pool};
+use crate::ffi::{ngx_keyval_t, ngx_pool_t, ngx_str_t};
+
+/// Wrapper for an [`ngx_keyval_t`], a pair of strings used by the configuration directives such
+/// as `add_header` or `proxy_set_header`.
+///
+/// nginx stores the pairs in an `ngx_array_t`, available as `NgxArray<NgxKeyValue>`.
+#[derive(Clone, Copy)]
+#[repr(transparent)]
+pub struct NgxKeyValue(ngx_keyval_t);
+
+impl NgxKeyValue {
+    /// Creates a pair from the key and value, copying the strings into the pool.
+    pub fn new_in(pool: &Pool, key: &[u8], value: &[u8]) -> Result<Self, AllocError> {
+        Ok(Self(ngx_keyval_t {
+            key: copy_in(pool, key)?,
+            value: copy_in(pool, value)?,
+        }))
+    }
+
+    /// Creates an `NgxKeyValue` reference from an `ngx_keyval_t` pointer.
+    ///
+    /// # Safety
+    ///
+    /// The caller must provide a valid non-null pointer to an `ngx_keyval_t`, which remains
+    /// valid for the lifetime of the returned reference.
+    pub unsafe fn from_ptr<'a>(kv: *const ngx_keyval_t) -> &'a Self {
+        &*kv.cast()
+    }
+
+    /// Returns the key.
+    pub fn key(&self) -> &NgxStr {
+        unsafe { NgxStr::from_ngx_str(self.0.key) }
+    }
+
+    /// Returns the value.
+    pub fn value(&self) -> &NgxStr {
+        unsafe { NgxStr::from_ngx_str(self.0.value) }
+    }
+}
+
+impl AsRef<ngx_keyval_t> for NgxKeyValue {
+    fn as_ref(&self) -> &ngx_keyval_t {
+        &self.0
+    }
+}
+
+impl AsMut<ngx_keyval_t> for NgxKeyValue {
+    fn as_mut(&mut self) -> &mut ngx_keyval_t {
+        &mut self.0
+    }
+}
+
+impl From<ngx_keyval_t> for NgxKeyValue {
+    fn from(kv: ngx_keyval_t) -> Self {
+        Self(kv)
+    }
+}
+
+impl fmt::Debug for NgxKeyValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NgxKeyValue")
+            .field("key", &self.key())
+            .field("value", &self.value())
+            .finish()
+    }
+}
+
+impl NgxArray<NgxKeyValue> {
+    /// Appends a new pair to the array, copying the key and value into the pool.
+    pub fn push_pair(
+        &mut self,
+        pool: &Pool,
+        key: &[u8],
+        value: &[u8],
+    ) -> Result<&mut NgxKeyValue, AllocError> {
+        self.push(NgxKeyValue::new_in(pool, key, value)?)
+    }
+
+    /// Returns the value of the first pair with the specified key.
+    pub fn get_value(&self, key: impl AsRef<[u8]>) -> Option<&NgxStr> {
+        let key = key.as_ref();
+        self.iter()
+            .find(|kv| kv.key().as_bytes() == key)
+            .map(NgxKeyValue::value)
+    }
+}
+
+fn copy_in(pool: &Pool, bytes: &[u8]) -> Result<ngx_str_t, AllocError> {
+    let pool: *const ngx_pool_t = pool.as_ref();
+    // SAFETY: `Pool` wraps a valid pool
+    unsafe { ngx_str_t::from_bytes(pool.cast_mut(), bytes) }.ok_or(AllocError)
+}
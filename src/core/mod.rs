@@ -1,14 +1,46 @@
+mod base64;
 mod buffer;
+mod conf_file;
+mod connection;
+mod cycle;
+mod escape;
+mod event;
+mod file;
+mod hash;
+mod keyval;
 mod pool;
+#[cfg(ngx_feature = "pcre")]
+mod regex;
 pub mod slab;
 mod status;
 mod string;
+mod table;
+#[cfg(ngx_feature = "threads")]
+mod thread_pool;
+mod url;
+mod variable;
 
+pub use base64::*;
 pub use buffer::*;
+pub use conf_file::*;
+pub use connection::*;
+pub use cycle::*;
+pub use escape::*;
+pub use event::*;
+pub use file::*;
+pub use hash::*;
+pub use keyval::*;
 pub use pool::*;
-pub use slab::SlabPool;
+#[cfg(ngx_feature = "pcre")]
+pub use regex::*;
+pub use slab::{SharedZone, SlabPool};
 pub use status::*;
 pub use string::*;
+pub use table::*;
+#[cfg(ngx_feature = "threads")]
+pub use thread_pool::ThreadPool;
+pub use url::*;
+pub use variable::*;
 
 /// Gets an outer object pointer from a pointer to one of its fields.
 /// While there is no corresponding C macro, the pattern is common in the NGINX source.
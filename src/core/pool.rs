@@ -4,16 +4,33 @@ use core::mem;
 use core::ptr::{self, NonNull};
 
 use nginx_sys::{
-    ngx_buf_t, ngx_create_temp_buf, ngx_palloc, ngx_pcalloc, ngx_pfree, ngx_pmemalign, ngx_pnalloc,
-    ngx_pool_cleanup_add, ngx_pool_t, NGX_ALIGNMENT,
+    ngx_buf_t, ngx_create_pool, ngx_create_temp_buf, ngx_destroy_pool, ngx_palloc, ngx_pcalloc,
+    ngx_pfree, ngx_pmemalign, ngx_pnalloc, ngx_pool_cleanup_add, ngx_pool_large_t, ngx_pool_t,
+    NGX_ALIGNMENT, NGX_POOL_ALIGNMENT,
 };
 
 use crate::allocator::{dangling_for_layout, AllocError, Allocator};
-use crate::core::buffer::{Buffer, MemoryBuffer, TemporaryBuffer};
+#[cfg(feature = "alloc")]
+use crate::collections::Vec;
+use crate::core::buffer::{MemoryBuffer, MutableBuffer, TemporaryBuffer};
+#[cfg(feature = "alloc")]
+use crate::core::NgxString;
+use crate::log::LogRef;
+
+/// Minimal size of a pool, matching the `NGX_MIN_POOL_SIZE` macro.
+const NGX_MIN_POOL_SIZE: usize = (mem::size_of::<ngx_pool_t>()
+    + 2 * mem::size_of::<ngx_pool_large_t>())
+.next_multiple_of(NGX_POOL_ALIGNMENT as usize);
 
 /// Non-owning wrapper for an [`ngx_pool_t`] pointer, providing methods for working with memory pools.
 ///
 /// See <https://nginx.org/en/docs/dev/development_guide.html#pool>
+///
+/// Allocations from a pool are not synchronized, and the type is neither `Send` nor `Sync`.
+/// Shared memory zones use [`SlabPool`](crate::core::SlabPool) instead.
+///
+/// The allocation methods take a shared reference, like the [`Allocator`] implementation, so a
+/// pool borrowed from the owning object, e.g. a request, can be used directly.
 #[derive(Clone, Debug)]
 #[repr(transparent)]
 pub struct Pool(NonNull<ngx_pool_t>);
@@ -97,12 +114,86 @@ impl Pool {
         Pool(NonNull::new_unchecked(pool))
     }
 
+    /// Creates a new memory pool of the specified size.
+    ///
+    /// The `size` is rounded up to the minimal pool size supported by nginx. The pool keeps a
+    /// reference to `log`, so the log object must outlive the pool; the cycle log is a good
+    /// candidate for pools created outside of a request or connection.
+    ///
+    /// The pool is not destroyed automatically, see [`Pool::destroy`].
+    ///
+    /// Returns `None` if the allocation fails.
+    pub fn create_with_log(size: usize, log: &LogRef) -> Option<Pool> {
+        let size = size.max(NGX_MIN_POOL_SIZE);
+        let pool = unsafe { ngx_create_pool(size, log.as_ptr()) };
+        Some(Pool(NonNull::new(pool)?))
+    }
+
+    /// Destroys the pool, calling the cleanup handlers and releasing all the allocated memory.
+    ///
+    /// # Safety
+    ///
+    /// The pool must be owned by the caller, e.g. created with [`Pool::create_with_log`]. Any
+    /// pointers to the pool memory or copies of this `Pool` must not be used after this call.
+    pub unsafe fn destroy(self) {
+        ngx_destroy_pool(self.0.as_ptr())
+    }
+
+    /// Runs the closure with a temporary pool of the specified size, destroying the pool and
+    /// releasing all its memory when the closure returns or unwinds.
+    ///
+    /// This is useful for the intermediate allocations, e.g. while parsing, that would otherwise
+    /// stay in a long-lived pool until it is destroyed. The temporary pool uses the log of this
+    /// pool.
+    ///
+    /// The memory allocated from the temporary pool, as well as the `Pool` handle itself, must
+    /// not escape the closure: copy the results to this pool or to the owned memory instead.
+    ///
+    /// Returns `None` if the temporary pool cannot be created.
+    pub fn temp_scope<F, R>(&self, size: usize, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut Pool) -> R,
+    {
+        struct TempPool(Pool);
+
+        impl Drop for TempPool {
+            fn drop(&mut self) {
+                // SAFETY: the pool is owned by `temp_scope` and is not used after the closure
+                unsafe { ngx_destroy_pool(self.0 .0.as_ptr()) }
+            }
+        }
+
+        // SAFETY: a valid pool always has a valid log
+        let log = unsafe { LogRef::from_ptr(self.0.as_ref().log) };
+        let mut temp = TempPool(Self::create_with_log(size, log)?);
+
+        Some(f(&mut temp.0))
+    }
+
+    /// Creates an empty vector allocating from the memory pool.
+    ///
+    /// The vector can be converted to an output buffer without copying with
+    /// [`TemporaryBuffer::from_vec`].
+    #[cfg(feature = "alloc")]
+    pub fn vec<T>(&self) -> Vec<T, Pool> {
+        Vec::new_in(self.clone())
+    }
+
+    /// Creates an empty string allocating from the memory pool.
+    ///
+    /// The string can be converted to an output buffer without copying with
+    /// [`TemporaryBuffer::from_string`].
+    #[cfg(feature = "alloc")]
+    pub fn string(&self) -> NgxString<Pool> {
+        NgxString::new_in(self.clone())
+    }
+
     /// Creates a buffer of the specified size in the memory pool.
     ///
     /// Returns `Some(TemporaryBuffer)` if the buffer is successfully created, or `None` if
     /// allocation fails.
-    pub fn create_buffer(&mut self, size: usize) -> Option<TemporaryBuffer> {
-        let buf = unsafe { ngx_create_temp_buf(self.as_mut(), size) };
+    pub fn create_buffer(&self, size: usize) -> Option<TemporaryBuffer> {
+        let buf = unsafe { ngx_create_temp_buf(self.0.as_ptr(), size) };
         if buf.is_null() {
             return None;
         }
@@ -114,13 +205,18 @@ impl Pool {
     ///
     /// Returns `Some(TemporaryBuffer)` if the buffer is successfully created, or `None` if
     /// allocation fails.
-    pub fn create_buffer_from_str(&mut self, str: &str) -> Option<TemporaryBuffer> {
-        let mut buffer = self.create_buffer(str.len())?;
-        unsafe {
-            let buf = buffer.as_ngx_buf_mut();
-            ptr::copy_nonoverlapping(str.as_ptr(), (*buf).pos, str.len());
-            (*buf).last = (*buf).pos.add(str.len());
-        }
+    pub fn create_buffer_from_str(&self, str: &str) -> Option<TemporaryBuffer> {
+        self.create_buffer_from_bytes(str.as_bytes())
+    }
+
+    /// Creates a buffer from a byte slice in the memory pool.
+    ///
+    /// Returns `Some(TemporaryBuffer)` if the buffer is successfully created, or `None` if
+    /// allocation fails.
+    pub fn create_buffer_from_bytes(&self, data: &[u8]) -> Option<TemporaryBuffer> {
+        let mut buffer = self.create_buffer(data.len())?;
+        let n = buffer.append(data);
+        debug_assert_eq!(n, data.len());
         Some(buffer)
     }
 
@@ -128,7 +224,7 @@ impl Pool {
     ///
     /// Returns `Some(MemoryBuffer)` if the buffer is successfully created, or `None` if allocation
     /// fails.
-    pub fn create_buffer_from_static_str(&mut self, str: &'static str) -> Option<MemoryBuffer> {
+    pub fn create_buffer_from_static_str(&self, str: &'static str) -> Option<MemoryBuffer> {
         let buf = self.calloc_type::<ngx_buf_t>();
         if buf.is_null() {
             return None;
@@ -156,7 +252,7 @@ impl Pool {
     ///
     /// # Safety
     /// This function is marked as unsafe because it involves raw pointer manipulation.
-    unsafe fn add_cleanup_for_value<T>(&mut self, value: *mut T) -> Result<(), ()> {
+    unsafe fn add_cleanup_for_value<T>(&self, value: *mut T) -> Result<(), ()> {
         let cln = ngx_pool_cleanup_add(self.0.as_ptr(), 0);
         if cln.is_null() {
             return Err(());
@@ -171,7 +267,7 @@ impl Pool {
     /// The resulting pointer is aligned to a platform word size.
     ///
     /// Returns a raw pointer to the allocated memory.
-    pub fn alloc(&mut self, size: usize) -> *mut c_void {
+    pub fn alloc(&self, size: usize) -> *mut c_void {
         unsafe { ngx_palloc(self.0.as_ptr(), size) }
     }
 
@@ -179,7 +275,7 @@ impl Pool {
     /// The resulting pointer is aligned to a platform word size.
     ///
     /// Returns a typed pointer to the allocated memory.
-    pub fn alloc_type<T: Copy>(&mut self) -> *mut T {
+    pub fn alloc_type<T: Copy>(&self) -> *mut T {
         self.alloc(mem::size_of::<T>()) as *mut T
     }
 
@@ -187,7 +283,7 @@ impl Pool {
     /// The resulting pointer is aligned to a platform word size.
     ///
     /// Returns a raw pointer to the allocated memory.
-    pub fn calloc(&mut self, size: usize) -> *mut c_void {
+    pub fn calloc(&self, size: usize) -> *mut c_void {
         unsafe { ngx_pcalloc(self.0.as_ptr(), size) }
     }
 
@@ -195,21 +291,21 @@ impl Pool {
     /// The resulting pointer is aligned to a platform word size.
     ///
     /// Returns a typed pointer to the allocated memory.
-    pub fn calloc_type<T: Copy>(&mut self) -> *mut T {
+    pub fn calloc_type<T: Copy>(&self) -> *mut T {
         self.calloc(mem::size_of::<T>()) as *mut T
     }
 
     /// Allocates unaligned memory from the pool of the specified size.
     ///
     /// Returns a raw pointer to the allocated memory.
-    pub fn alloc_unaligned(&mut self, size: usize) -> *mut c_void {
+    pub fn alloc_unaligned(&self, size: usize) -> *mut c_void {
         unsafe { ngx_pnalloc(self.0.as_ptr(), size) }
     }
 
     /// Allocates unaligned memory for a type from the pool.
     ///
     /// Returns a typed pointer to the allocated memory.
-    pub fn alloc_type_unaligned<T: Copy>(&mut self) -> *mut T {
+    pub fn alloc_type_unaligned<T: Copy>(&self) -> *mut T {
         self.alloc_unaligned(mem::size_of::<T>()) as *mut T
     }
 
@@ -218,7 +314,7 @@ impl Pool {
     ///
     /// Returns a typed pointer to the allocated memory if successful, or a null pointer if
     /// allocation or cleanup handler addition fails.
-    pub fn allocate<T>(&mut self, value: T) -> *mut T {
+    pub fn allocate<T>(&self, value: T) -> *mut T {
         unsafe {
             let p = self.alloc(mem::size_of::<T>()) as *mut T;
             ptr::write(p, value);
@@ -229,6 +325,53 @@ impl Pool {
             p
         }
     }
+
+    /// Allocates an array of `n` values of a specified type, initialized from the iterator, and
+    /// adds a cleanup handler dropping all the elements to the memory pool.
+    ///
+    /// Returns `None` if the allocation or cleanup handler addition fails, or if the iterator
+    /// yields fewer than `n` values. The values already written are dropped in the latter case.
+    /// Any values beyond the first `n` are not consumed.
+    pub fn allocate_array<T>(
+        &mut self,
+        values: impl IntoIterator<Item = T>,
+        n: usize,
+    ) -> Option<&mut [T]> {
+        if n == 0 {
+            return Some(&mut []);
+        }
+
+        let layout = Layout::array::<T>(n).ok()?;
+        let p = <Self as Allocator>::allocate(self, layout)
+            .ok()?
+            .cast::<T>();
+
+        unsafe {
+            // Reserve the cleanup handler before writing the values, so we won't have to drop
+            // them on failure.
+            let cln = ngx_pool_cleanup_add(self.0.as_ptr(), mem::size_of::<*mut [T]>());
+            if cln.is_null() {
+                return None;
+            }
+
+            let mut len = 0;
+            for value in values.into_iter().take(n) {
+                p.add(len).write(value);
+                len += 1;
+            }
+
+            let elts = ptr::slice_from_raw_parts_mut(p.as_ptr(), len);
+            if len < n {
+                ptr::drop_in_place(elts);
+                return None;
+            }
+
+            (*cln).data.cast::<*mut [T]>().write(elts);
+            (*cln).handler = Some(cleanup_slice::<T>);
+
+            Some(&mut *elts)
+        }
+    }
 }
 
 /// Cleanup handler for a specific type `T`.
@@ -245,3 +388,11 @@ impl Pool {
 unsafe extern "C" fn cleanup_type<T>(data: *mut c_void) {
     ptr::drop_in_place(data as *mut T);
 }
+
+/// Cleanup handler for an array of values of type `T`.
+///
+/// # Safety
+/// `data` must point to a slice pointer to the initialized values of type `T`.
+unsafe extern "C" fn cleanup_slice<T>(data: *mut c_void) {
+    ptr::drop_in_place(*data.cast::<*mut [T]>())
+}
@@ -0,0 +1,152 @@
+use core::ffi::c_int;
+use core::ptr::{self, NonNull};
+
+use crate::core::{NgxStr, Pool, Status};
+use crate::ffi::{
+    ngx_int_t, ngx_regex_compile, ngx_regex_compile_t, ngx_regex_t, ngx_str_t, u_char,
+    NGX_MAX_CONF_ERRSTR, NGX_OK, NGX_REGEX_NO_MATCHED,
+};
+
+/// Regular expression compiled with `ngx_regex_compile`, using the PCRE or PCRE2 library nginx
+/// was built with.
+///
+/// The compiled expression is allocated from the pool and remains valid until the pool is
+/// destroyed. The expressions are usually compiled from a directive handler, with the
+/// configuration pool.
+///
+/// ```rust,ignore
+/// let re = Regex::compile(&cf.pool(), pattern, NGX_REGEX_CASELESS as _).map_err(|err| {
+///     cf.log_error(NGX_LOG_EMERG, format_args!("{err}"));
+/// })?;
+///
+/// let mut captures = [0; 30];
+/// if let Ok(n) = re.exec(r.path(), &mut captures) { ... }
+/// ```
+///
+/// See <https://nginx.org/en/docs/dev/development_guide.html#regex>
+#[derive(Clone, Copy, Debug)]
+pub struct Regex {
+    regex: NonNull<ngx_regex_t>,
+    captures: usize,
+}
+
+impl Regex {
+    /// Compiles the pattern with the `NGX_REGEX_*` options.
+    ///
+    /// On failure, returns the error message from the regex library, allocated from the pool.
+    pub fn compile<'a>(
+        pool: &'a Pool,
+        pattern: &NgxStr,
+        options: ngx_int_t,
+    ) -> Result<Regex, &'a NgxStr> {
+        let mut pool = pool.clone();
+        let len = pattern.as_bytes().len();
+
+        let err = pool
+            .alloc_unaligned(NGX_MAX_CONF_ERRSTR as usize)
+            .cast::<u_char>();
+        // PCRE requires a null-terminated pattern
+        let data = pool.alloc_unaligned(len + 1).cast::<u_char>();
+        if err.is_null() || data.is_null() {
+            return Err(NgxStr::from_bytes(b"memory allocation failed"));
+        }
+
+        // SAFETY: the allocated buffer has `len + 1` bytes
+        unsafe {
+            ptr::copy_nonoverlapping(pattern.as_bytes().as_ptr(), data, len);
+            *data.add(len) = 0;
+        }
+
+        // SAFETY: a zeroed structure is a valid initial state for `ngx_regex_compile`
+        let mut rc: ngx_regex_compile_t = unsafe { core::mem::zeroed() };
+        rc.pattern = ngx_str_t { data, len };
+        rc.pool = pool.as_mut();
+        rc.options = options;
+        rc.err = ngx_str_t {
+            data: err,
+            len: NGX_MAX_CONF_ERRSTR as usize,
+        };
+
+        if unsafe { ngx_regex_compile(&mut rc) } != NGX_OK as ngx_int_t {
+            // SAFETY: on failure, `err` is updated with the length of the message
+            return Err(unsafe { NgxStr::from_ngx_str(rc.err) });
+        }
+
+        Ok(Regex {
+            // SAFETY: `regex` is set on success
+            regex: unsafe { NonNull::new_unchecked(rc.regex) },
+            captures: rc.captures as usize,
+        })
+    }
+
+    /// Returns the number of capturing groups in the expression.
+    ///
+    /// A `captures` array of `(self.captures() + 1) * 3` elements is sufficient to store all the
+    /// captures in [`Regex::exec`].
+    pub fn captures(&self) -> usize {
+        self.captures
+    }
+
+    /// Matches the subject against the expression, storing the capture offsets in `captures`.
+    ///
+    /// The offsets are stored in pairs of the start and the end of each capture, starting with
+    /// the whole match, and the last third of the array is used as the workspace by PCRE. An
+    /// empty `captures` array can be used to check for a match.
+    ///
+    /// Returns the number of the captures set, `0` if there was not enough space for all of
+    /// them, `Err(NGX_DECLINED)` if the subject does not match, or `Err(NGX_ERROR)` on error.
+    pub fn exec(&self, subject: &NgxStr, captures: &mut [c_int]) -> Result<usize, Status> {
+        let rc = self.exec_raw(subject.as_bytes(), captures);
+
+        if rc >= 0 {
+            Ok(rc as usize)
+        } else if rc == NGX_REGEX_NO_MATCHED as ngx_int_t {
+            Err(Status::NGX_DECLINED)
+        } else {
+            Err(Status::NGX_ERROR)
+        }
+    }
+
+    #[cfg(ngx_feature = "pcre2")]
+    fn exec_raw(&self, subject: &[u8], captures: &mut [c_int]) -> ngx_int_t {
+        let mut s = ngx_str_t {
+            data: subject.as_ptr().cast_mut(),
+            len: subject.len(),
+        };
+        let ptr = if captures.is_empty() {
+            ptr::null_mut()
+        } else {
+            captures.as_mut_ptr()
+        };
+
+        // SAFETY: the expression is valid, the subject is not modified, and the size matches the
+        // captures array
+        unsafe { crate::ffi::ngx_regex_exec(self.regex.as_ptr(), &mut s, ptr, captures.len() as _) }
+    }
+
+    #[cfg(not(ngx_feature = "pcre2"))]
+    fn exec_raw(&self, subject: &[u8], captures: &mut [c_int]) -> ngx_int_t {
+        let ptr = if captures.is_empty() {
+            ptr::null_mut()
+        } else {
+            captures.as_mut_ptr()
+        };
+
+        // `ngx_regex_exec` is a macro over `pcre_exec` with PCRE.
+        // SAFETY: the expression is valid, the subject is not modified, and the size matches the
+        // captures array
+        unsafe {
+            let re = self.regex.as_ref();
+            crate::ffi::pcre_exec(
+                re.code,
+                re.extra,
+                subject.as_ptr().cast(),
+                subject.len() as c_int,
+                0,
+                0,
+                ptr,
+                captures.len() as c_int,
+            ) as ngx_int_t
+        }
+    }
+}
@@ -3,14 +3,17 @@
 //! See <https://nginx.org/en/docs/dev/development_guide.html#shared_memory>.
 use core::alloc::Layout;
 use core::cmp;
+use core::ffi::c_void;
+use core::mem;
 use core::ptr::{self, NonNull};
 
 use nginx_sys::{
-    ngx_shm_zone_t, ngx_shmtx_lock, ngx_shmtx_unlock, ngx_slab_alloc_locked, ngx_slab_free_locked,
-    ngx_slab_pool_t,
+    ngx_int_t, ngx_shared_memory_add, ngx_shm_zone_t, ngx_shmtx_lock, ngx_shmtx_unlock,
+    ngx_slab_alloc_locked, ngx_slab_free_locked, ngx_slab_pool_t, ngx_str_t,
 };
 
 use crate::allocator::{dangling_for_layout, AllocError, Allocator};
+use crate::core::{NgxConfRef, NgxStr, Status};
 
 /// Non-owning wrapper for an [`ngx_slab_pool_t`] pointer, providing methods for working with
 /// shared memory slab pools.
@@ -152,3 +155,143 @@ impl Drop for LockedSlabPool {
         unsafe { ngx_shmtx_unlock(&mut shpool.mutex) }
     }
 }
+
+/// Wrapper for a borrowed [`ngx_shm_zone_t`], a shared memory zone registered in the
+/// configuration.
+///
+/// See <https://nginx.org/en/docs/dev/development_guide.html#shared_memory>.
+#[repr(transparent)]
+pub struct SharedZone(ngx_shm_zone_t);
+
+impl SharedZone {
+    /// Creates a mutable `SharedZone` reference from an `ngx_shm_zone_t` pointer.
+    ///
+    /// # Safety
+    ///
+    /// The caller must provide a valid non-null pointer to an `ngx_shm_zone_t`, which remains
+    /// valid and is not accessed by other means for the lifetime of the returned reference.
+    pub unsafe fn from_ptr_mut<'a>(zone: *mut ngx_shm_zone_t) -> &'a mut Self {
+        &mut *zone.cast()
+    }
+
+    /// Adds a shared memory zone to the configuration, or returns an existing zone with the same
+    /// name and tag.
+    ///
+    /// A `size` of 0 looks up a zone defined elsewhere. The `tag` identifies the owner of the zone,
+    /// usually the module. The `name` must outlive the configuration, e.g. an argument of the
+    /// directive.
+    pub fn add<'a>(
+        cf: &'a mut NgxConfRef,
+        name: ngx_str_t,
+        size: usize,
+        tag: *mut c_void,
+    ) -> Option<&'a mut Self> {
+        let mut name = name;
+        let zone = unsafe { ngx_shared_memory_add(cf.as_ptr(), &mut name, size, tag) };
+        if zone.is_null() {
+            return None;
+        }
+
+        Some(unsafe { Self::from_ptr_mut(zone) })
+    }
+
+    /// Returns a raw pointer to the zone.
+    pub fn as_ptr(&self) -> *mut ngx_shm_zone_t {
+        ptr::from_ref(&self.0).cast_mut()
+    }
+
+    /// Returns the zone name.
+    pub fn name(&self) -> &NgxStr {
+        unsafe { NgxStr::from_ngx_str(self.0.shm.name) }
+    }
+
+    /// Returns the zone size.
+    pub fn size(&self) -> usize {
+        self.0.shm.size
+    }
+
+    /// Returns the module data associated with the zone.
+    pub fn data(&self) -> *mut c_void {
+        self.0.data
+    }
+
+    /// Associates the module data with the zone.
+    ///
+    /// The value is passed to the init callback of the zone in the next configuration cycle, if
+    /// the zone is inherited.
+    pub fn set_data(&mut self, data: *mut c_void) {
+        self.0.data = data;
+    }
+
+    /// Returns the slab pool of the zone, or `None` if the zone is not mapped yet.
+    ///
+    /// # Safety
+    ///
+    /// See [`SlabPool::from_shm_zone`].
+    pub unsafe fn slab_pool(&self) -> Option<SlabPool> {
+        SlabPool::from_shm_zone(&self.0)
+    }
+
+    /// Sets the zone init callback, called when the shared memory is mapped during the
+    /// configuration.
+    ///
+    /// The callback receives the data of the zone from the previous configuration cycle if the
+    /// existing memory was inherited on a configuration reload, and `None` for a fresh zone. The
+    /// contents of an inherited zone are preserved, and can be referenced from the new data.
+    ///
+    /// The callback must not capture any state, as the zone has no room to store it; use
+    /// [`SharedZone::data`] instead. Capturing closures are rejected at compile time.
+    ///
+    /// ```ignore
+    /// zone.init(|zone, shpool, prev| {
+    ///     let ctx = zone.data().cast::<ZoneCtx>();
+    ///     match prev {
+    ///         Some(prev) => unsafe { (*ctx).sh = (*prev.cast::<ZoneCtx>().as_ptr()).sh },
+    ///         None => { /* allocate the shared state in `shpool` */ }
+    ///     }
+    ///     Status::NGX_OK
+    /// });
+    /// ```
+    pub fn init<F>(&mut self, cb: F)
+    where
+        F: Fn(&mut SharedZone, &mut SlabPool, Option<NonNull<c_void>>) -> Status + Copy + 'static,
+    {
+        const {
+            assert!(
+                mem::size_of::<F>() == 0,
+                "SharedZone::init: the callback must not capture any state"
+            )
+        };
+
+        let _ = cb;
+        self.0.init = Some(shared_zone_init::<F>);
+    }
+}
+
+impl AsRef<ngx_shm_zone_t> for SharedZone {
+    fn as_ref(&self) -> &ngx_shm_zone_t {
+        &self.0
+    }
+}
+
+impl AsMut<ngx_shm_zone_t> for SharedZone {
+    fn as_mut(&mut self) -> &mut ngx_shm_zone_t {
+        &mut self.0
+    }
+}
+
+unsafe extern "C" fn shared_zone_init<F>(zone: *mut ngx_shm_zone_t, data: *mut c_void) -> ngx_int_t
+where
+    F: Fn(&mut SharedZone, &mut SlabPool, Option<NonNull<c_void>>) -> Status + Copy + 'static,
+{
+    // SAFETY: `F` is a zero-sized `Copy` type, and an instance of it was passed to
+    // `SharedZone::init`, thus any aligned pointer is a valid source of the value.
+    let cb: F = NonNull::<F>::dangling().read();
+
+    let zone = SharedZone::from_ptr_mut(zone);
+    let Some(mut shpool) = zone.slab_pool() else {
+        return Status::NGX_ERROR.into();
+    };
+
+    cb(zone, &mut shpool, NonNull::new(data)).into()
+}
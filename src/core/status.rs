@@ -19,7 +19,19 @@ impl Status {
 
 impl fmt::Debug for Status {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Debug::fmt(&self.0, f)
+        match self.name() {
+            Some(name) => f.write_str(name),
+            None => fmt::Debug::fmt(&self.0, f),
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.name() {
+            Some(name) => f.write_str(name),
+            None => fmt::Display::fmt(&self.0, f),
+        }
     }
 }
 
@@ -42,6 +54,17 @@ macro_rules! ngx_codes {
             pub const $konst: Status = Status($konst as ngx_int_t);
         )+
 
+            /// Returns the symbolic name of a special return code, e.g. `"NGX_AGAIN"`.
+            ///
+            /// Returns `None` for any other value, such as an HTTP status code.
+            pub fn name(&self) -> Option<&'static str> {
+                $(
+                if self.0 == $konst as ngx_int_t {
+                    return Some(stringify!($konst));
+                }
+                )+
+                None
+            }
         }
     }
 }
@@ -68,3 +91,38 @@ ngx_codes! {
 pub const NGX_CONF_ERROR: *mut c_char = ptr::null_mut::<c_char>().wrapping_offset(-1);
 /// Configuration handler succeeded.
 pub const NGX_CONF_OK: *mut c_char = ptr::null_mut();
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+    use alloc::format;
+
+    use super::*;
+
+    #[test]
+    fn test_codes() {
+        let codes = [
+            (Status::NGX_OK, NGX_OK as ngx_int_t, "NGX_OK"),
+            (Status::NGX_ERROR, NGX_ERROR as ngx_int_t, "NGX_ERROR"),
+            (Status::NGX_AGAIN, NGX_AGAIN as ngx_int_t, "NGX_AGAIN"),
+            (Status::NGX_BUSY, NGX_BUSY as ngx_int_t, "NGX_BUSY"),
+            (Status::NGX_DONE, NGX_DONE as ngx_int_t, "NGX_DONE"),
+            (
+                Status::NGX_DECLINED,
+                NGX_DECLINED as ngx_int_t,
+                "NGX_DECLINED",
+            ),
+            (Status::NGX_ABORT, NGX_ABORT as ngx_int_t, "NGX_ABORT"),
+        ];
+
+        for (status, code, name) in codes {
+            assert_eq!(status.0, code);
+            assert_eq!(status.name(), Some(name));
+            assert_eq!(format!("{status}"), name);
+            assert_eq!(format!("{status:?}"), name);
+        }
+
+        assert_eq!(Status(404).name(), None);
+        assert_eq!(format!("{}", Status(404)), "404");
+    }
+}
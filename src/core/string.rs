@@ -1,34 +1,64 @@
 #[cfg(all(not(feature = "std"), feature = "alloc"))]
 use alloc::{borrow::Cow, string::String};
+use core::alloc::Layout;
 use core::cmp;
 use core::fmt;
+use core::ops;
+use core::ptr::NonNull;
+use core::slice::{self, SliceIndex};
 use core::str::{self, Utf8Error};
 #[cfg(feature = "std")]
 use std::{borrow::Cow, string::String};
 
-use crate::ffi::{ngx_str_t, u_char};
+use crate::allocator::Allocator;
+use crate::core::Pool;
+use crate::ffi::{ngx_str_t, ngx_strlow, u_char};
 
 /// Static string initializer for [`ngx_str_t`].
 ///
-/// The resulting byte string is always nul-terminated (just like a C string).
+/// The resulting byte string is always nul-terminated (just like a C string). The macro is
+/// evaluated at compile time and can be used in `const` and `static` initializers; a literal with
+/// an interior nul byte is rejected with a compilation error.
+///
+/// See [`ngx_str_t::from_static`].
 ///
 /// [`ngx_str_t`]: https://nginx.org/en/docs/dev/development_guide.html#string_overview
 #[macro_export]
 macro_rules! ngx_string {
-    ($s:expr) => {{
-        $crate::ffi::ngx_str_t {
-            len: $s.len() as _,
-            data: concat!($s, "\0").as_ptr() as *mut u8,
-        }
-    }};
+    ($s:expr) => {
+        const { $crate::ffi::ngx_str_t::from_static(concat!($s, "\0")) }
+    };
 }
 
 #[cfg(feature = "alloc")]
 pub use self::_alloc::NgxString;
 
+/// Formats a string in the memory pool, returning an `Option<ngx_str_t>`.
+///
+/// The string is allocated once, with the exact length of the output.
+///
+/// ```ignore
+/// let addr: ngx_str_t = ngx_format!(&pool, "{}:{}", host, port).ok_or(AllocError)?;
+/// ```
+#[cfg(feature = "alloc")]
+#[macro_export]
+macro_rules! ngx_format {
+    ($pool:expr, $($arg:tt)+) => {
+        $crate::core::NgxString::try_format_in(
+            ::core::format_args!($($arg)+),
+            ::core::clone::Clone::clone($pool),
+        )
+        .ok()
+        .map($crate::core::NgxString::into_ngx_str)
+    };
+}
+
 /// Representation of a borrowed [Nginx string].
 ///
 /// [Nginx string]: https://nginx.org/en/docs/dev/development_guide.html#string_overview
+///
+/// `NgxStr` is a plain byte slice and is `Send` and `Sync`. The lifetime of the reference still
+/// has to be bound to the pool owning the data, such as the request pool.
 #[derive(Hash, PartialEq, Eq, PartialOrd, Ord)]
 #[repr(transparent)]
 pub struct NgxStr([u_char]);
@@ -67,9 +97,20 @@ impl NgxStr {
         &self.0
     }
 
-    /// Yields a `&str` slice if the [`NgxStr`] contains valid UTF-8.
-    pub fn to_str(&self) -> Result<&str, Utf8Error> {
-        str::from_utf8(self.as_bytes())
+    /// Yields a [`ValidatedStr`] if the [`NgxStr`] contains valid UTF-8.
+    ///
+    /// The result dereferences to `&str` and can be kept around to avoid repeated validation.
+    pub fn to_str(&self) -> Result<ValidatedStr<'_>, Utf8Error> {
+        str::from_utf8(self.as_bytes()).map(ValidatedStr)
+    }
+
+    /// Yields a `&str` slice without checking that the [`NgxStr`] contains valid UTF-8.
+    ///
+    /// # Safety
+    ///
+    /// The string must contain valid UTF-8. See [`str::from_utf8_unchecked`].
+    pub unsafe fn as_str_unchecked(&self) -> &str {
+        str::from_utf8_unchecked(self.as_bytes())
     }
 
     /// Converts an [`NgxStr`] into a [`Cow<str>`], replacing invalid UTF-8 sequences.
@@ -80,10 +121,90 @@ impl NgxStr {
         String::from_utf8_lossy(self.as_bytes())
     }
 
+    /// Returns the length of the [`NgxStr`] in bytes.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
     /// Returns `true` if the [`NgxStr`] is empty, otherwise `false`.
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
+
+    /// Returns a substring referencing the same memory, or `None` if the range is out of bounds.
+    ///
+    /// ```
+    /// # use ngx::core::NgxStr;
+    /// let s = NgxStr::from_bytes(b"text/plain");
+    /// assert_eq!(s.get(..4), Some(NgxStr::from_bytes(b"text")));
+    /// assert_eq!(s.get(5..20), None);
+    /// ```
+    #[inline]
+    pub fn get<I>(&self, index: I) -> Option<&NgxStr>
+    where
+        I: SliceIndex<[u8], Output = [u8]>,
+    {
+        self.0.get(index).map(NgxStr::from_bytes)
+    }
+
+    /// Divides the string into two at an index, or returns `None` if the index is out of bounds.
+    #[inline]
+    pub fn split_at(&self, mid: usize) -> Option<(&NgxStr, &NgxStr)> {
+        if mid > self.0.len() {
+            return None;
+        }
+        let (head, tail) = self.0.split_at(mid);
+        Some((NgxStr::from_bytes(head), NgxStr::from_bytes(tail)))
+    }
+
+    /// Returns a substring with the prefix removed, or `None` if the string does not start with
+    /// `prefix`.
+    #[inline]
+    pub fn strip_prefix(&self, prefix: impl AsRef<[u8]>) -> Option<&NgxStr> {
+        self.0.strip_prefix(prefix.as_ref()).map(NgxStr::from_bytes)
+    }
+
+    /// Returns a substring with the suffix removed, or `None` if the string does not end with
+    /// `suffix`.
+    #[inline]
+    pub fn strip_suffix(&self, suffix: impl AsRef<[u8]>) -> Option<&NgxStr> {
+        self.0.strip_suffix(suffix.as_ref()).map(NgxStr::from_bytes)
+    }
+
+    /// Returns a substring with the leading and trailing ASCII whitespace removed.
+    #[inline]
+    pub fn trim_ascii(&self) -> &NgxStr {
+        NgxStr::from_bytes(self.0.trim_ascii())
+    }
+
+    /// Checks that two strings are an ASCII case-insensitive match.
+    ///
+    /// Unlike [`str::eq_ignore_ascii_case`], this works on arbitrary bytes and does not require
+    /// the string to be valid UTF-8.
+    #[inline]
+    pub fn eq_ignore_ascii_case(&self, other: impl AsRef<[u8]>) -> bool {
+        self.0.eq_ignore_ascii_case(other.as_ref())
+    }
+
+    /// Returns a copy of this string with ASCII characters converted to lowercase, allocated in
+    /// the pool.
+    ///
+    /// Returns `None` if the allocation fails.
+    pub fn to_lowercase_in<'a>(&self, pool: &'a Pool) -> Option<&'a NgxStr> {
+        let len = self.0.len();
+        let layout = Layout::array::<u8>(len).ok()?;
+        let data: NonNull<u8> = pool.allocate(layout).ok()?.cast();
+        // SAFETY: `data` points to `len` writable bytes, which remain valid for the lifetime of the
+        // pool.
+        unsafe {
+            ngx_strlow(data.as_ptr(), self.0.as_ptr().cast_mut(), len);
+            Some(NgxStr::from_bytes(slice::from_raw_parts(
+                data.as_ptr(),
+                len,
+            )))
+        }
+    }
 }
 
 impl AsRef<[u8]> for NgxStr {
@@ -100,6 +221,18 @@ impl AsMut<[u8]> for NgxStr {
     }
 }
 
+impl<I> ops::Index<I> for NgxStr
+where
+    I: SliceIndex<[u8], Output = [u8]>,
+{
+    type Output = NgxStr;
+
+    #[inline]
+    fn index(&self, index: I) -> &Self::Output {
+        NgxStr::from_bytes(&self.0[index])
+    }
+}
+
 impl fmt::Debug for NgxStr {
     #[inline]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -123,6 +256,83 @@ impl fmt::Display for NgxStr {
     }
 }
 
+/// An [`NgxStr`] known to contain valid UTF-8.
+///
+/// Returned by [`NgxStr::to_str`]. The validation result is retained, so repeated access to the
+/// string contents is free.
+#[derive(Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ValidatedStr<'a>(&'a str);
+
+impl<'a> ValidatedStr<'a> {
+    /// Returns the string as a `&str` slice.
+    #[inline]
+    pub fn as_str(&self) -> &'a str {
+        self.0
+    }
+
+    /// Returns the string as an [`NgxStr`].
+    #[inline]
+    pub fn as_ngx_str(&self) -> &'a NgxStr {
+        NgxStr::from_bytes(self.0.as_bytes())
+    }
+}
+
+impl core::ops::Deref for ValidatedStr<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl AsRef<str> for ValidatedStr<'_> {
+    fn as_ref(&self) -> &str {
+        self.0
+    }
+}
+
+impl AsRef<[u8]> for ValidatedStr<'_> {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_bytes()
+    }
+}
+
+impl<'a> From<ValidatedStr<'a>> for &'a str {
+    fn from(value: ValidatedStr<'a>) -> Self {
+        value.0
+    }
+}
+
+impl<'a> From<ValidatedStr<'a>> for &'a NgxStr {
+    fn from(value: ValidatedStr<'a>) -> Self {
+        value.as_ngx_str()
+    }
+}
+
+impl fmt::Debug for ValidatedStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.0, f)
+    }
+}
+
+impl fmt::Display for ValidatedStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.0, f)
+    }
+}
+
+impl PartialEq<str> for ValidatedStr<'_> {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for ValidatedStr<'_> {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
 macro_rules! impl_partial_ord_eq_from {
     ($self:ty, $other:ty) => { impl_partial_ord_eq_from!($self, $other;); };
 
@@ -299,6 +509,24 @@ mod _alloc {
             }
         }
 
+        /// Tries to construct a new `NgxString<A>` from the format arguments.
+        ///
+        /// The length of the output is calculated first, so the string is allocated once with the
+        /// exact capacity. This avoids leaving unused allocations in a memory pool.
+        ///
+        /// Returns an error if the allocation fails or a formatting trait implementation returns
+        /// an error.
+        pub fn try_format_in(args: fmt::Arguments<'_>, alloc: A) -> Result<Self, fmt::Error> {
+            let mut len = LengthCounter(0);
+            fmt::write(&mut len, args)?;
+
+            let mut s = Self::new_in(alloc);
+            s.try_reserve_exact(len.0).map_err(|_| fmt::Error)?;
+            // The output is not guaranteed to be identical, thus the buffer may still grow.
+            fmt::write(&mut GrowingWriter(&mut s), args)?;
+            Ok(s)
+        }
+
         /// Tries to append the bytes to the `NgxString`.
         #[inline]
         pub fn try_append(&mut self, other: impl AsRef<[u8]>) -> Result<(), TryReserveError> {
@@ -364,6 +592,36 @@ mod _alloc {
         }
     }
 
+    impl NgxString<Pool> {
+        /// Converts the string into an `ngx_str_t`.
+        ///
+        /// The memory is not freed until the pool is destroyed.
+        pub fn into_ngx_str(self) -> ngx_str_t {
+            let (data, len, _, _) = self.into_raw_parts();
+            ngx_str_t { data, len }
+        }
+    }
+
+    struct LengthCounter(usize);
+
+    impl fmt::Write for LengthCounter {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.0 = self.0.checked_add(s.len()).ok_or(fmt::Error)?;
+            Ok(())
+        }
+    }
+
+    struct GrowingWriter<'a, A: Allocator + Clone>(&'a mut NgxString<A>);
+
+    impl<A> fmt::Write for GrowingWriter<'_, A>
+    where
+        A: Allocator + Clone,
+    {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.0.try_append(s).map_err(|_| fmt::Error)
+        }
+    }
+
     impl<A> AsRef<NgxStr> for NgxString<A>
     where
         A: Allocator + Clone,
@@ -515,6 +773,28 @@ mod _alloc {
         }
     }
 
+    impl allocator::TryCloneIn for NgxStr {
+        type Target<A: Allocator + Clone> = NgxString<A>;
+
+        fn try_clone_in<A: Allocator + Clone>(
+            &self,
+            alloc: A,
+        ) -> Result<Self::Target<A>, allocator::AllocError> {
+            NgxString::try_from_bytes_in(self.as_bytes(), alloc).map_err(|_| allocator::AllocError)
+        }
+    }
+
+    impl allocator::TryCloneIn for ngx_str_t {
+        type Target<A: Allocator + Clone> = NgxString<A>;
+
+        fn try_clone_in<A: Allocator + Clone>(
+            &self,
+            alloc: A,
+        ) -> Result<Self::Target<A>, allocator::AllocError> {
+            NgxString::try_from_bytes_in(self.as_bytes(), alloc).map_err(|_| allocator::AllocError)
+        }
+    }
+
     impl<A> fmt::Write for NgxString<A>
     where
         A: Allocator + Clone,
@@ -577,6 +857,68 @@ mod tests {
         assert_eq!(ns, "test");
     }
 
+    #[test]
+    fn test_ngx_string_macro() {
+        const NAME: ngx_str_t = crate::ngx_string!("test");
+
+        assert_eq!(NAME.len, 4);
+        assert_eq!(NAME.as_bytes(), b"test");
+        assert_eq!(unsafe { *NAME.data.add(NAME.len) }, b'\0');
+
+        let empty = ngx_str_t::from_static("");
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_eq_ignore_ascii_case() {
+        let ns = NgxStr::from_bytes(b"Content-Type\xff");
+
+        assert!(ns.eq_ignore_ascii_case(b"content-type\xff"));
+        assert!(ns.eq_ignore_ascii_case(b"CONTENT-TYPE\xff"));
+        assert!(!ns.eq_ignore_ascii_case(b"content-type\xdf"));
+        assert!(!ns.eq_ignore_ascii_case("content-type"));
+    }
+
+    #[test]
+    fn test_to_str() {
+        let ns = NgxStr::from_bytes(b"text/plain");
+        let vs = ns.to_str().unwrap();
+
+        assert_eq!(vs, "text/plain");
+        assert_eq!(vs.len(), 10);
+        assert_eq!(vs.as_ngx_str(), ns);
+        assert_eq!(unsafe { ns.as_str_unchecked() }, vs.as_str());
+
+        assert!(NgxStr::from_bytes(b"\xff").to_str().is_err());
+    }
+
+    #[test]
+    fn test_substrings() {
+        let ns = NgxStr::from_bytes(b" max-age=60 ");
+
+        assert_eq!(ns.len(), 12);
+        assert_eq!(&ns[1..8], "max-age");
+        assert_eq!(ns.get(9..11).unwrap(), "60");
+        assert_eq!(ns.get(9..13), None);
+
+        let trimmed = ns.trim_ascii();
+        assert_eq!(trimmed, "max-age=60");
+        assert_eq!(trimmed.strip_prefix("max-age=").unwrap(), "60");
+        assert_eq!(trimmed.strip_suffix("=60").unwrap(), "max-age");
+        assert_eq!(trimmed.strip_prefix("no-cache"), None);
+
+        let (name, value) = trimmed.split_at(7).unwrap();
+        assert_eq!(name, "max-age");
+        assert_eq!(value, "=60");
+        assert!(trimmed.split_at(11).is_none());
+
+        // substrings reference the original memory
+        assert!(core::ptr::eq(
+            trimmed.as_bytes().as_ptr(),
+            ns[1..].as_bytes().as_ptr()
+        ));
+    }
+
     #[test]
     #[cfg(feature = "alloc")]
     fn test_string_comparisons() {
@@ -630,6 +972,41 @@ mod tests {
         assert_eq!((s.as_bytes().as_ptr(), s.capacity()), saved);
     }
 
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_string_format() {
+        use crate::allocator::Global;
+
+        let host = NgxStr::from_bytes(b"localhost");
+
+        let s = NgxString::try_format_in(format_args!("{host}:{}", 8080), Global).expect("format");
+        assert_eq!(s, b"localhost:8080");
+        assert_eq!(s.capacity(), s.len());
+
+        let s = NgxString::try_format_in(format_args!(""), Global).expect("format");
+        assert!(s.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_try_clone_in() {
+        use crate::allocator::{Global, TryCloneIn};
+        use crate::collections::Vec;
+
+        let s = NgxStr::from_bytes(b"test");
+        let owned = s.try_clone_in(Global).expect("clone");
+        assert_eq!(owned, b"test");
+        assert_ne!(owned.as_bytes().as_ptr(), s.as_bytes().as_ptr());
+
+        let mut v: Vec<NgxString<Global>, Global> = Vec::new_in(Global);
+        v.push(owned);
+        v.push(NgxString::try_from_bytes_in("value", Global).expect("alloc"));
+        let copy = v.try_clone_in(Global).expect("clone");
+        assert_eq!(copy.len(), 2);
+        assert_eq!(copy[0], b"test");
+        assert_eq!(copy[1], b"value");
+    }
+
     #[test]
     fn test_lifetimes() {
         let a: &NgxStr = "Hello World!".into();
@@ -0,0 +1,166 @@
+use core::iter::FusedIterator;
+use core::slice;
+
+use crate::core::NgxStr;
+use crate::ffi::{ngx_table_elt_t, ngx_uint_t};
+
+/// Wrapper for a borrowed [`ngx_table_elt_t`], a key-value element of the header lists.
+///
+/// Since nginx 1.23.0, the header elements with the same name are linked via the `next` field,
+/// e.g. `headers_in.cookie` or `headers_in.x_forwarded_for` point to the first of the values.
+/// Use [`NgxTableElementRef::iter`] to access all the values of such header.
+///
+/// See <https://nginx.org/en/docs/dev/development_guide.html#http_request>
+#[repr(transparent)]
+pub struct NgxTableElementRef(ngx_table_elt_t);
+
+impl NgxTableElementRef {
+    /// Creates an `NgxTableElementRef` reference from an `ngx_table_elt_t` pointer.
+    ///
+    /// # Safety
+    ///
+    /// The caller must provide a valid non-null pointer to an `ngx_table_elt_t`, which remains
+    /// valid for the lifetime of the returned reference.
+    pub unsafe fn from_ptr<'a>(elt: *const ngx_table_elt_t) -> &'a Self {
+        &*elt.cast()
+    }
+
+    /// Creates a mutable `NgxTableElementRef` reference from an `ngx_table_elt_t` pointer.
+    ///
+    /// # Safety
+    ///
+    /// The caller must provide a valid non-null pointer to an `ngx_table_elt_t`, which remains
+    /// valid and is not accessed by other means for the lifetime of the returned reference.
+    pub unsafe fn from_ptr_mut<'a>(elt: *mut ngx_table_elt_t) -> &'a mut Self {
+        &mut *elt.cast()
+    }
+
+    /// Returns the element key.
+    pub fn key(&self) -> &NgxStr {
+        unsafe { NgxStr::from_ngx_str(self.0.key) }
+    }
+
+    /// Returns the element value.
+    pub fn value(&self) -> &NgxStr {
+        unsafe { NgxStr::from_ngx_str(self.0.value) }
+    }
+
+    /// Returns the lowercase copy of the key, if available.
+    pub fn lowcase_key(&self) -> Option<&NgxStr> {
+        if self.0.lowcase_key.is_null() {
+            return None;
+        }
+
+        let key = unsafe { slice::from_raw_parts(self.0.lowcase_key, self.0.key.len) };
+        Some(NgxStr::from_bytes(key))
+    }
+
+    /// Returns the hash of the lowercase key.
+    ///
+    /// Zero value means that the element is deleted and should be ignored.
+    pub fn hash(&self) -> ngx_uint_t {
+        self.0.hash
+    }
+
+    /// Returns the next element with the same key.
+    ///
+    /// Always returns `None` for nginx versions before 1.23.0.
+    pub fn next(&self) -> Option<&Self> {
+        #[cfg(nginx1_23_0)]
+        if !self.0.next.is_null() {
+            return Some(unsafe { Self::from_ptr(self.0.next) });
+        }
+
+        None
+    }
+
+    /// Returns an iterator over this element and all the elements linked via `next`.
+    ///
+    /// Deleted elements are skipped.
+    pub fn iter(&self) -> NgxTableElementIter<'_> {
+        NgxTableElementIter(Some(self))
+    }
+
+    /// Returns an iterator over all the values of the header.
+    pub fn values(&self) -> impl Iterator<Item = &NgxStr> + '_ {
+        self.iter().map(NgxTableElementRef::value)
+    }
+}
+
+impl AsRef<ngx_table_elt_t> for NgxTableElementRef {
+    fn as_ref(&self) -> &ngx_table_elt_t {
+        &self.0
+    }
+}
+
+impl AsMut<ngx_table_elt_t> for NgxTableElementRef {
+    fn as_mut(&mut self) -> &mut ngx_table_elt_t {
+        &mut self.0
+    }
+}
+
+impl<'a> IntoIterator for &'a NgxTableElementRef {
+    type Item = &'a NgxTableElementRef;
+    type IntoIter = NgxTableElementIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the chain of [`ngx_table_elt_t`] elements with the same key.
+pub struct NgxTableElementIter<'a>(Option<&'a NgxTableElementRef>);
+
+impl<'a> Iterator for NgxTableElementIter<'a> {
+    type Item = &'a NgxTableElementRef;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let elt = self.0?;
+            self.0 = elt.next();
+
+            if elt.hash() != 0 {
+                return Some(elt);
+            }
+        }
+    }
+}
+
+impl FusedIterator for NgxTableElementIter<'_> {}
+
+#[cfg(all(test, nginx1_23_0))]
+mod tests {
+    use core::ptr;
+
+    use super::*;
+    use crate::ffi::ngx_str_t;
+
+    fn elt(value: &'static str, hash: ngx_uint_t) -> ngx_table_elt_t {
+        let mut elt: ngx_table_elt_t = unsafe { core::mem::zeroed() };
+        elt.hash = hash;
+        elt.key = crate::ngx_string!("Set-Cookie");
+        elt.value = ngx_str_t {
+            data: value.as_ptr().cast_mut(),
+            len: value.len(),
+        };
+        elt
+    }
+
+    #[test]
+    fn test_iter() {
+        let mut c = elt("c=3", 1);
+        let mut b = elt("b=2", 0);
+        let mut a = elt("a=1", 1);
+
+        b.next = ptr::addr_of_mut!(c);
+        a.next = ptr::addr_of_mut!(b);
+
+        let head = unsafe { NgxTableElementRef::from_ptr(ptr::addr_of!(a)) };
+        let mut it = head.values();
+
+        assert_eq!(it.next(), Some(NgxStr::from_bytes(b"a=1")));
+        assert_eq!(it.next(), Some(NgxStr::from_bytes(b"c=3")));
+        assert_eq!(it.next(), None);
+        assert_eq!(head.lowcase_key(), None);
+    }
+}
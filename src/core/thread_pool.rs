@@ -0,0 +1,59 @@
+use crate::core::{NgxConfRef, NgxStr};
+use crate::ffi::{
+    ngx_cycle_t, ngx_str_t, ngx_thread_pool_add, ngx_thread_pool_get, ngx_thread_pool_t,
+};
+
+/// Wrapper for a borrowed [`ngx_thread_pool_t`], a pool of threads configured with the
+/// `thread_pool` directive.
+///
+/// See <https://nginx.org/en/docs/dev/development_guide.html#threads>
+#[repr(transparent)]
+pub struct ThreadPool(ngx_thread_pool_t);
+
+impl ThreadPool {
+    /// Creates a `ThreadPool` reference from an `ngx_thread_pool_t` pointer.
+    ///
+    /// # Safety
+    ///
+    /// The caller must provide a valid non-null pointer to an `ngx_thread_pool_t`, which remains
+    /// valid for the lifetime of the returned reference.
+    pub unsafe fn from_ptr<'a>(tp: *const ngx_thread_pool_t) -> &'a Self {
+        &*tp.cast()
+    }
+
+    /// Returns a raw pointer to the thread pool.
+    pub fn as_ptr(&self) -> *mut ngx_thread_pool_t {
+        core::ptr::from_ref(&self.0).cast_mut()
+    }
+
+    /// Registers a reference to the thread pool `name` while parsing the configuration, or to the
+    /// `default` pool if the name is `None`.
+    ///
+    /// The pool is created with the default parameters, unless configured with the
+    /// `thread_pool` directive. The returned reference is valid for the lifetime of the cycle.
+    pub fn add<'a>(cf: &'a mut NgxConfRef, name: Option<&NgxStr>) -> Option<&'a Self> {
+        // The thread pool keeps a pointer to the name; copy it to the configuration pool.
+        let mut name = match name {
+            // SAFETY: configuration parser state always has a valid pool
+            Some(x) => Some(unsafe { ngx_str_t::from_bytes(cf.as_ref().pool, x.as_bytes()) }?),
+            None => None,
+        };
+        let name = name
+            .as_mut()
+            .map_or(core::ptr::null_mut(), core::ptr::from_mut);
+
+        let tp = unsafe { ngx_thread_pool_add(cf.as_ptr(), name) };
+        (!tp.is_null()).then(|| unsafe { Self::from_ptr(tp) })
+    }
+
+    /// Looks up a thread pool configured in the cycle.
+    pub fn get<'a>(cycle: &'a ngx_cycle_t, name: &NgxStr) -> Option<&'a Self> {
+        let mut name = ngx_str_t {
+            data: name.as_bytes().as_ptr().cast_mut(),
+            len: name.as_bytes().len(),
+        };
+
+        let tp = unsafe { ngx_thread_pool_get(core::ptr::from_ref(cycle).cast_mut(), &mut name) };
+        (!tp.is_null()).then(|| unsafe { Self::from_ptr(tp) })
+    }
+}
@@ -0,0 +1,116 @@
+use core::error;
+use core::ffi::CStr;
+use core::fmt;
+use core::mem;
+use core::slice;
+
+use crate::core::{NgxStr, Pool};
+use crate::ffi::{ngx_addr_t, ngx_int_t, ngx_parse_url, ngx_str_t, ngx_url_t, NGX_OK};
+
+/// A parsed URL or address, as accepted by the `proxy_pass` or `listen` directives.
+///
+/// The host name is resolved to a list of addresses at parsing time, so the type is best suited
+/// for the configuration parsing. The strings and addresses are allocated from the pool, and
+/// remain valid until the pool is destroyed.
+///
+/// See [`Url::parse`].
+pub struct Url(ngx_url_t);
+
+impl Url {
+    /// Parses and resolves the URL, using the `default_port` if the URL does not specify one.
+    ///
+    /// The host name resolution uses the blocking system resolver, and should not be performed in
+    /// a worker process.
+    pub fn parse(pool: &Pool, url: &str, default_port: u16) -> Result<Self, UrlError> {
+        let mut pool = pool.clone();
+
+        // The parsed components reference the URL string, so it is copied to the pool.
+        let data = pool.alloc_unaligned(url.len()).cast::<u8>();
+        if data.is_null() {
+            return Err(UrlError("memory allocation failed"));
+        }
+        unsafe { core::ptr::copy_nonoverlapping(url.as_ptr(), data, url.len()) };
+
+        // SAFETY: an all-zeroes `ngx_url_t` is a valid initial state
+        let mut u: ngx_url_t = unsafe { mem::zeroed() };
+        u.url = ngx_str_t {
+            data,
+            len: url.len(),
+        };
+        u.default_port = default_port;
+
+        if unsafe { ngx_parse_url(pool.as_mut(), &mut u) } != NGX_OK as ngx_int_t {
+            // `err` always points to a static string, if set.
+            let message = if u.err.is_null() {
+                "invalid URL"
+            } else {
+                unsafe { CStr::from_ptr(u.err) }
+                    .to_str()
+                    .unwrap_or("invalid URL")
+            };
+            return Err(UrlError(message));
+        }
+
+        Ok(Self(u))
+    }
+
+    /// Returns the host part of the URL.
+    pub fn host(&self) -> &NgxStr {
+        unsafe { NgxStr::from_ngx_str(self.0.host) }
+    }
+
+    /// Returns the port of the URL, or the default port if the URL does not specify one.
+    pub fn port(&self) -> u16 {
+        self.0.port
+    }
+
+    /// Returns the path and query part of the URL.
+    pub fn uri(&self) -> &NgxStr {
+        unsafe { NgxStr::from_ngx_str(self.0.uri) }
+    }
+
+    /// Returns the addresses the URL host was resolved to.
+    pub fn addrs(&self) -> &[ngx_addr_t] {
+        if self.0.addrs.is_null() {
+            return &[];
+        }
+        // SAFETY: the array of `naddrs` addresses is allocated from the pool by `ngx_parse_url`
+        unsafe { slice::from_raw_parts(self.0.addrs, self.0.naddrs) }
+    }
+}
+
+impl AsRef<ngx_url_t> for Url {
+    fn as_ref(&self) -> &ngx_url_t {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Url {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Url")
+            .field("host", &self.host())
+            .field("port", &self.port())
+            .field("uri", &self.uri())
+            .field("naddrs", &self.0.naddrs)
+            .finish()
+    }
+}
+
+/// An error returned by [`Url::parse`], carrying the error message from nginx.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UrlError(&'static str);
+
+impl UrlError {
+    /// Returns the error message.
+    pub fn message(&self) -> &'static str {
+        self.0
+    }
+}
+
+impl fmt::Display for UrlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl error::Error for UrlError {}
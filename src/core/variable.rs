@@ -0,0 +1,187 @@
+use core::fmt;
+use core::mem;
+use core::slice;
+
+use crate::core::{NgxStr, Pool};
+use crate::ffi::{ngx_pool_t, ngx_str_t, ngx_variable_value_t};
+
+/// Wrapper for an [`ngx_variable_value_t`], the value of a variable returned by the get handler.
+///
+/// The constructors set the `valid`, `not_found` and `no_cacheable` flags consistently, so that
+/// nginx never caches or reads a value without the data.
+///
+/// ```ignore
+/// extern "C" fn get_handler(
+///     r: *mut ngx_http_request_t,
+///     v: *mut ngx_variable_value_t,
+///     _data: usize,
+/// ) -> ngx_int_t {
+///     let r = unsafe { Request::from_ngx_http_request(r) };
+///     let v = unsafe { VariableValue::from_ptr_mut(v) };
+///
+///     *v = match r.user_agent() {
+///         Some(ua) => match VariableValue::from_bytes(r.pool(), ua.as_bytes()) {
+///             Some(value) => value.no_cacheable(),
+///             None => return Status::NGX_ERROR.into(),
+///         },
+///         None => VariableValue::not_found(),
+///     };
+///
+///     Status::NGX_OK.into()
+/// }
+/// ```
+///
+/// See <https://nginx.org/en/docs/dev/development_guide.html#http_variables>
+#[repr(transparent)]
+pub struct VariableValue(ngx_variable_value_t);
+
+impl VariableValue {
+    /// The maximum length of a value, limited by the size of the `len` bit field.
+    pub const MAX_LEN: usize = (1 << 28) - 1;
+
+    /// Creates a mutable `VariableValue` reference from an `ngx_variable_value_t` pointer.
+    ///
+    /// # Safety
+    ///
+    /// The caller must provide a valid non-null pointer to an `ngx_variable_value_t`, which
+    /// remains valid and is not accessed by other means for the lifetime of the returned
+    /// reference.
+    pub unsafe fn from_ptr_mut<'a>(v: *mut ngx_variable_value_t) -> &'a mut Self {
+        &mut *v.cast()
+    }
+
+    /// Creates a valid value referencing the string, without copying.
+    ///
+    /// The string must remain valid as long as the value is used, e.g. be allocated from the
+    /// request pool or be static. Returns `None` if the string is longer than
+    /// [`MAX_LEN`](Self::MAX_LEN).
+    pub fn from_ngx_str(s: ngx_str_t) -> Option<Self> {
+        if s.len > Self::MAX_LEN {
+            return None;
+        }
+
+        // SAFETY: a zeroed value is an empty value without any flags set
+        let mut v: ngx_variable_value_t = unsafe { mem::zeroed() };
+        v.data = s.data;
+        v.set_len(s.len as _);
+        v.set_valid(1);
+        Some(Self(v))
+    }
+
+    /// Creates a valid value with a copy of the bytes allocated from the pool.
+    ///
+    /// Returns `None` if the allocation fails or the value is too long.
+    pub fn from_bytes(pool: &Pool, bytes: &[u8]) -> Option<Self> {
+        if bytes.len() > Self::MAX_LEN {
+            return None;
+        }
+
+        let pool: *const ngx_pool_t = pool.as_ref();
+        // SAFETY: `Pool` wraps a valid pool
+        Self::from_ngx_str(unsafe { ngx_str_t::from_bytes(pool.cast_mut(), bytes) }?)
+    }
+
+    /// Creates a valid value with a copy of the string allocated from the pool.
+    ///
+    /// Returns `None` if the allocation fails or the value is too long.
+    pub fn from_str(pool: &Pool, s: &str) -> Option<Self> {
+        Self::from_bytes(pool, s.as_bytes())
+    }
+
+    /// Creates a value for a variable that is not found, e.g. a missing header.
+    ///
+    /// Such a value is evaluated to an empty string.
+    pub fn not_found() -> Self {
+        // SAFETY: a zeroed value is an empty value without any flags set
+        let mut v: ngx_variable_value_t = unsafe { mem::zeroed() };
+        v.set_not_found(1);
+        Self(v)
+    }
+
+    /// Marks the value as not cacheable, so the get handler is called on every access within
+    /// the request.
+    pub fn no_cacheable(mut self) -> Self {
+        self.set_no_cacheable(true);
+        self
+    }
+
+    /// Sets or clears the `no_cacheable` flag.
+    pub fn set_no_cacheable(&mut self, no_cacheable: bool) {
+        self.0.set_no_cacheable(no_cacheable as _);
+    }
+
+    /// Returns `true` if the value was evaluated successfully.
+    pub fn is_valid(&self) -> bool {
+        self.0.valid() != 0
+    }
+
+    /// Returns `true` if the variable was not found.
+    pub fn is_not_found(&self) -> bool {
+        self.0.not_found() != 0
+    }
+
+    /// Returns `true` if the value must not be cached.
+    pub fn is_no_cacheable(&self) -> bool {
+        self.0.no_cacheable() != 0
+    }
+
+    /// Returns the value if it is valid.
+    pub fn as_ngx_str(&self) -> Option<&NgxStr> {
+        if !self.is_valid() || self.is_not_found() {
+            return None;
+        }
+
+        let len = self.0.len() as usize;
+        if len == 0 {
+            return Some(NgxStr::from_bytes(&[]));
+        }
+        // SAFETY: a valid value points to `len` bytes of data
+        Some(NgxStr::from_bytes(unsafe {
+            slice::from_raw_parts(self.0.data, len)
+        }))
+    }
+}
+
+impl AsRef<ngx_variable_value_t> for VariableValue {
+    fn as_ref(&self) -> &ngx_variable_value_t {
+        &self.0
+    }
+}
+
+impl AsMut<ngx_variable_value_t> for VariableValue {
+    fn as_mut(&mut self) -> &mut ngx_variable_value_t {
+        &mut self.0
+    }
+}
+
+impl fmt::Debug for VariableValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VariableValue")
+            .field("value", &self.as_ngx_str())
+            .field("valid", &self.is_valid())
+            .field("not_found", &self.is_not_found())
+            .field("no_cacheable", &self.is_no_cacheable())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variable_value() {
+        let v = VariableValue::not_found();
+        assert!(!v.is_valid());
+        assert!(v.is_not_found());
+        assert_eq!(v.as_ngx_str(), None);
+
+        let v = VariableValue::from_ngx_str(crate::ngx_string!("value"))
+            .unwrap()
+            .no_cacheable();
+        assert!(v.is_valid());
+        assert!(!v.is_not_found());
+        assert!(v.is_no_cacheable());
+        assert_eq!(v.as_ngx_str(), Some(NgxStr::from_bytes(b"value")));
+    }
+}
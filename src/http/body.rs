@@ -0,0 +1,384 @@
+use core::cell::RefCell;
+use core::error;
+use core::ffi::c_void;
+use core::fmt;
+use core::future::Future;
+use core::mem;
+use core::pin::Pin;
+use core::ptr;
+use core::task::{Context, Poll, Waker};
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::{rc::Rc, vec::Vec};
+#[cfg(feature = "std")]
+use std::{io, rc::Rc, vec::Vec};
+
+use bytes::Bytes;
+
+use crate::core::{Buffer, Status};
+use crate::ffi::{
+    ngx_http_cleanup_add, ngx_http_event_handler_pt, ngx_http_finalize_request,
+    ngx_http_read_client_request_body, ngx_http_request_t, ngx_int_t, NGX_HTTP_SPECIAL_RESPONSE,
+};
+use crate::http::{HTTPStatus, Request};
+
+/// An error reading the request body with [`Request::body_bytes`].
+///
+/// The HTTP status the request should be finalized with is available with
+/// [`RequestBodyError::status`]:
+///
+/// ```ignore
+/// let status = match request.body_bytes().await {
+///     Ok(body) => process(request, body),
+///     Err(err) if err.is_finalized() => return Status::NGX_DONE,
+///     Err(err) => err.status(),
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RequestBodyError {
+    /// Reading the body failed with the HTTP status, e.g. `413 Request Entity Too Large` if the
+    /// body exceeds `client_max_body_size`.
+    ///
+    /// `finalized` is set if nginx has already finalized the request with the status, see
+    /// [`RequestBodyError::is_finalized`].
+    Status {
+        /// The HTTP status of the failure.
+        status: HTTPStatus,
+        /// Whether the request is already finalized by nginx.
+        finalized: bool,
+    },
+    /// Memory allocation for the body failed.
+    OutOfMemory,
+    /// The temporary file with the body is shorter than expected.
+    UnexpectedEof,
+}
+
+impl RequestBodyError {
+    /// Returns the HTTP status for the error; `500 Internal Server Error` for the errors without
+    /// a status.
+    pub fn status(&self) -> HTTPStatus {
+        match self {
+            Self::Status { status, .. } => *status,
+            _ => HTTPStatus::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Returns `true` if nginx has already finalized the request with the error status.
+    ///
+    /// This happens when reading fails after the body read was started, e.g. on a read timeout
+    /// or a chunked body exceeding `client_max_body_size`. The error response is already sent,
+    /// so the request should only be released, without awaiting anything else first.
+    pub fn is_finalized(&self) -> bool {
+        matches!(
+            self,
+            Self::Status {
+                finalized: true,
+                ..
+            }
+        )
+    }
+}
+
+impl fmt::Display for RequestBodyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Status { status, .. } => write!(f, "reading request body failed: {status:?}"),
+            Self::OutOfMemory => f.write_str("reading request body failed: out of memory"),
+            Self::UnexpectedEof => {
+                f.write_str("reading request body failed: unexpected end of file")
+            }
+        }
+    }
+}
+
+impl error::Error for RequestBodyError {}
+
+#[cfg(feature = "std")]
+impl From<RequestBodyError> for io::Error {
+    fn from(err: RequestBodyError) -> Self {
+        let kind = match err {
+            RequestBodyError::Status { .. } => io::ErrorKind::Other,
+            RequestBodyError::OutOfMemory => io::ErrorKind::OutOfMemory,
+            RequestBodyError::UnexpectedEof => io::ErrorKind::UnexpectedEof,
+        };
+        io::Error::new(kind, err)
+    }
+}
+
+impl Request {
+    /// Reads the whole request body into a single [`Bytes`].
+    ///
+    /// Both the in-memory buffers and the buffers written to a temporary file are collected; an
+    /// empty [`Bytes`] is returned for a request without a body. A failure to read the body is
+    /// reported as a [`RequestBodyError`] with the status nginx returned, e.g.
+    /// `413 Request Entity Too Large` if the body exceeds `client_max_body_size`.
+    ///
+    /// The future must be polled on the nginx event loop, e.g. in a task created with
+    /// `ngx::async_::spawn`, while the request is kept alive by the handler.
+    ///
+    /// ```ignore
+    /// let body = match request.body_bytes().await {
+    ///     Ok(body) => body,
+    ///     Err(err) => return finalize_with_error(request, err),
+    /// };
+    /// ```
+    pub fn body_bytes(&mut self) -> ReadBodyBytes<'_> {
+        ReadBodyBytes {
+            request: self,
+            state: None,
+        }
+    }
+}
+
+/// Future returned by [`Request::body_bytes`].
+pub struct ReadBodyBytes<'a> {
+    request: &'a mut Request,
+    state: Option<Rc<RefCell<BodyReadState>>>,
+}
+
+struct BodyReadState {
+    waker: Option<Waker>,
+    result: Option<Result<(), RequestBodyError>>,
+    read_event_handler: ngx_http_event_handler_pt,
+}
+
+impl BodyReadState {
+    fn complete(state: &RefCell<Self>, result: Result<(), RequestBodyError>) {
+        let waker = {
+            let mut state = state.borrow_mut();
+            state.result = Some(result);
+            state.waker.take()
+        };
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// The request cleanup holding the body read state.
+///
+/// nginx does not call the body post handler if reading fails after
+/// `ngx_http_read_client_request_body` returned; the cleanup completes the read with an error
+/// when the request is terminated or freed before that.
+struct BodyReadCleanup {
+    request: *mut ngx_http_request_t,
+    state: Rc<RefCell<BodyReadState>>,
+}
+
+impl ReadBodyBytes<'_> {
+    /// Starts reading the body, registering the state in the request cleanups.
+    fn start(&mut self, cx: &Context<'_>) -> Result<Rc<RefCell<BodyReadState>>, RequestBodyError> {
+        let r = ptr::from_mut::<ngx_http_request_t>(self.request.as_mut());
+
+        let state = Rc::new(RefCell::new(BodyReadState {
+            waker: Some(cx.waker().clone()),
+            result: None,
+            read_event_handler: None,
+        }));
+
+        // SAFETY: `r` is a valid request; the cleanup data is initialized before the handler is
+        // set.
+        unsafe {
+            let cln = ngx_http_cleanup_add(r, mem::size_of::<BodyReadCleanup>());
+            if cln.is_null() {
+                return Err(RequestBodyError::OutOfMemory);
+            }
+
+            ptr::write(
+                (*cln).data.cast::<BodyReadCleanup>(),
+                BodyReadCleanup {
+                    request: r,
+                    state: state.clone(),
+                },
+            );
+            (*cln).handler = Some(body_read_cleanup);
+        }
+        self.state = Some(state.clone());
+
+        // The reference taken by `ngx_http_read_client_request_body` is released by the post
+        // handler, or by nginx finalizing the request with an error. The request stays alive
+        // for the future either way.
+        //
+        // SAFETY: `r` is a valid request
+        let rc = unsafe { ngx_http_read_client_request_body(r, Some(body_read_post_handler)) };
+        if rc >= NGX_HTTP_SPECIAL_RESPONSE as ngx_int_t {
+            let err = RequestBodyError::Status {
+                status: HTTPStatus(rc as _),
+                finalized: false,
+            };
+            state.borrow_mut().result = Some(Err(err));
+            return Err(err);
+        }
+
+        if state.borrow().result.is_none() {
+            // Watch for the read stopping without the post handler call.
+            // SAFETY: `r` is a valid request
+            unsafe {
+                state.borrow_mut().read_event_handler = (*r).read_event_handler;
+                (*r).read_event_handler = Some(body_read_event_handler);
+            }
+        }
+
+        Ok(state)
+    }
+
+    /// Collects the read body into a single buffer.
+    fn collect(&self) -> Result<Bytes, RequestBodyError> {
+        let Some(chain) = self.request.request_body() else {
+            return Ok(Bytes::new());
+        };
+
+        let mut total = 0usize;
+        for buf in chain.iter() {
+            total = total.saturating_add(if buf.in_file() {
+                let range = buf.file_range();
+                usize::try_from(range.end - range.start).unwrap_or(usize::MAX)
+            } else {
+                buf.len()
+            });
+        }
+
+        let mut body = Vec::new();
+        body.try_reserve_exact(total)
+            .map_err(|_| RequestBodyError::OutOfMemory)?;
+
+        for buf in chain.iter() {
+            if !buf.in_file() {
+                body.extend_from_slice(buf.as_bytes());
+                continue;
+            }
+
+            let start = body.len();
+            let range = buf.file_range();
+            let len = usize::try_from(range.end - range.start).unwrap_or(usize::MAX);
+            body.resize(start + len, 0);
+
+            let mut offset = 0;
+            while offset < len {
+                match buf.read_file(offset, &mut body[start + offset..]) {
+                    Ok(0) => return Err(RequestBodyError::UnexpectedEof),
+                    Ok(n) => offset += n,
+                    Err(_) => {
+                        return Err(RequestBodyError::Status {
+                            status: HTTPStatus::INTERNAL_SERVER_ERROR,
+                            finalized: false,
+                        })
+                    }
+                }
+            }
+        }
+
+        Ok(Bytes::from(body))
+    }
+}
+
+impl Future for ReadBodyBytes<'_> {
+    type Output = Result<Bytes, RequestBodyError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        let state = match &this.state {
+            Some(state) => state.clone(),
+            None => this.start(cx)?,
+        };
+
+        let mut state = state.borrow_mut();
+        match state.result {
+            None => {
+                if !state
+                    .waker
+                    .as_ref()
+                    .is_some_and(|w| w.will_wake(cx.waker()))
+                {
+                    state.waker = Some(cx.waker().clone());
+                }
+                Poll::Pending
+            }
+            Some(Err(err)) => Poll::Ready(Err(err)),
+            Some(Ok(())) => {
+                drop(state);
+                Poll::Ready(this.collect())
+            }
+        }
+    }
+}
+
+impl Drop for ReadBodyBytes<'_> {
+    fn drop(&mut self) {
+        // The body may still be read after the future is dropped; do not wake a stale task.
+        if let Some(state) = &self.state {
+            state.borrow_mut().waker = None;
+        }
+    }
+}
+
+/// Returns the body read state registered in the cleanups of the main request.
+unsafe fn body_read_state(r: *mut ngx_http_request_t) -> Option<Rc<RefCell<BodyReadState>>> {
+    let mut cln = (*(*r).main).cleanup;
+    while !cln.is_null() {
+        if (*cln)
+            .handler
+            .is_some_and(|h| h as usize == body_read_cleanup as usize)
+        {
+            return Some((*(*cln).data.cast::<BodyReadCleanup>()).state.clone());
+        }
+        cln = (*cln).next;
+    }
+    None
+}
+
+/// Maps the status the request was finalized with to a [`RequestBodyError`].
+unsafe fn finalized_error(r: *mut ngx_http_request_t) -> RequestBodyError {
+    let status = (*(*r).main).headers_out.status;
+    RequestBodyError::Status {
+        status: if status >= NGX_HTTP_SPECIAL_RESPONSE as _ {
+            HTTPStatus(status)
+        } else {
+            HTTPStatus::INTERNAL_SERVER_ERROR
+        },
+        finalized: true,
+    }
+}
+
+unsafe extern "C" fn body_read_cleanup(data: *mut c_void) {
+    let cln = ptr::read(data.cast::<BodyReadCleanup>());
+    if cln.state.borrow().result.is_none() {
+        // The request is terminated, e.g. on a read timeout, or freed after being finalized
+        // with an error.
+        BodyReadState::complete(&cln.state, Err(finalized_error(cln.request)));
+    }
+}
+
+unsafe extern "C" fn body_read_event_handler(r: *mut ngx_http_request_t) {
+    let Some(state) = body_read_state(r) else {
+        return;
+    };
+
+    let handler = state.borrow().read_event_handler;
+    if let Some(handler) = handler {
+        handler(r);
+    }
+
+    // The request may be already released by the woken task if the read is completed.
+    if state.borrow().result.is_some() {
+        return;
+    }
+
+    // nginx replaces the read event handler when it finalizes the request with an error, e.g.
+    // `413 Request Entity Too Large` for a chunked body, without calling the post handler.
+    if (*r).read_event_handler.map(|h| h as usize) != Some(body_read_event_handler as usize) {
+        BodyReadState::complete(&state, Err(finalized_error(r)));
+    }
+}
+
+unsafe extern "C" fn body_read_post_handler(r: *mut ngx_http_request_t) {
+    // Release the reference taken by `ngx_http_read_client_request_body` first; the woken task
+    // may finalize the request.
+    ngx_http_finalize_request(r, Status::NGX_DONE.0);
+
+    if let Some(state) = body_read_state(r) {
+        BodyReadState::complete(&state, Ok(()));
+    }
+}
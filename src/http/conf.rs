@@ -1,5 +1,6 @@
 use ::core::ptr::NonNull;
 
+use crate::core::NgxConfRef;
 use crate::ffi::{
     ngx_http_conf_ctx_t, ngx_http_core_srv_conf_t, ngx_http_request_t,
     ngx_http_upstream_srv_conf_t, ngx_module_t,
@@ -74,6 +75,23 @@ impl HttpModuleConfExt for crate::ffi::ngx_conf_t {
     }
 }
 
+impl HttpModuleConfExt for NgxConfRef {
+    #[inline]
+    unsafe fn http_main_conf_unchecked<T>(&self, module: &ngx_module_t) -> Option<NonNull<T>> {
+        self.as_ref().http_main_conf_unchecked(module)
+    }
+
+    #[inline]
+    unsafe fn http_server_conf_unchecked<T>(&self, module: &ngx_module_t) -> Option<NonNull<T>> {
+        self.as_ref().http_server_conf_unchecked(module)
+    }
+
+    #[inline]
+    unsafe fn http_location_conf_unchecked<T>(&self, module: &ngx_module_t) -> Option<NonNull<T>> {
+        self.as_ref().http_location_conf_unchecked(module)
+    }
+}
+
 impl HttpModuleConfExt for ngx_http_core_srv_conf_t {
     #[inline]
     unsafe fn http_main_conf_unchecked<T>(&self, module: &ngx_module_t) -> Option<NonNull<T>> {
@@ -131,6 +149,11 @@ pub unsafe trait HttpModuleMainConf: HttpModule {
     /// Type for main module configuration
     type MainConf;
     /// Get reference to main module configuration
+    ///
+    /// `o` can be any of the configuration containers, including a [`Request`], e.g.
+    /// `Module::main_conf(request)` in a request handler.
+    ///
+    /// [`Request`]: crate::http::Request
     fn main_conf(o: &impl HttpModuleConfExt) -> Option<&'static Self::MainConf> {
         unsafe { Some(o.http_main_conf_unchecked(Self::module())?.as_ref()) }
     }
@@ -149,6 +172,11 @@ pub unsafe trait HttpModuleServerConf: HttpModule {
     /// Type for server-specific module configuration
     type ServerConf;
     /// Get reference to server-specific module configuration
+    ///
+    /// For a [`Request`], the configuration of the virtual server selected for the request is
+    /// returned.
+    ///
+    /// [`Request`]: crate::http::Request
     fn server_conf(o: &impl HttpModuleConfExt) -> Option<&'static Self::ServerConf> {
         unsafe { Some(o.http_server_conf_unchecked(Self::module())?.as_ref()) }
     }
@@ -179,14 +207,65 @@ pub unsafe trait HttpModuleLocationConf: HttpModule {
 }
 
 mod core {
+    use ::core::mem;
+
+    use crate::core::NgxConfRef;
     use crate::ffi::{
         ngx_http_core_loc_conf_t, ngx_http_core_main_conf_t, ngx_http_core_module,
-        ngx_http_core_srv_conf_t,
+        ngx_http_core_srv_conf_t, ngx_http_handler_pt, NGX_LOG_WARN,
     };
+    use crate::http::HttpModuleLocationConf;
 
     /// Auxiliary structure to access `ngx_http_core_module` configuration.
     pub struct NgxHttpCoreModule;
 
+    impl NgxHttpCoreModule {
+        /// Sets the content handler of the location, returning the previous handler.
+        ///
+        /// The content handler replaces the content phase handlers for the requests processed in
+        /// the location, as with the `proxy_pass` or `fastcgi_pass` directives.
+        pub fn set_content_handler(
+            clcf: &mut ngx_http_core_loc_conf_t,
+            handler: ngx_http_handler_pt,
+        ) -> ngx_http_handler_pt {
+            mem::replace(&mut clcf.handler, handler)
+        }
+
+        /// Sets the content handler of the location being parsed, e.g. from a directive handler.
+        ///
+        /// Logs a warning if a content handler is already set for the location, e.g. by a
+        /// duplicate directive or another module's directive. Returns `None` outside of the HTTP
+        /// configuration.
+        ///
+        /// ```rust,ignore
+        /// extern "C" fn ngx_http_example_set(
+        ///     cf: *mut ngx_conf_t,
+        ///     _cmd: *mut ngx_command_t,
+        ///     _conf: *mut c_void,
+        /// ) -> *mut c_char {
+        ///     let cf = unsafe { NgxConfRef::from_ptr_mut(cf) };
+        ///     match NgxHttpCoreModule::set_conf_content_handler(cf, Some(example_handler)) {
+        ///         Some(()) => NGX_CONF_OK,
+        ///         None => NGX_CONF_ERROR,
+        ///     }
+        /// }
+        /// ```
+        pub fn set_conf_content_handler(
+            cf: &mut NgxConfRef,
+            handler: ngx_http_handler_pt,
+        ) -> Option<()> {
+            let clcf = Self::location_conf_mut(&*cf)?;
+            if Self::set_content_handler(clcf, handler).is_some() {
+                cf.log_error(
+                    NGX_LOG_WARN,
+                    format_args!("content handler is already set for the location, replacing"),
+                );
+            }
+
+            Some(())
+        }
+    }
+
     impl crate::http::HttpModule for NgxHttpCoreModule {
         fn module() -> &'static crate::ffi::ngx_module_t {
             unsafe { &*::core::ptr::addr_of!(ngx_http_core_module) }
@@ -0,0 +1,229 @@
+use core::cell::UnsafeCell;
+use core::ptr;
+
+use crate::core::{Chain, Status};
+use crate::ffi::{
+    ngx_chain_t, ngx_http_request_t, ngx_http_top_body_filter, ngx_http_top_header_filter,
+    ngx_int_t,
+};
+use crate::http::{HttpModule, Request};
+
+/// Storage for the next output filter in the chain, saved when a filter is installed.
+///
+/// The filter chains are built during the configuration and only read at runtime, on the same
+/// thread. A filter module should declare a static instance for each filter it installs.
+///
+/// ```ignore
+/// static NEXT_BODY_FILTER: NextBodyFilter = NextFilter::new();
+/// ```
+pub struct NextFilter<F>(UnsafeCell<Option<F>>);
+
+/// Storage for the next body filter, see [`HttpBodyFilter`].
+pub type NextBodyFilter =
+    NextFilter<unsafe extern "C" fn(*mut ngx_http_request_t, *mut ngx_chain_t) -> ngx_int_t>;
+
+/// Storage for the next header filter, see [`HttpHeaderFilter`].
+pub type NextHeaderFilter = NextFilter<unsafe extern "C" fn(*mut ngx_http_request_t) -> ngx_int_t>;
+
+// SAFETY: the value is only modified during the configuration, before the worker processes start
+// serving requests, and nginx event loop is single-threaded.
+unsafe impl<F: Sync> Sync for NextFilter<F> {}
+
+impl<F: Copy> NextFilter<F> {
+    /// Creates an empty slot for the next filter.
+    pub const fn new() -> Self {
+        Self(UnsafeCell::new(None))
+    }
+
+    /// Returns the saved filter, if any.
+    pub fn get(&self) -> Option<F> {
+        // SAFETY: see the `Sync` implementation
+        unsafe { *self.0.get() }
+    }
+
+    /// Saves the next filter.
+    ///
+    /// # Safety
+    ///
+    /// Must only be called during the configuration, on the main thread.
+    pub unsafe fn set(&self, filter: Option<F>) {
+        *self.0.get() = filter;
+    }
+}
+
+impl<F: Copy> Default for NextFilter<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The `HttpBodyFilter` trait allows a module to inspect and modify the response body.
+///
+/// The filter is installed at the top of the body filter chain with
+/// [`install_body_filter`](HttpBodyFilter::install_body_filter), usually from the
+/// [`HttpModule::postconfiguration`] callback. The module must be listed among the HTTP filter
+/// modules in the build configuration to be placed in the chain correctly.
+///
+/// See <https://nginx.org/en/docs/dev/development_guide.html#http_response_body_filters>
+///
+/// ```ignore
+/// static NEXT_BODY_FILTER: NextBodyFilter = NextFilter::new();
+///
+/// impl HttpBodyFilter for Module {
+///     fn next_body_filter() -> &'static NextBodyFilter {
+///         &NEXT_BODY_FILTER
+///     }
+///
+///     fn body_filter(r: &mut Request, chain: Option<&mut Chain>) -> Status {
+///         if let Some(chain) = chain.as_deref() {
+///             ngx_log_debug_http!(r, "body filter: {} bytes", chain.total_len());
+///         }
+///         Self::call_next_body_filter(r, chain)
+///     }
+/// }
+///
+/// impl HttpModule for Module {
+///     unsafe extern "C" fn postconfiguration(_cf: *mut ngx_conf_t) -> ngx_int_t {
+///         Self::install_body_filter();
+///         Status::NGX_OK.into()
+///     }
+///     // ...
+/// }
+/// ```
+pub trait HttpBodyFilter: HttpModule {
+    /// Returns the storage for the next body filter in the chain.
+    fn next_body_filter() -> &'static NextBodyFilter;
+
+    /// Processes a part of the response body.
+    ///
+    /// The chain is `None` when the filter is called to flush the data buffered in the filters.
+    /// The implementation is expected to pass the resulting chain to the next filter with
+    /// [`call_next_body_filter`](HttpBodyFilter::call_next_body_filter).
+    fn body_filter(r: &mut Request, chain: Option<&mut Chain>) -> Status;
+
+    /// Passes the chain to the next body filter.
+    fn call_next_body_filter(r: &mut Request, chain: Option<&mut Chain>) -> Status {
+        let Some(next) = Self::next_body_filter().get() else {
+            return Status::NGX_ERROR;
+        };
+
+        let chain = chain.map_or(ptr::null_mut(), |cl| ptr::from_mut(cl.as_mut()));
+        // SAFETY: the next filter is a valid nginx body filter
+        Status(unsafe { next(r.into(), chain) })
+    }
+
+    /// Installs the filter at the top of the body filter chain, saving the previous top filter.
+    ///
+    /// # Safety
+    ///
+    /// Must only be called once, during the configuration, e.g. from the
+    /// [`HttpModule::postconfiguration`] callback.
+    unsafe fn install_body_filter() {
+        Self::next_body_filter().set(ngx_http_top_body_filter);
+        ngx_http_top_body_filter = Some(Self::body_filter_handler);
+    }
+
+    /// The body filter entry point called by nginx.
+    ///
+    /// # Safety
+    ///
+    /// Callers should provide a valid non-null request and a valid or null chain pointer.
+    unsafe extern "C" fn body_filter_handler(
+        r: *mut ngx_http_request_t,
+        chain: *mut ngx_chain_t,
+    ) -> ngx_int_t {
+        let r = Request::from_ngx_http_request(r);
+        let chain = if chain.is_null() {
+            None
+        } else {
+            Some(Chain::from_ptr_mut(chain))
+        };
+
+        Self::body_filter(r, chain).into()
+    }
+}
+
+/// The `HttpHeaderFilter` trait allows a module to inspect and modify the response status and
+/// headers before they are sent.
+///
+/// The filter is installed at the top of the header filter chain with
+/// [`install_header_filter`](HttpHeaderFilter::install_header_filter), usually from the
+/// [`HttpModule::postconfiguration`] callback. The module must be listed among the HTTP filter
+/// modules in the build configuration to be placed in the chain correctly.
+///
+/// See <https://nginx.org/en/docs/dev/development_guide.html#http_response_header_filters>
+///
+/// The following filter replaces the `Server` header:
+///
+/// ```ignore
+/// static NEXT_HEADER_FILTER: NextHeaderFilter = NextFilter::new();
+///
+/// impl HttpHeaderFilter for Module {
+///     fn next_header_filter() -> &'static NextHeaderFilter {
+///         &NEXT_HEADER_FILTER
+///     }
+///
+///     fn header_filter(r: &mut Request) -> Status {
+///         r.remove_header_out("Server");
+///
+///         let Some(server) = r.push_header_out("Server", "example") else {
+///             return Status::NGX_ERROR;
+///         };
+///
+///         // A non-null `headers_out.server` disables the header generated by nginx.
+///         let server: *mut ngx_table_elt_t = server.as_mut();
+///         r.as_mut().headers_out.server = server;
+///
+///         Self::call_next_header_filter(r)
+///     }
+/// }
+///
+/// impl HttpModule for Module {
+///     unsafe extern "C" fn postconfiguration(_cf: *mut ngx_conf_t) -> ngx_int_t {
+///         Self::install_header_filter();
+///         Status::NGX_OK.into()
+///     }
+///     // ...
+/// }
+/// ```
+pub trait HttpHeaderFilter: HttpModule {
+    /// Returns the storage for the next header filter in the chain.
+    fn next_header_filter() -> &'static NextHeaderFilter;
+
+    /// Processes the response header.
+    ///
+    /// The implementation may modify the status and `headers_out` of the request, and is expected
+    /// to delegate to the next filter with
+    /// [`call_next_header_filter`](HttpHeaderFilter::call_next_header_filter).
+    fn header_filter(r: &mut Request) -> Status;
+
+    /// Passes the request to the next header filter.
+    fn call_next_header_filter(r: &mut Request) -> Status {
+        let Some(next) = Self::next_header_filter().get() else {
+            return Status::NGX_ERROR;
+        };
+
+        // SAFETY: the next filter is a valid nginx header filter
+        Status(unsafe { next(r.into()) })
+    }
+
+    /// Installs the filter at the top of the header filter chain, saving the previous top filter.
+    ///
+    /// # Safety
+    ///
+    /// Must only be called once, during the configuration, e.g. from the
+    /// [`HttpModule::postconfiguration`] callback.
+    unsafe fn install_header_filter() {
+        Self::next_header_filter().set(ngx_http_top_header_filter);
+        ngx_http_top_header_filter = Some(Self::header_filter_handler);
+    }
+
+    /// The header filter entry point called by nginx.
+    ///
+    /// # Safety
+    ///
+    /// Callers should provide a valid non-null request pointer.
+    unsafe extern "C" fn header_filter_handler(r: *mut ngx_http_request_t) -> ngx_int_t {
+        Self::header_filter(Request::from_ngx_http_request(r)).into()
+    }
+}
@@ -0,0 +1,140 @@
+//! Conversions between the nginx request data and the types of the [`http`](::http) crate.
+//!
+//! The request line and the headers are not guaranteed to be valid UTF-8, thus the conversions
+//! operate on bytes and report the invalid values as errors instead of panicking.
+//!
+//! The [`HeaderName`], [`HeaderValue`] and [`Uri`] types implement `TryFrom<&NgxStr>`, and the
+//! functions below are shorthands for these conversions.
+
+use core::slice;
+
+use ::http::header::{HeaderName, HeaderValue, InvalidHeaderName, InvalidHeaderValue};
+use ::http::method::InvalidMethod;
+use ::http::uri::{InvalidUri, Uri};
+use ::http::{request, Version};
+
+use crate::allocator::AllocError;
+use crate::core::{NgxStr, Pool};
+use crate::ffi::{ngx_list_part_t, ngx_pool_t, ngx_str_t, ngx_table_elt_t};
+use crate::http::{HttpVersion, Request};
+
+impl TryFrom<&NgxStr> for HeaderName {
+    type Error = InvalidHeaderName;
+
+    fn try_from(name: &NgxStr) -> Result<Self, Self::Error> {
+        HeaderName::from_bytes(name.as_bytes())
+    }
+}
+
+impl TryFrom<&NgxStr> for HeaderValue {
+    type Error = InvalidHeaderValue;
+
+    fn try_from(value: &NgxStr) -> Result<Self, Self::Error> {
+        HeaderValue::from_bytes(value.as_bytes())
+    }
+}
+
+impl TryFrom<&NgxStr> for Uri {
+    type Error = InvalidUri;
+
+    fn try_from(uri: &NgxStr) -> Result<Self, Self::Error> {
+        Uri::try_from(uri.as_bytes())
+    }
+}
+
+/// Converts a header name.
+pub fn header_name(name: &NgxStr) -> Result<HeaderName, InvalidHeaderName> {
+    HeaderName::try_from(name)
+}
+
+/// Converts a header value.
+///
+/// Unlike the request line, the header values may contain the bytes outside of the ASCII range.
+pub fn header_value(value: &NgxStr) -> Result<HeaderValue, InvalidHeaderValue> {
+    HeaderValue::try_from(value)
+}
+
+/// Copies a header value to the pool, e.g. to add it to the response headers.
+pub fn header_value_to_ngx_str(pool: &Pool, value: &HeaderValue) -> Result<ngx_str_t, AllocError> {
+    let pool: *const ngx_pool_t = pool.as_ref();
+    // SAFETY: `Pool` wraps a valid pool
+    unsafe { ngx_str_t::from_bytes(pool.cast_mut(), value.as_bytes()) }.ok_or(AllocError)
+}
+
+/// Converts a request URI, either absolute or relative.
+pub fn uri(uri: &NgxStr) -> Result<Uri, InvalidUri> {
+    Uri::try_from(uri)
+}
+
+/// Converts the request method, including the methods not known to this crate.
+pub fn method(r: &Request) -> Result<::http::Method, InvalidMethod> {
+    ::http::Method::from_bytes(r.as_ref().method_name.as_bytes())
+}
+
+/// Converts the HTTP version, returning `None` for an unknown version.
+pub fn version(version: HttpVersion) -> Option<Version> {
+    match version {
+        HttpVersion::Http09 => Some(Version::HTTP_09),
+        HttpVersion::Http10 => Some(Version::HTTP_10),
+        HttpVersion::Http11 => Some(Version::HTTP_11),
+        HttpVersion::Http2 => Some(Version::HTTP_2),
+        HttpVersion::Http3 => Some(Version::HTTP_3),
+        HttpVersion::Unknown(_) => None,
+    }
+}
+
+/// Creates a request builder with the method, URI, version and headers of the request.
+///
+/// The URI is the unparsed request URI, as sent by the client. The deleted headers are skipped.
+/// Any conversion errors are reported by [`request::Builder::body`].
+pub fn request_builder(r: &Request) -> request::Builder {
+    let mut builder = request::Builder::new()
+        .method(r.as_ref().method_name.as_bytes())
+        .uri(r.unparsed_uri().as_bytes());
+
+    if let Some(version) = version(r.version()) {
+        builder = builder.version(version);
+    }
+
+    let mut part: *const ngx_list_part_t = &r.as_ref().headers_in.headers.part;
+
+    // SAFETY: the list contains `ngx_table_elt_t` elements
+    while let Some(p) = unsafe { part.as_ref() } {
+        let elts: &[ngx_table_elt_t] = if p.nelts != 0 && !p.elts.is_null() {
+            unsafe { slice::from_raw_parts(p.elts.cast(), p.nelts) }
+        } else {
+            &[]
+        };
+
+        // The headers with zero hash are deleted, e.g. by another module.
+        for h in elts.iter().filter(|h| h.hash != 0) {
+            builder = builder.header(h.key.as_bytes(), h.value.as_bytes());
+        }
+
+        part = p.next;
+    }
+
+    builder
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_try_from_ngx_str() {
+        let name = HeaderName::try_from(NgxStr::from_bytes(b"X-Foo")).unwrap();
+        assert_eq!(name.as_str(), "x-foo");
+        assert!(HeaderName::try_from(NgxStr::from_bytes(b"X Foo")).is_err());
+
+        // the values are not required to be valid UTF-8
+        let value = HeaderValue::try_from(NgxStr::from_bytes(b"caf\xe9")).unwrap();
+        assert_eq!(value.as_bytes(), b"caf\xe9");
+        assert!(HeaderValue::try_from(NgxStr::from_bytes(b"a\r\nb")).is_err());
+
+        let uri = Uri::try_from(NgxStr::from_bytes(b"/path?arg=1")).unwrap();
+        assert_eq!(uri.path(), "/path");
+        assert_eq!(uri.query(), Some("arg=1"));
+        assert!(Uri::try_from(NgxStr::from_bytes(b"/a b")).is_err());
+    }
+}
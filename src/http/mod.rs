@@ -1,10 +1,20 @@
+#[cfg(all(feature = "alloc", feature = "bytes"))]
+mod body;
 mod conf;
+mod filter;
+#[cfg(feature = "http")]
+pub mod interop;
 mod module;
+mod parse;
 mod request;
 mod status;
-mod upstream;
+pub mod upstream;
 
+#[cfg(all(feature = "alloc", feature = "bytes"))]
+pub use body::*;
 pub use conf::*;
+pub use filter::*;
 pub use module::*;
+pub use parse::*;
 pub use request::*;
 pub use status::*;
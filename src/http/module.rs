@@ -42,12 +42,78 @@ impl Merge for () {
     }
 }
 
+/// Derive macro generating the field-wise [`Merge`] implementation.
+///
+/// See [`MergeField`] for the default merging behavior.
+#[cfg(feature = "derive")]
+pub use ngx_rust_derive::Merge;
+
+/// Re-exported for the [`Merge`] derive macro.
+pub use crate::core::MergeField;
+
 /// The `HTTPModule` trait provides the NGINX configuration stage interface.
 ///
 /// These functions allocate structures, initialize them, and merge through the configuration
 /// layers.
 ///
 /// See <https://nginx.org/en/docs/dev/development_guide.html#adding_new_modules> for details.
+///
+/// The configuration callbacks are provided for any module implementing the corresponding
+/// [`HttpModuleMainConf`], [`HttpModuleServerConf`] or [`HttpModuleLocationConf`] trait, as long as
+/// the configuration type implements [`Default`] and, where applicable, [`Merge`].
+///
+/// [`HttpModuleMainConf`]: super::HttpModuleMainConf
+/// [`HttpModuleServerConf`]: super::HttpModuleServerConf
+/// [`HttpModuleLocationConf`]: super::HttpModuleLocationConf
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use ngx::ffi::{ngx_http_module_t, ngx_module_t, NGX_HTTP_MODULE};
+/// use ngx::http::{HttpModule, HttpModuleLocationConf, Merge, MergeConfigError};
+///
+/// struct Module;
+///
+/// #[derive(Default)]
+/// struct LocationConf {
+///     enable: bool,
+/// }
+///
+/// impl Merge for LocationConf {
+///     fn merge(&mut self, prev: &Self) -> Result<(), MergeConfigError> {
+///         self.enable |= prev.enable;
+///         Ok(())
+///     }
+/// }
+///
+/// impl HttpModule for Module {
+///     fn module() -> &'static ngx_module_t {
+///         unsafe { &*core::ptr::addr_of!(ngx_http_example_module) }
+///     }
+/// }
+///
+/// unsafe impl HttpModuleLocationConf for Module {
+///     type LocationConf = LocationConf;
+/// }
+///
+/// static NGX_HTTP_EXAMPLE_MODULE_CTX: ngx_http_module_t = ngx_http_module_t {
+///     preconfiguration: Some(Module::preconfiguration),
+///     postconfiguration: Some(Module::postconfiguration),
+///     create_main_conf: None,
+///     init_main_conf: None,
+///     create_srv_conf: None,
+///     merge_srv_conf: None,
+///     create_loc_conf: Some(Module::create_loc_conf),
+///     merge_loc_conf: Some(Module::merge_loc_conf),
+/// };
+///
+/// #[allow(non_upper_case_globals)]
+/// static mut ngx_http_example_module: ngx_module_t = ngx_module_t {
+///     ctx: core::ptr::addr_of!(NGX_HTTP_EXAMPLE_MODULE_CTX) as _,
+///     type_: NGX_HTTP_MODULE as _,
+///     ..ngx_module_t::default()
+/// };
+/// ```
 pub trait HttpModule {
     /// Returns reference to a global variable of type [ngx_module_t] created for this module.
     fn module() -> &'static ngx_module_t;
@@ -68,6 +134,8 @@ pub trait HttpModule {
         Status::NGX_OK.into()
     }
 
+    /// Allocates the default main configuration in the configuration pool.
+    ///
     /// # Safety
     ///
     /// Callers should provide valid non-null `ngx_conf_t` arguments. Implementers must
@@ -77,10 +145,12 @@ pub trait HttpModule {
         Self: super::HttpModuleMainConf,
         Self::MainConf: Default,
     {
-        let mut pool = Pool::from_ngx_pool((*cf).pool);
+        let pool = Pool::from_ngx_pool((*cf).pool);
         pool.allocate::<Self::MainConf>(Default::default()) as *mut c_void
     }
 
+    /// Initializes the main configuration. The default implementation does nothing.
+    ///
     /// # Safety
     ///
     /// Callers should provide valid non-null `ngx_conf_t` arguments. Implementers must
@@ -93,6 +163,8 @@ pub trait HttpModule {
         ptr::null_mut()
     }
 
+    /// Allocates the default server configuration in the configuration pool.
+    ///
     /// # Safety
     ///
     /// Callers should provide valid non-null `ngx_conf_t` arguments. Implementers must
@@ -102,10 +174,13 @@ pub trait HttpModule {
         Self: super::HttpModuleServerConf,
         Self::ServerConf: Default,
     {
-        let mut pool = Pool::from_ngx_pool((*cf).pool);
+        let pool = Pool::from_ngx_pool((*cf).pool);
         pool.allocate::<Self::ServerConf>(Default::default()) as *mut c_void
     }
 
+    /// Merges the server configuration with the configuration from the enclosing level using
+    /// [`Merge`].
+    ///
     /// # Safety
     ///
     /// Callers should provide valid non-null `ngx_conf_t` arguments. Implementers must
@@ -127,6 +202,8 @@ pub trait HttpModule {
         }
     }
 
+    /// Allocates the default location configuration in the configuration pool.
+    ///
     /// # Safety
     ///
     /// Callers should provide valid non-null `ngx_conf_t` arguments. Implementers must
@@ -136,10 +213,13 @@ pub trait HttpModule {
         Self: super::HttpModuleLocationConf,
         Self::LocationConf: Default,
     {
-        let mut pool = Pool::from_ngx_pool((*cf).pool);
+        let pool = Pool::from_ngx_pool((*cf).pool);
         pool.allocate::<Self::LocationConf>(Default::default()) as *mut c_void
     }
 
+    /// Merges the location configuration with the configuration from the enclosing level using
+    /// [`Merge`].
+    ///
     /// # Safety
     ///
     /// Callers should provide valid non-null `ngx_conf_t` arguments. Implementers must
@@ -161,3 +241,70 @@ pub trait HttpModule {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_field() {
+        let mut x: Option<u32> = None;
+        x.merge_field(&Some(1));
+        assert_eq!(x, Some(1));
+        x.merge_field(&Some(2));
+        assert_eq!(x, Some(1));
+
+        let mut n = NGX_CONF_UNSET as isize;
+        n.merge_field(&10);
+        assert_eq!(n, 10);
+
+        let mut u = usize::MAX;
+        assert!(u.is_unset());
+        u.merge_field(&0);
+        assert_eq!(u, 0);
+    }
+
+    #[test]
+    #[cfg(feature = "derive")]
+    fn test_derive_merge() {
+        #[derive(Clone, Debug, Default, PartialEq, Merge)]
+        struct Inner {
+            value: Option<u32>,
+        }
+
+        #[derive(Debug, PartialEq, Merge)]
+        struct Conf {
+            enable: Option<bool>,
+            #[merge(default = 100)]
+            max: isize,
+            #[merge(inherit)]
+            inner: Inner,
+            #[merge(skip)]
+            local: Option<u32>,
+        }
+
+        let prev = Conf {
+            enable: Some(true),
+            max: -1,
+            inner: Inner { value: Some(1) },
+            local: Some(1),
+        };
+        let mut conf = Conf {
+            enable: None,
+            max: -1,
+            inner: Inner::default(),
+            local: None,
+        };
+
+        conf.merge(&prev).unwrap();
+        assert_eq!(
+            conf,
+            Conf {
+                enable: Some(true),
+                max: 100,
+                inner: Inner { value: Some(1) },
+                local: None,
+            }
+        );
+    }
+}
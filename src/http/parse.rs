@@ -0,0 +1,113 @@
+use core::slice;
+
+use crate::core::{BufRef, Buffer, NgxStr};
+use crate::ffi::*;
+use crate::http::Request;
+
+/// Incremental HTTP header parser, based on `ngx_http_parse_header_line`.
+///
+/// The parser follows the exact rules nginx applies to the client request and upstream response
+/// headers. The parser state is stored in the associated request, thus only one parser per
+/// request can be active at a time.
+pub struct HeaderParser<'r> {
+    request: &'r mut Request,
+    allow_underscores: bool,
+}
+
+/// Result of a [`HeaderParser::parse`] call.
+#[derive(Debug)]
+pub enum ParsedHeader<'a> {
+    /// A complete header line.
+    Header {
+        /// Header name.
+        name: &'a NgxStr,
+        /// Header value, with the leading and trailing whitespace removed.
+        value: &'a NgxStr,
+        /// The name contains characters that are not allowed in a header name.
+        ///
+        /// nginx ignores such headers when `ignore_invalid_headers` is enabled.
+        invalid: bool,
+    },
+    /// The empty line terminating the header block.
+    Done,
+    /// The buffer ended in the middle of a header line and more data is needed.
+    ///
+    /// The unparsed part of the buffer must be preserved until the next call.
+    Again,
+    /// An invalid header line. The buffer position is advanced past the line.
+    Invalid,
+}
+
+impl<'r> HeaderParser<'r> {
+    /// Creates a new parser storing its state in the `request`.
+    ///
+    /// The parser continues from the state already stored in the request, e.g. after a header
+    /// line split between the buffers. nginx resets the state once a header line or the header
+    /// block is complete; use [`HeaderParser::reset`] to discard a partially parsed line.
+    ///
+    /// `allow_underscores` permits underscores in header names, similar to the
+    /// `underscores_in_headers` directive.
+    pub fn new(request: &'r mut Request, allow_underscores: bool) -> Self {
+        Self {
+            request,
+            allow_underscores,
+        }
+    }
+
+    /// Resets the parser state, discarding a partially parsed header line.
+    pub fn reset(&mut self) {
+        let r: &mut ngx_http_request_t = self.request.as_mut();
+        r.state = 0;
+    }
+
+    /// Parses the next header line in the buffer, advancing the buffer position.
+    ///
+    /// The returned name and value reference the buffer memory.
+    pub fn parse<'b>(&mut self, buf: &'b mut BufRef) -> ParsedHeader<'b> {
+        let r: &mut ngx_http_request_t = self.request.as_mut();
+        let rc = unsafe {
+            ngx_http_parse_header_line(
+                r,
+                buf.as_ngx_buf_mut(),
+                self.allow_underscores as ngx_uint_t,
+            )
+        };
+
+        match rc {
+            x if x == NGX_OK as ngx_int_t => {
+                // SAFETY: on success, the header name and value spans point into the buffer
+                let (name, value) = unsafe {
+                    (
+                        span(r.header_name_start, r.header_name_end),
+                        span(r.header_start, r.header_end),
+                    )
+                };
+
+                ParsedHeader::Header {
+                    name,
+                    value,
+                    invalid: r.invalid_header() != 0,
+                }
+            }
+            x if x == NGX_AGAIN as ngx_int_t => ParsedHeader::Again,
+            x if x == NGX_HTTP_PARSE_HEADER_DONE as ngx_int_t => ParsedHeader::Done,
+            _ => ParsedHeader::Invalid,
+        }
+    }
+}
+
+/// Creates an [`NgxStr`] from a pair of pointers into a buffer.
+///
+/// # Safety
+///
+/// `start` and `end` must belong to the same allocation, which outlives `'a`.
+unsafe fn span<'a>(start: *mut u_char, end: *mut u_char) -> &'a NgxStr {
+    if start.is_null() || end <= start {
+        return NgxStr::from_bytes(&[]);
+    }
+
+    NgxStr::from_bytes(slice::from_raw_parts(
+        start,
+        end.offset_from(start) as usize,
+    ))
+}
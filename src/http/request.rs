@@ -1,13 +1,20 @@
+#[cfg(debug_assertions)]
+use core::any::TypeId;
 use core::error;
 use core::ffi::c_void;
 use core::fmt;
-use core::ptr::NonNull;
+use core::mem;
+use core::ptr::{self, NonNull};
 use core::slice;
 use core::str::FromStr;
+use core::time::Duration;
 
+use crate::allocator::AllocError;
 use crate::core::*;
 use crate::ffi::*;
 use crate::http::status::*;
+use crate::http::{HttpModuleLocationConf, NgxHttpCoreModule};
+use crate::log::{IntoLogPtr, LogRef};
 
 /// Define a static request handler.
 ///
@@ -89,6 +96,18 @@ macro_rules! http_variable_get {
 /// requests.
 ///
 /// See <https://nginx.org/en/docs/dev/development_guide.html#http_request>
+///
+/// # Thread safety
+///
+/// A request is owned by the event loop of a worker process and its memory is managed by the
+/// request pool, so `Request` is neither `Send` nor `Sync`. Code running on other threads should
+/// pass results back to the event loop, e.g. with `ngx::async_::spawn_blocking`, instead of
+/// accessing the request directly.
+///
+/// ```compile_fail
+/// fn assert_send<T: Send>() {}
+/// assert_send::<&ngx::http::Request>();
+/// ```
 #[repr(transparent)]
 pub struct Request(ngx_http_request_t);
 
@@ -116,6 +135,20 @@ impl AsMut<ngx_http_request_t> for Request {
     }
 }
 
+impl IntoLogPtr for &Request {
+    #[inline(always)]
+    fn into_log_ptr(self) -> *mut ngx_log_t {
+        self.log_ptr()
+    }
+}
+
+impl IntoLogPtr for &mut Request {
+    #[inline(always)]
+    fn into_log_ptr(self) -> *mut ngx_log_t {
+        self.log_ptr()
+    }
+}
+
 impl Request {
     /// Create a [`Request`] from an [`ngx_http_request_t`].
     ///
@@ -134,9 +167,24 @@ impl Request {
     }
 
     /// Request pool.
-    pub fn pool(&self) -> Pool {
+    ///
+    /// The returned reference is bound to the request borrow, and can be used for allocations
+    /// directly, as the [`Pool`] allocation methods take a shared reference. The memory allocated
+    /// from the pool is freed when the request is finalized, and must not be used afterwards, e.g.
+    /// from a future that outlives the request. A cloned handle is detached from the borrow, and
+    /// is subject to the same restrictions as [`Request::pool_ptr`].
+    pub fn pool(&self) -> &Pool {
         // SAFETY: This request is allocated from `pool`, thus must be a valid pool.
-        unsafe { Pool::from_ngx_pool(self.0.pool) }
+        // `Pool` is a transparent wrapper over a non-null `ngx_pool_t` pointer.
+        unsafe { &*ptr::addr_of!(self.0.pool).cast::<Pool>() }
+    }
+
+    /// Pointer to the request pool.
+    ///
+    /// The pointer is valid until the request is finalized, and can be used where a detached
+    /// reference is needed, e.g. in the cleanup handlers or the C callbacks.
+    pub fn pool_ptr(&self) -> *mut ngx_pool_t {
+        self.0.pool
     }
 
     /// Returns the result as an `Option` if it exists, otherwise `None`.
@@ -153,18 +201,32 @@ impl Request {
         Some(self.0.upstream)
     }
 
+    /// Client [`Connection`] object.
+    pub fn connection(&mut self) -> &mut Connection {
+        // SAFETY: a request always has a valid client connection
+        unsafe { Connection::from_ptr_mut(self.0.connection) }
+    }
+
     /// Pointer to a [`ngx_connection_t`] client connection object.
     ///
     /// [`ngx_connection_t`]: https://nginx.org/en/docs/dev/development_guide.html#connection
-    pub fn connection(&self) -> *mut ngx_connection_t {
+    pub fn connection_ptr(&self) -> *mut ngx_connection_t {
         self.0.connection
     }
 
+    /// Returns the request log.
+    ///
+    /// See <https://nginx.org/en/docs/dev/development_guide.html#logging>
+    pub fn log(&self) -> &LogRef {
+        // SAFETY: an active request always has a connection with a valid log
+        unsafe { LogRef::from_ptr(self.log_ptr()) }
+    }
+
     /// Pointer to a [`ngx_log_t`].
     ///
     /// [`ngx_log_t`]: https://nginx.org/en/docs/dev/development_guide.html#logging
-    pub fn log(&self) -> *mut ngx_log_t {
-        unsafe { (*self.connection()).log }
+    pub fn log_ptr(&self) -> *mut ngx_log_t {
+        unsafe { (*self.connection_ptr()).log }
     }
 
     /// Get Module context pointer
@@ -189,6 +251,59 @@ impl Request {
         };
     }
 
+    /// Allocates the value in the request pool and sets it as the module's context.
+    ///
+    /// The value is dropped when the request pool is destroyed. The context must be accessed with
+    /// [`module_ctx`](Self::module_ctx) or [`module_ctx_mut`](Self::module_ctx_mut) with the same
+    /// type `T`; the debug builds store the type alongside the value and check it on access.
+    ///
+    /// Returns `None` if the allocation fails.
+    pub fn create_module_ctx<T: 'static>(
+        &mut self,
+        module: &ngx_module_t,
+        value: T,
+    ) -> Option<&mut T> {
+        let ctx = self.pool().allocate(TypedModuleCtx {
+            value,
+            #[cfg(debug_assertions)]
+            type_id: TypeId::of::<T>(),
+        });
+        if ctx.is_null() {
+            return None;
+        }
+
+        self.set_module_ctx(ctx.cast(), module);
+        // SAFETY: the context is allocated from the request pool and lives as long as the request
+        Some(unsafe { &mut (*ctx).value })
+    }
+
+    /// Returns the module's context created with [`create_module_ctx`](Self::create_module_ctx).
+    ///
+    /// # Panics
+    ///
+    /// Panics in debug builds if the context was created with a different type.
+    pub fn module_ctx<T: 'static>(&self, module: &ngx_module_t) -> Option<&T> {
+        let ctx = self.get_module_ctx_ptr(module).cast::<TypedModuleCtx<T>>();
+        // SAFETY: ctx is either NULL or allocated by `create_module_ctx`
+        let ctx = unsafe { ctx.as_ref()? };
+        ctx.check_type();
+        Some(&ctx.value)
+    }
+
+    /// Returns a mutable reference to the module's context created with
+    /// [`create_module_ctx`](Self::create_module_ctx).
+    ///
+    /// # Panics
+    ///
+    /// Panics in debug builds if the context was created with a different type.
+    pub fn module_ctx_mut<T: 'static>(&mut self, module: &ngx_module_t) -> Option<&mut T> {
+        let ctx = self.get_module_ctx_ptr(module).cast::<TypedModuleCtx<T>>();
+        // SAFETY: ctx is either NULL or allocated by `create_module_ctx`
+        let ctx = unsafe { ctx.as_mut()? };
+        ctx.check_type();
+        Some(&mut ctx.value)
+    }
+
     /// Get the value of a [complex value].
     ///
     /// [complex value]: https://nginx.org/en/docs/dev/development_guide.html#http_complex_values
@@ -206,6 +321,67 @@ impl Request {
         }
     }
 
+    /// Maps the request URI to a file system path, according to the `root` or `alias` directive
+    /// of the location.
+    ///
+    /// Returns the null-terminated path allocated in the request pool, without the terminating
+    /// null, and the length of the root prefix of the path. The allocation has `reserved` more
+    /// bytes after the path, e.g. to append an index file name.
+    ///
+    /// This is how the static module resolves the file to serve.
+    pub fn map_uri_to_path(&mut self, reserved: usize) -> Option<(&NgxStr, usize)> {
+        let mut path = ngx_str_t::default();
+        let mut root: usize = 0;
+        // SAFETY: `ngx_http_map_uri_to_path` allocates from the request pool and evaluates the
+        // `root` variables, and returns a pointer to the terminating null on success.
+        unsafe {
+            let last = ngx_http_map_uri_to_path(&mut self.0, &mut path, &mut root, reserved);
+            if last.is_null() {
+                return None;
+            }
+            path.len = last.offset_from(path.data) as usize;
+            Some((NgxStr::from_ngx_str(path), root))
+        }
+    }
+
+    /// Adds a handler called when the request is freed, e.g. to release an external resource.
+    ///
+    /// Unlike the pool cleanup handlers, the handler runs as soon as the main request completes,
+    /// before the connection is closed or kept alive for the next request. The handlers added
+    /// for a subrequest are called with the ones of the main request, in the reverse order.
+    ///
+    /// The closure is stored in the request pool, and must not be aligned stricter than the
+    /// pool allocations.
+    pub fn add_cleanup<F>(&mut self, f: F) -> Result<(), AllocError>
+    where
+        F: FnOnce() + 'static,
+    {
+        const {
+            assert!(
+                mem::align_of::<F>() <= NGX_ALIGNMENT,
+                "the cleanup handler is over-aligned"
+            )
+        };
+
+        // SAFETY: the cleanup is allocated from the request pool with the space for `F`
+        unsafe {
+            let cln = ngx_http_cleanup_add(&mut self.0, mem::size_of::<F>());
+            if cln.is_null() {
+                return Err(AllocError);
+            }
+
+            if mem::size_of::<F>() > 0 {
+                ptr::write((*cln).data.cast::<F>(), f);
+            } else {
+                (*cln).data = NonNull::<F>::dangling().as_ptr().cast();
+                mem::forget(f);
+            }
+            (*cln).handler = Some(request_cleanup_handler::<F>);
+        }
+
+        Ok(())
+    }
+
     /// Discard (read and ignore) the [request body].
     ///
     /// [request body]: https://nginx.org/en/docs/dev/development_guide.html#http_request_body
@@ -213,15 +389,136 @@ impl Request {
         unsafe { Status(ngx_http_discard_request_body(&mut self.0)) }
     }
 
+    /// Returns the buffers of the request body read with `ngx_http_read_client_request_body`.
+    ///
+    /// The body may be partially or completely stored in a temporary file, if it does not fit
+    /// into `client_body_buffer_size`. Such buffers have no memory contents; check
+    /// [`Chain::in_file`] and read the data with [`BufRef::read_file`].
+    pub fn request_body(&self) -> Option<&Chain> {
+        let body = unsafe { self.0.request_body.as_ref()? };
+        if body.bufs.is_null() {
+            return None;
+        }
+        Some(unsafe { Chain::from_ptr(body.bufs) })
+    }
+
+    /// Returns `true` if the request body was written to a temporary file.
+    pub fn request_body_in_file(&self) -> bool {
+        unsafe { self.0.request_body.as_ref() }.is_some_and(|body| !body.temp_file.is_null())
+    }
+
+    /// Returns the length of the request body from the `Content-Length` header.
+    ///
+    /// Returns `None` if the length is unknown, e.g. for a chunked request body.
+    pub fn content_length(&self) -> Option<u64> {
+        if self.is_chunked() {
+            return None;
+        }
+        u64::try_from(self.0.headers_in.content_length_n).ok()
+    }
+
+    /// Returns `true` if the request body uses chunked transfer encoding.
+    ///
+    /// nginx also sets this for HTTP/2 and HTTP/3 request bodies without a `Content-Length`.
+    pub fn is_chunked(&self) -> bool {
+        self.0.headers_in.chunked() != 0
+    }
+
+    /// Returns `true` if the request has a body to be either read with
+    /// `ngx_http_read_client_request_body` or discarded with [`Self::discard_request_body`].
+    pub fn has_body(&self) -> bool {
+        self.is_chunked() || self.0.headers_in.content_length_n > 0
+    }
+
     /// Client HTTP [User-Agent].
     ///
     /// [User-Agent]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/User-Agent
     pub fn user_agent(&self) -> Option<&NgxStr> {
-        if !self.0.headers_in.user_agent.is_null() {
-            unsafe { Some(NgxStr::from_ngx_str((*self.0.headers_in.user_agent).value)) }
-        } else {
-            None
-        }
+        header_in_value(self.0.headers_in.user_agent)
+    }
+
+    /// Client HTTP [Referer].
+    ///
+    /// [Referer]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Referer
+    pub fn referer(&self) -> Option<&NgxStr> {
+        header_in_value(self.0.headers_in.referer)
+    }
+
+    /// Request body [Content-Type].
+    ///
+    /// [Content-Type]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Content-Type
+    pub fn content_type(&self) -> Option<&NgxStr> {
+        header_in_value(self.0.headers_in.content_type)
+    }
+
+    /// Client [Authorization] credentials.
+    ///
+    /// [Authorization]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Authorization
+    pub fn authorization(&self) -> Option<&NgxStr> {
+        header_in_value(self.0.headers_in.authorization)
+    }
+
+    /// Conditional request [If-Modified-Since] header.
+    ///
+    /// [If-Modified-Since]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/If-Modified-Since
+    pub fn if_modified_since(&self) -> Option<&NgxStr> {
+        header_in_value(self.0.headers_in.if_modified_since)
+    }
+
+    /// Conditional request [If-Unmodified-Since] header.
+    ///
+    /// [If-Unmodified-Since]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/If-Unmodified-Since
+    pub fn if_unmodified_since(&self) -> Option<&NgxStr> {
+        header_in_value(self.0.headers_in.if_unmodified_since)
+    }
+
+    /// Conditional request [If-Match] header.
+    ///
+    /// [If-Match]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/If-Match
+    pub fn if_match(&self) -> Option<&NgxStr> {
+        header_in_value(self.0.headers_in.if_match)
+    }
+
+    /// Conditional request [If-None-Match] header.
+    ///
+    /// [If-None-Match]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/If-None-Match
+    pub fn if_none_match(&self) -> Option<&NgxStr> {
+        header_in_value(self.0.headers_in.if_none_match)
+    }
+
+    /// Client [Range] request header.
+    ///
+    /// [Range]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Range
+    pub fn range(&self) -> Option<&NgxStr> {
+        header_in_value(self.0.headers_in.range)
+    }
+
+    /// Conditional range request [If-Range] header.
+    ///
+    /// [If-Range]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/If-Range
+    pub fn if_range(&self) -> Option<&NgxStr> {
+        header_in_value(self.0.headers_in.if_range)
+    }
+
+    /// Client [Connection] request header.
+    ///
+    /// [Connection]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Connection
+    pub fn connection_header(&self) -> Option<&NgxStr> {
+        header_in_value(self.0.headers_in.connection)
+    }
+
+    /// Client [Expect] request header.
+    ///
+    /// [Expect]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Expect
+    pub fn expect(&self) -> Option<&NgxStr> {
+        header_in_value(self.0.headers_in.expect)
+    }
+
+    /// Client [Upgrade] request header.
+    ///
+    /// [Upgrade]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Upgrade
+    pub fn upgrade(&self) -> Option<&NgxStr> {
+        header_in_value(self.0.headers_in.upgrade)
     }
 
     /// Set HTTP status of response.
@@ -229,6 +526,17 @@ impl Request {
         self.0.headers_out.status = status.into();
     }
 
+    /// Sets the HTTP status of response along with the full status line, e.g. `200 Custom OK`.
+    ///
+    /// The line is sent instead of the standard one generated by nginx. It is copied to the
+    /// request pool; returns `None` if the allocation fails.
+    pub fn set_status_line(&mut self, status: HTTPStatus, line: &str) -> Option<()> {
+        let line = unsafe { ngx_str_t::from_bytes(self.0.pool, line.as_bytes())? };
+        self.0.headers_out.status = status.into();
+        self.0.headers_out.status_line = line;
+        Some(())
+    }
+
     /// Add header to the `headers_in` object.
     ///
     /// See <https://nginx.org/en/docs/dev/development_guide.html#http_request>
@@ -247,6 +555,52 @@ impl Request {
         unsafe { add_to_ngx_table(table, self.0.pool, key, value) }
     }
 
+    /// Adds a header to the `headers_out` object and returns the new element.
+    ///
+    /// The element can be assigned to one of the `headers_out` fields, e.g. `server`, to replace
+    /// the header otherwise generated by nginx.
+    pub fn push_header_out(&mut self, key: &str, value: &str) -> Option<&mut NgxTableElementRef> {
+        let table: *mut ngx_table_elt_t =
+            unsafe { ngx_list_push(&mut self.0.headers_out.headers) as _ };
+        unsafe { add_to_ngx_table(table, self.0.pool, key, value)? };
+
+        #[cfg(nginx1_23_0)]
+        unsafe {
+            (*table).next = core::ptr::null_mut()
+        };
+
+        Some(unsafe { NgxTableElementRef::from_ptr_mut(table) })
+    }
+
+    /// Removes all the headers with the specified name from the `headers_out` object.
+    ///
+    /// The headers are marked as deleted by setting the hash to zero, as nginx does. The headers
+    /// generated by the nginx header filter, such as `Server` or `Date`, are not affected unless
+    /// the corresponding `headers_out` field points to a removed element.
+    ///
+    /// Returns `true` if any header was removed.
+    pub fn remove_header_out(&mut self, key: &str) -> bool {
+        let mut removed = false;
+        let mut part: *mut ngx_list_part_t = &mut self.0.headers_out.headers.part;
+
+        // SAFETY: the list contains `ngx_table_elt_t` elements and is not modified concurrently
+        while let Some(p) = unsafe { part.as_mut() } {
+            let elts =
+                unsafe { slice::from_raw_parts_mut(p.elts.cast::<ngx_table_elt_t>(), p.nelts) };
+
+            for h in elts {
+                if h.hash != 0 && h.key.as_bytes().eq_ignore_ascii_case(key.as_bytes()) {
+                    h.hash = 0;
+                    removed = true;
+                }
+            }
+
+            part = p.next;
+        }
+
+        removed
+    }
+
     /// Set response body [Content-Length].
     ///
     /// [Content-Length]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Content-Length
@@ -254,11 +608,162 @@ impl Request {
         self.0.headers_out.content_length_n = n as off_t;
     }
 
+    /// Sets the response [Content-Encoding], e.g. for a body already compressed by the module.
+    ///
+    /// The gzip filter does not compress the responses with a `Content-Encoding` set.
+    ///
+    /// [Content-Encoding]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Content-Encoding
+    pub fn set_content_encoding(&mut self, encoding: &str) -> Option<()> {
+        let header: *mut ngx_table_elt_t =
+            self.push_header_out("Content-Encoding", encoding)?.as_mut();
+        self.0.headers_out.content_encoding = header;
+        Some(())
+    }
+
+    /// Adds the [WWW-Authenticate] header with the authentication scheme and the realm, e.g.
+    /// `Basic realm="example"`, to a `401 Unauthorized` response.
+    ///
+    /// Quotes and backslashes in the realm are escaped. The header is copied to the request pool;
+    /// returns `None` if the allocation fails.
+    ///
+    /// [WWW-Authenticate]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/WWW-Authenticate
+    pub fn set_www_authenticate(&mut self, scheme: &str, realm: &str) -> Option<()> {
+        const PREFIX: &[u8] = b" realm=\"";
+
+        let escaped = realm.bytes().filter(|c| matches!(c, b'"' | b'\\')).count();
+        let len = scheme.len() + PREFIX.len() + realm.len() + escaped + 1;
+
+        let data = self.pool().alloc_unaligned(len).cast::<u8>();
+        if data.is_null() {
+            return None;
+        }
+        // SAFETY: the buffer is allocated with the length calculated above
+        let buf = unsafe { slice::from_raw_parts_mut(data, len) };
+
+        buf[..scheme.len()].copy_from_slice(scheme.as_bytes());
+        let mut i = scheme.len() + PREFIX.len();
+        buf[scheme.len()..i].copy_from_slice(PREFIX);
+        for c in realm.bytes() {
+            if matches!(c, b'"' | b'\\') {
+                buf[i] = b'\\';
+                i += 1;
+            }
+            buf[i] = c;
+            i += 1;
+        }
+        buf[i] = b'"';
+
+        let header = self.push_header_out("WWW-Authenticate", "")?;
+        header.as_mut().value = ngx_str_t { data, len };
+
+        let header: *mut ngx_table_elt_t = header.as_mut();
+        self.0.headers_out.www_authenticate = header;
+        Some(())
+    }
+
+    /// Returns the MIME type for the file name extension, according to the `types` directive of
+    /// the location.
+    ///
+    /// The extension is matched case-insensitively, as nginx does. Returns `None` if there is no
+    /// such type or if the lowercase copy of the extension cannot be allocated.
+    pub fn content_type_for_extension(&self, ext: &NgxStr) -> Option<&NgxStr> {
+        let clcf = NgxHttpCoreModule::location_conf(self)?;
+        let types = ptr::from_ref(&clcf.types_hash).cast_mut();
+
+        let src = ext.as_bytes().as_ptr().cast_mut();
+        let len = ext.len();
+
+        // SAFETY: `ngx_hash_key` and `ngx_hash_strlow` only read from `src`; the lowercase copy
+        // is written to a buffer of the same size allocated from the request pool.
+        let (key, hash) = unsafe {
+            if ext.as_bytes().iter().any(u8::is_ascii_uppercase) {
+                let key = self.pool().alloc_unaligned(len).cast::<u8>();
+                if key.is_null() {
+                    return None;
+                }
+                (key, ngx_hash_strlow(key, src, len))
+            } else {
+                (src, ngx_hash_key(src, len))
+            }
+        };
+
+        // SAFETY: `ngx_hash_find` does not modify the key. The hash stores `ngx_str_t` values.
+        unsafe {
+            let value = ngx_hash_find(types, hash, key, len).cast::<ngx_str_t>();
+            Some(NgxStr::from_ngx_str(*value.as_ref()?))
+        }
+    }
+
+    /// Sets the response `Content-Type` for the file path, based on its extension and the
+    /// `types` and `default_type` directives of the location.
+    ///
+    /// This is similar to the `ngx_http_set_content_type` function, which uses the extension of
+    /// the request URI.
+    pub fn set_content_type_by_path(&mut self, path: &NgxStr) {
+        let bytes = path.as_bytes();
+        let name = bytes.rsplit(|&c| c == b'/').next().unwrap_or(bytes);
+
+        let content_type = name
+            .iter()
+            .rposition(|&c| c == b'.')
+            .and_then(|dot| self.content_type_for_extension(NgxStr::from_bytes(&name[dot + 1..])))
+            .map(|ct| ngx_str_t {
+                data: ct.as_bytes().as_ptr().cast_mut(),
+                len: ct.len(),
+            })
+            .or_else(|| Some(NgxHttpCoreModule::location_conf(self)?.default_type));
+
+        if let Some(content_type) = content_type {
+            self.0.headers_out.content_type = content_type;
+            self.0.headers_out.content_type_len = content_type.len;
+            self.0.headers_out.content_type_lowcase = ptr::null_mut();
+        }
+    }
+
+    /// Tests the response `Content-Type` against a hash of MIME types, e.g. the one built from a
+    /// `*_types` directive with `ngx_http_types_slot`, and returns the stored value on match.
+    ///
+    /// Wraps the `ngx_http_test_content_type` function.
+    pub fn test_content_type(&mut self, types_hash: &ngx_hash_t) -> Option<NonNull<c_void>> {
+        let types = ptr::from_ref(types_hash).cast_mut();
+        // SAFETY: the function only reads the hash and may allocate the lowercase content type
+        // from the request pool.
+        NonNull::new(unsafe { ngx_http_test_content_type(&mut self.0, types) })
+    }
+
+    /// Checks whether the client accepts a gzip-compressed response, according to the
+    /// `Accept-Encoding` header and the `gzip_http_version`, `gzip_proxied` and `gzip_disable`
+    /// directives.
+    ///
+    /// The result is cached in the request. Available if nginx was built with any of the gzip
+    /// modules.
+    #[cfg(ngx_feature = "http_gzip")]
+    pub fn gzip_ok(&mut self) -> bool {
+        unsafe { ngx_http_gzip_ok(&mut self.0) == NGX_OK as ngx_int_t }
+    }
+
     /// Send the output header.
     ///
     /// Do not call this function until all output headers are set.
-    pub fn send_header(&mut self) -> Status {
-        unsafe { Status(ngx_http_send_header(&mut self.0)) }
+    ///
+    /// On success, the returned [`HeaderSent`] tells whether the response body should follow;
+    /// it does not for the requests that only need the header, e.g. `HEAD`. Otherwise, the
+    /// request should be finalized with the returned status, which is either `NGX_ERROR` or an
+    /// HTTP status code set by a header filter.
+    ///
+    /// ```ignore
+    /// match request.send_header() {
+    ///     Ok(sent) if sent.body_expected() => request.output_filter(&mut out),
+    ///     Ok(_) => Status::NGX_OK,
+    ///     Err(rc) => rc,
+    /// }
+    /// ```
+    pub fn send_header(&mut self) -> Result<HeaderSent, Status> {
+        let rc = unsafe { Status(ngx_http_send_header(&mut self.0)) };
+        if rc == Status::NGX_ERROR || rc.0 > NGX_OK as ngx_int_t {
+            return Err(rc);
+        }
+        Ok(HeaderSent(!self.header_only()))
     }
 
     /// Flag indicating that the output does not require a body.
@@ -268,11 +773,105 @@ impl Request {
         self.0.header_only() != 0
     }
 
+    /// Sets the flag indicating that the output does not require a body.
+    ///
+    /// The flag must be set before [`Request::send_header`], as the header filters use it to
+    /// decide on the response framing and the body output is skipped afterwards.
+    pub fn set_header_only(&mut self, header_only: bool) {
+        self.0.set_header_only(if header_only { 1 } else { 0 });
+    }
+
+    /// Returns `true` if the client connection will be kept alive after the response.
+    pub fn keepalive(&self) -> bool {
+        self.0.keepalive() != 0
+    }
+
+    /// Controls whether the client connection is kept alive after the response.
+    ///
+    /// The value is initialized from the request headers and the `keepalive_timeout` and
+    /// `keepalive_requests` directives. It must be set before [`Request::send_header`], as the
+    /// header filter emits the `Connection` header based on it.
+    ///
+    /// Setting the flag to `true` does not override the conditions under which nginx cannot keep
+    /// the connection, such as an unread request body.
+    pub fn set_keepalive(&mut self, keepalive: bool) {
+        self.0.set_keepalive(if keepalive { 1 } else { 0 });
+    }
+
+    /// Returns `true` if the request was redirected internally and can access `internal`
+    /// locations.
+    pub fn is_internal(&self) -> bool {
+        self.0.internal() != 0
+    }
+
+    /// Returns the time the request started, as the duration since the Unix epoch.
+    ///
+    /// The time is taken from the cached nginx time when the first bytes of the request were
+    /// read, with millisecond resolution.
+    pub fn start_time(&self) -> Duration {
+        let sec = u64::try_from(self.0.start_sec).unwrap_or(0);
+        Duration::from_secs(sec) + Duration::from_millis(self.0.start_msec as u64)
+    }
+
+    /// Returns the time elapsed since the request started, as reported by the `$request_time`
+    /// variable.
+    ///
+    /// The current time is the cached nginx time, updated at the start of each event loop
+    /// iteration.
+    pub fn elapsed(&self) -> Duration {
+        let tp = ngx_timeofday();
+        let ms =
+            (tp.sec - self.0.start_sec) as i64 * 1000 + (tp.msec as i64 - self.0.start_msec as i64);
+        Duration::from_millis(ms.max(0) as u64)
+    }
+
     /// request method
     pub fn method(&self) -> Method {
         Method::from_ngx(self.0.method)
     }
 
+    /// HTTP protocol version of the request.
+    pub fn version(&self) -> HttpVersion {
+        HttpVersion::from(self.0.http_version)
+    }
+
+    /// Request scheme, as specified in the absolute request URI or the `:scheme` pseudo-header.
+    ///
+    /// The value is empty for the HTTP/1.x requests with a relative URI.
+    pub fn scheme(&self) -> &NgxStr {
+        // The `schema` string replaced the `schema_start`/`schema_end` pair in nginx 1.21.1.
+        #[cfg(nginx1_21_1)]
+        let schema = self.0.schema;
+        #[cfg(not(nginx1_21_1))]
+        let schema = if self.0.schema_start.is_null() {
+            ngx_str_t::empty()
+        } else {
+            ngx_str_t {
+                data: self.0.schema_start,
+                len: unsafe { self.0.schema_end.offset_from(self.0.schema_start) } as usize,
+            }
+        };
+
+        unsafe { NgxStr::from_ngx_str(schema) }
+    }
+
+    /// Request host, from the absolute request URI or the [Host] header.
+    ///
+    /// The value is validated and converted to lowercase by nginx.
+    ///
+    /// [Host]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Host
+    pub fn host(&self) -> Option<&NgxStr> {
+        if self.0.headers_in.server.len != 0 {
+            return Some(unsafe { NgxStr::from_ngx_str(self.0.headers_in.server) });
+        }
+
+        if !self.0.headers_in.host.is_null() {
+            unsafe { Some(NgxStr::from_ngx_str((*self.0.headers_in.host).value)) }
+        } else {
+            None
+        }
+    }
+
     /// path part of request only
     pub fn path(&self) -> &NgxStr {
         unsafe { NgxStr::from_ngx_str(self.0.uri) }
@@ -283,6 +882,102 @@ impl Request {
         unsafe { NgxStr::from_ngx_str(self.0.unparsed_uri) }
     }
 
+    /// Query string of the request, without the leading `?`.
+    pub fn args(&self) -> &NgxStr {
+        unsafe { NgxStr::from_ngx_str(self.0.args) }
+    }
+
+    /// Returns the raw value of the first query string argument named `name`.
+    ///
+    /// The name is compared case-insensitively. The value references the request query string and
+    /// is not unescaped; see [`Request::arg_unescaped`] for a decoded copy.
+    pub fn arg(&self, name: &str) -> Option<&NgxStr> {
+        let r = (self as *const Request as *mut Request).cast();
+        // SAFETY: `ngx_http_arg` does not mutate `r` or `name` and, on success, stores a
+        // subslice of the request `args` in `value`.
+        unsafe {
+            let mut value = ngx_str_t::default();
+            if ngx_http_arg(r, name.as_ptr().cast_mut(), name.len(), &mut value)
+                != NGX_OK as ngx_int_t
+            {
+                return None;
+            }
+            Some(NgxStr::from_ngx_str(value))
+        }
+    }
+
+    /// Returns the value of the first query string argument named `name` with percent-encoded
+    /// sequences decoded.
+    ///
+    /// The decoded value is copied to the request pool, unless it contains no escape sequences.
+    /// Returns `None` if the argument is missing or the allocation fails.
+    pub fn arg_unescaped(&self, name: &str) -> Option<&NgxStr> {
+        let value = self.arg(name)?;
+        if !value.as_bytes().contains(&b'%') {
+            return Some(value);
+        }
+
+        let len = value.as_bytes().len();
+        let data = self.pool().alloc_unaligned(len).cast::<u_char>();
+        if data.is_null() {
+            return None;
+        }
+
+        let mut src = value.as_bytes().as_ptr().cast_mut();
+        let mut dst = data;
+        // SAFETY: `data` has `len` bytes allocated and unescaping never produces more bytes than
+        // it consumes. The pool memory remains valid for the lifetime of the request.
+        unsafe {
+            ngx_unescape_uri(&mut dst, &mut src, len, 0);
+            let len = dst.offset_from(data) as usize;
+            Some(NgxStr::from_bytes(slice::from_raw_parts(data, len)))
+        }
+    }
+
+    /// Returns an iterator over the query string arguments.
+    ///
+    /// Each item is a pair of the argument name and value, borrowed from the request and not
+    /// unescaped. See [`Request::arg`] to look up a single argument.
+    pub fn args_iter(&self) -> NgxArgsIterator<'_> {
+        NgxArgsIterator::new(self.args())
+    }
+
+    /// Returns the capture offsets of the last regular expression matched for the request, e.g.
+    /// by a regex `location` or `server_name`.
+    ///
+    /// The offsets are stored in pairs of the start and the end of each capture, starting with
+    /// the whole match, and are relative to the matched string. Groups that did not participate in
+    /// the match have negative offsets.
+    #[cfg(ngx_feature = "pcre")]
+    pub fn captures(&self) -> Option<&[core::ffi::c_int]> {
+        if self.0.captures.is_null() || self.0.ncaptures == 0 {
+            return None;
+        }
+        // SAFETY: `captures` is allocated from the request pool with at least `ncaptures` elements
+        Some(unsafe { slice::from_raw_parts(self.0.captures, self.0.ncaptures as usize) })
+    }
+
+    /// Returns the `n`-th capture of the last regular expression matched for the request.
+    ///
+    /// The capture `0` is the whole match, and the numbered captures start with `1`, as `$1` in
+    /// the configuration. Returns `None` if the capture does not exist or did not participate in
+    /// the match.
+    #[cfg(ngx_feature = "pcre")]
+    pub fn capture(&self, n: usize) -> Option<&NgxStr> {
+        let captures = self.captures()?;
+        let start = usize::try_from(*captures.get(2 * n)?).ok()?;
+        let end = usize::try_from(*captures.get(2 * n + 1)?).ok()?;
+
+        if self.0.captures_data.is_null() || end < start {
+            return None;
+        }
+        // SAFETY: the offsets are within the matched string referenced by `captures_data`, which
+        // remains valid for the lifetime of the request
+        Some(NgxStr::from_bytes(unsafe {
+            slice::from_raw_parts(self.0.captures_data.add(start), end - start)
+        }))
+    }
+
     /// Send the [response body].
     ///
     /// This function can be called multiple times.
@@ -293,6 +988,132 @@ impl Request {
         unsafe { Status(ngx_http_output_filter(&mut self.0, body)) }
     }
 
+    /// Send a range of the file as the last part of the [response body].
+    ///
+    /// The data is not copied to memory: nginx sends the file buffer with `sendfile`, or reads it
+    /// with `aio` or thread pools, as configured for the location. The file must remain open
+    /// until the request is finalized.
+    ///
+    /// [response body]: https://nginx.org/en/docs/dev/development_guide.html#http_request_body
+    pub fn send_file(&mut self, file: &NgxFile, offset: u64, len: u64) -> Status {
+        let Some(file_last) = offset.checked_add(len) else {
+            return Status::NGX_ERROR;
+        };
+        let (Ok(file_pos), Ok(file_last)) = (off_t::try_from(offset), off_t::try_from(file_last))
+        else {
+            return Status::NGX_ERROR;
+        };
+
+        let pool = self.pool();
+        let buf = pool.calloc_type::<ngx_buf_t>();
+        let out = pool.calloc_type::<ngx_chain_t>();
+        if buf.is_null() || out.is_null() {
+            return Status::NGX_ERROR;
+        }
+
+        let is_main = self.is_main();
+        // SAFETY: the buffer and the chain link are allocated from the request pool
+        unsafe {
+            (*buf).file = file.as_ptr();
+            (*buf).file_pos = file_pos;
+            (*buf).file_last = file_last;
+            (*buf).set_in_file(if len > 0 { 1 } else { 0 });
+            (*buf).set_last_buf(if is_main { 1 } else { 0 });
+            (*buf).set_last_in_chain(1);
+
+            (*out).buf = buf;
+            (*out).next = core::ptr::null_mut();
+
+            self.output_filter(&mut *out)
+        }
+    }
+
+    /// Finalize the request with the specified status or return code.
+    ///
+    /// See <https://nginx.org/en/docs/dev/development_guide.html#http_request_finalization>
+    pub fn finalize(&mut self, status: Status) {
+        debug_assert!(
+            !self.0.connection.is_null() && !self.0.pool.is_null(),
+            "finalizing a request that was already freed"
+        );
+        unsafe { ngx_http_finalize_request(&mut self.0, status.0) }
+    }
+
+    /// Finalize the request with the specified HTTP status code.
+    pub fn finalize_http(&mut self, status: HTTPStatus) {
+        self.finalize(status.into())
+    }
+
+    /// Finalize the request with `NGX_OK`.
+    ///
+    /// This is a common way to complete a request after the response was sent.
+    pub fn finalize_ok(&mut self) {
+        self.finalize(Status::NGX_OK)
+    }
+
+    /// Send a complete response with the specified status, content type and body.
+    ///
+    /// The request body is discarded, and only the header is sent for the requests that do not
+    /// require a body, e.g. `HEAD`. The returned status can be passed to
+    /// [`Request::finalize`].
+    ///
+    /// This is similar to the `ngx_http_send_response` function.
+    pub fn send_response(&mut self, status: HTTPStatus, content_type: &str, body: &[u8]) -> Status {
+        let rc = self.discard_request_body();
+        if !rc.is_ok() {
+            return rc;
+        }
+
+        let pool = self.0.pool;
+
+        let Some(content_type) = (unsafe { ngx_str_t::from_bytes(pool, content_type.as_bytes()) })
+        else {
+            return Status::NGX_ERROR;
+        };
+
+        self.set_status(status);
+        self.0.headers_out.content_type = content_type;
+        self.0.headers_out.content_type_len = content_type.len;
+        self.0.headers_out.content_type_lowcase = core::ptr::null_mut();
+        self.set_content_length_n(body.len());
+
+        match self.send_header() {
+            Ok(sent) if sent.body_expected() => {}
+            Ok(_) => return Status::NGX_OK,
+            Err(rc) => return rc,
+        }
+
+        let buf = self.pool().calloc_type::<ngx_buf_t>();
+        if buf.is_null() {
+            return Status::NGX_ERROR;
+        }
+
+        if !body.is_empty() {
+            let Some(data) = (unsafe { ngx_str_t::from_bytes(pool, body) }) else {
+                return Status::NGX_ERROR;
+            };
+
+            unsafe {
+                (*buf).start = data.data;
+                (*buf).pos = data.data;
+                (*buf).last = data.data.add(data.len);
+                (*buf).end = (*buf).last;
+                (*buf).set_memory(1);
+            }
+        }
+
+        unsafe {
+            (*buf).set_last_buf(if self.is_main() { 1 } else { 0 });
+            (*buf).set_last_in_chain(1);
+        }
+
+        let mut out = ngx_chain_t {
+            buf,
+            next: core::ptr::null_mut(),
+        };
+        self.output_filter(&mut out)
+    }
+
     /// Perform internal redirect to a location
     pub fn internal_redirect(&self, location: &str) -> Status {
         assert!(!location.is_empty(), "uri location is empty");
@@ -385,6 +1206,241 @@ impl Request {
     pub fn headers_out_iterator(&self) -> NgxListIterator<'_> {
         unsafe { list_iterator(&self.0.headers_out.headers) }
     }
+
+    /// Continues the request processing from the current phase handler.
+    ///
+    /// A phase handler that returned `NGX_AGAIN` or `NGX_DONE` to wait for an asynchronous
+    /// operation calls this to resume the request once the operation completes. The handler is
+    /// invoked again and must be able to tell that the operation has completed, e.g. from the
+    /// module context.
+    pub fn run_phases(&mut self) {
+        unsafe { ngx_http_core_run_phases(&mut self.0) }
+    }
+
+    /// Returns the reference count of the main request.
+    ///
+    /// The main request and its memory remain alive while the count is not zero. See
+    /// [`Request::main_count_guard`].
+    pub fn main_count(&self) -> u32 {
+        // SAFETY: `main` is always a valid pointer, either to this or to the main request
+        unsafe { (*self.0.main).count() }
+    }
+
+    /// Sets the reference count of the main request.
+    ///
+    /// # Safety
+    ///
+    /// Every reference counted must be released exactly once by finalizing the request, and the
+    /// request must not be accessed once the count drops to zero. Prefer
+    /// [`Request::main_count_guard`].
+    pub unsafe fn set_main_count(&mut self, count: u32) {
+        (*self.0.main).set_count(count)
+    }
+
+    /// Increments the reference count of the main request, keeping the request alive while an
+    /// asynchronous operation is pending, e.g. a timer or a task on another thread.
+    ///
+    /// The handler that starts the operation usually returns `NGX_DONE`. The returned guard
+    /// releases the reference when dropped, by finalizing the request with `NGX_DONE`, which may
+    /// free the request if it was completed in the meantime, or with
+    /// [`MainCountGuard::finalize`].
+    pub fn main_count_guard(&mut self) -> MainCountGuard {
+        // SAFETY: `main` is always a valid pointer, either to this or to the main request
+        unsafe {
+            let main = &mut *self.0.main;
+            main.set_count(main.count() + 1);
+        }
+        MainCountGuard(NonNull::from(&mut self.0))
+    }
+}
+
+/// The result of a successful [`Request::send_header`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[must_use]
+pub struct HeaderSent(bool);
+
+impl HeaderSent {
+    /// Returns `true` if the response body should be sent after the header, or `false` if the
+    /// request only needs the header, e.g. for `HEAD` or `304 Not Modified`.
+    pub fn body_expected(&self) -> bool {
+        self.0
+    }
+}
+
+/// A reference to the main request, released when the guard is dropped.
+///
+/// See [`Request::main_count_guard`].
+pub struct MainCountGuard(NonNull<ngx_http_request_t>);
+
+impl MainCountGuard {
+    /// Returns the request the guard was created for.
+    pub fn request(&mut self) -> &mut Request {
+        // SAFETY: the reference count keeps the main request and its subrequests alive
+        unsafe { Request::from_ngx_http_request(self.0.as_ptr()) }
+    }
+
+    /// Releases the reference by finalizing the request with the specified status.
+    ///
+    /// A content handler returning `NGX_DONE` releases the reference it was called with, leaving
+    /// the guard responsible for completing the request, e.g. from a task:
+    ///
+    /// ```ignore
+    /// let mut guard = request.main_count_guard();
+    /// spawn(async move {
+    ///     let rc = handle(guard.request()).await;
+    ///     guard.finalize(rc);
+    /// })
+    /// .detach();
+    /// Status::NGX_DONE
+    /// ```
+    pub fn finalize(self, status: Status) {
+        let mut this = mem::ManuallyDrop::new(self);
+        this.request().finalize(status)
+    }
+}
+
+impl Drop for MainCountGuard {
+    fn drop(&mut self) {
+        // `NGX_DONE` only decrements the count and closes the request once it drops to zero.
+        self.request().finalize(Status::NGX_DONE)
+    }
+}
+
+/// An opt-in guard that completes the request if the handler returns without doing so.
+///
+/// The guard owns a reference to the main request taken with [`Request::main_count_guard`], and
+/// finalizing the request releases it. It is intended for the code completing the request after
+/// the content handler returned `NGX_DONE`, e.g. an async task or a request body post handler.
+///
+/// When dropped without calling [`RequestGuard::finalize`] or [`RequestGuard::disarm`], e.g. on
+/// an early return from an error path, the guard discards the request body unless it was already
+/// read or discarded, and finalizes the request with the default status. This keeps the
+/// connection in a consistent state for the following requests.
+///
+/// ```ignore
+/// let mut r = RequestGuard::new(request.main_count_guard());
+/// spawn(async move {
+///     // the request is finalized with `500 Internal Server Error` on an early return
+///     let body = build_response(&r).await?;
+///     let rc = r.send_response(HTTPStatus::OK, "text/plain", &body);
+///     r.finalize(rc);
+/// })
+/// .detach();
+/// Status::NGX_DONE
+/// ```
+pub struct RequestGuard {
+    guard: Option<MainCountGuard>,
+    status: Status,
+}
+
+impl RequestGuard {
+    /// Creates a guard finalizing the request with `500 Internal Server Error` by default.
+    pub fn new(guard: MainCountGuard) -> Self {
+        Self::with_status(guard, HTTPStatus::INTERNAL_SERVER_ERROR.into())
+    }
+
+    /// Creates a guard finalizing the request with the specified status by default.
+    pub fn with_status(guard: MainCountGuard, status: Status) -> Self {
+        Self {
+            guard: Some(guard),
+            status,
+        }
+    }
+
+    /// Sets the status used to finalize the request on drop.
+    pub fn set_default_status(&mut self, status: Status) {
+        self.status = status;
+    }
+
+    /// Finalizes the request with the specified status and disarms the guard.
+    pub fn finalize(mut self, status: Status) {
+        if let Some(guard) = self.guard.take() {
+            guard.finalize(status);
+        }
+    }
+
+    /// Disarms the guard, returning the reference to the main request to the caller.
+    pub fn disarm(mut self) -> MainCountGuard {
+        self.guard.take().expect("request guard is armed")
+    }
+}
+
+impl core::ops::Deref for RequestGuard {
+    type Target = Request;
+
+    fn deref(&self) -> &Self::Target {
+        let guard = self.guard.as_ref().expect("request guard is armed");
+        // SAFETY: the reference count keeps the main request and its subrequests alive
+        unsafe { guard.0.cast::<Request>().as_ref() }
+    }
+}
+
+impl core::ops::DerefMut for RequestGuard {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.guard
+            .as_mut()
+            .expect("request guard is armed")
+            .request()
+    }
+}
+
+impl Drop for RequestGuard {
+    fn drop(&mut self) {
+        let Some(mut guard) = self.guard.take() else {
+            return;
+        };
+
+        let request = guard.request();
+        crate::ngx_log_debug_http!(request, "request guard: finalizing with {}", self.status.0);
+
+        // Does nothing if the body was already read or discarded.
+        let rc = request.discard_request_body();
+        let status = if rc.is_ok() { self.status } else { rc };
+
+        guard.finalize(status);
+    }
+}
+
+/// Calls the closure saved by [`Request::add_cleanup`].
+///
+/// # Safety
+///
+/// `data` must point to an initialized `F` which is not used afterwards.
+unsafe extern "C" fn request_cleanup_handler<F: FnOnce()>(data: *mut c_void) {
+    ptr::read(data.cast::<F>())()
+}
+
+/// Returns the value of a header cached in one of the `headers_in` fields.
+///
+/// The fields point to the elements of the `headers_in.headers` list, and are set by nginx when
+/// the header is parsed.
+fn header_in_value<'a>(h: *const ngx_table_elt_t) -> Option<&'a NgxStr> {
+    // SAFETY: the pointer is either NULL or points to a header of the request
+    let h = unsafe { h.as_ref()? };
+    Some(unsafe { NgxStr::from_ngx_str(h.value) })
+}
+
+/// A module context allocated by [`Request::create_module_ctx`].
+///
+/// The value is placed first, so the context pointer can be used as a pointer to the value, e.g.
+/// with [`Request::get_module_ctx`].
+#[repr(C)]
+struct TypedModuleCtx<T> {
+    value: T,
+    #[cfg(debug_assertions)]
+    type_id: TypeId,
+}
+
+impl<T: 'static> TypedModuleCtx<T> {
+    #[inline(always)]
+    fn check_type(&self) {
+        #[cfg(debug_assertions)]
+        assert!(
+            self.type_id == TypeId::of::<T>(),
+            "module context type mismatch: expected {}",
+            core::any::type_name::<T>()
+        );
+    }
 }
 
 impl crate::http::HttpModuleConfExt for Request {
@@ -435,7 +1491,8 @@ struct ListPart<'a> {
 }
 impl<'a> From<&'a ngx_list_part_t> for ListPart<'a> {
     fn from(raw: &'a ngx_list_part_t) -> Self {
-        let arr = if raw.nelts != 0 {
+        // A zeroed list, not initialized with `ngx_list_init`, has no elements array.
+        let arr = if raw.nelts != 0 && !raw.elts.is_null() {
             unsafe { slice::from_raw_parts(raw.elts.cast(), raw.nelts) }
         } else {
             &[]
@@ -448,7 +1505,8 @@ impl<'a> From<&'a ngx_list_part_t> for ListPart<'a> {
 ///
 /// # Safety
 ///
-/// The caller has provided a valid [`ngx_str_t`] which can be dereferenced validly.
+/// The caller has provided a valid [`ngx_list_t`] of [`ngx_table_elt_t`] elements, either
+/// initialized or zeroed.
 pub unsafe fn list_iterator(list: &ngx_list_t) -> NgxListIterator<'_> {
     NgxListIterator {
         part: Some((&list.part).into()),
@@ -466,7 +1524,8 @@ impl<'a> Iterator for NgxListIterator<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         let part = self.part.as_mut()?;
-        if self.i >= part.arr.len() {
+        // skip the exhausted and empty parts
+        while self.i >= part.arr.len() {
             if let Some(next_part_raw) = unsafe { part.raw.next.as_ref() } {
                 // loop back
                 *part = next_part_raw.into();
@@ -487,6 +1546,89 @@ impl<'a> Iterator for NgxListIterator<'a> {
     }
 }
 
+/// Iterator over the `name=value` pairs of a query string.
+///
+/// Arguments are separated by `&`, empty arguments are skipped and an argument without `=` has an
+/// empty value. Neither names nor values are unescaped.
+pub struct NgxArgsIterator<'a>(slice::Split<'a, u8, fn(&u8) -> bool>);
+
+impl<'a> NgxArgsIterator<'a> {
+    /// Creates an iterator over the arguments in the query string `args`.
+    pub fn new(args: &'a NgxStr) -> Self {
+        fn is_separator(b: &u8) -> bool {
+            *b == b'&'
+        }
+
+        Self(args.as_bytes().split(is_separator as fn(&u8) -> bool))
+    }
+}
+
+impl<'a> Iterator for NgxArgsIterator<'a> {
+    type Item = (&'a NgxStr, &'a NgxStr);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let arg = self.0.by_ref().find(|arg| !arg.is_empty())?;
+        let (name, value) = match arg.iter().position(|b| *b == b'=') {
+            Some(i) => (&arg[..i], &arg[i + 1..]),
+            None => (arg, &arg[arg.len()..]),
+        };
+        Some((NgxStr::from_bytes(name), NgxStr::from_bytes(value)))
+    }
+}
+
+/// HTTP protocol version of a request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HttpVersion {
+    /// HTTP/0.9
+    Http09,
+    /// HTTP/1.0
+    Http10,
+    /// HTTP/1.1
+    Http11,
+    /// HTTP/2
+    Http2,
+    /// HTTP/3
+    Http3,
+    /// A version value not known to this crate.
+    Unknown(ngx_uint_t),
+}
+
+impl HttpVersion {
+    /// Returns the protocol name in the format of the `$server_protocol` variable.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HttpVersion::Http09 => "HTTP/0.9",
+            HttpVersion::Http10 => "HTTP/1.0",
+            HttpVersion::Http11 => "HTTP/1.1",
+            HttpVersion::Http2 => "HTTP/2.0",
+            HttpVersion::Http3 => "HTTP/3.0",
+            HttpVersion::Unknown(_) => "UNKNOWN",
+        }
+    }
+}
+
+impl From<ngx_uint_t> for HttpVersion {
+    fn from(value: ngx_uint_t) -> Self {
+        // `NGX_HTTP_VERSION_30` is not defined in the older versions of nginx.
+        const NGX_HTTP_VERSION_30: ngx_uint_t = 3000;
+
+        match value {
+            x if x == NGX_HTTP_VERSION_9 as ngx_uint_t => HttpVersion::Http09,
+            x if x == NGX_HTTP_VERSION_10 as ngx_uint_t => HttpVersion::Http10,
+            x if x == NGX_HTTP_VERSION_11 as ngx_uint_t => HttpVersion::Http11,
+            x if x == NGX_HTTP_VERSION_20 as ngx_uint_t => HttpVersion::Http2,
+            NGX_HTTP_VERSION_30 => HttpVersion::Http3,
+            x => HttpVersion::Unknown(x),
+        }
+    }
+}
+
+impl fmt::Display for HttpVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// A possible error value when converting `Method`
 pub struct InvalidMethod {
     _priv: (),
@@ -739,3 +1881,54 @@ enum MethodInner {
     Trace,
     Connect,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_args_iterator() {
+        let args = NgxStr::from_bytes(b"a=1&&b=&c&d=x=y&");
+        let mut it = NgxArgsIterator::new(args);
+
+        assert_eq!(it.next(), Some(("a".into(), "1".into())));
+        assert_eq!(it.next(), Some(("b".into(), "".into())));
+        assert_eq!(it.next(), Some(("c".into(), "".into())));
+        assert_eq!(it.next(), Some(("d".into(), "x=y".into())));
+        assert_eq!(it.next(), None);
+
+        assert_eq!(NgxArgsIterator::new(NgxStr::from_bytes(b"")).next(), None);
+    }
+
+    #[test]
+    fn test_http_version() {
+        assert_eq!(HttpVersion::from(1000), HttpVersion::Http10);
+        assert_eq!(HttpVersion::from(1001), HttpVersion::Http11);
+        assert_eq!(HttpVersion::from(2000).as_str(), "HTTP/2.0");
+        assert_eq!(HttpVersion::from(3000), HttpVersion::Http3);
+        assert_eq!(HttpVersion::from(42), HttpVersion::Unknown(42));
+        assert!(HttpVersion::Http10 < HttpVersion::Http11);
+    }
+
+    #[test]
+    fn test_list_iterator_empty() {
+        let list: ngx_list_t = unsafe { core::mem::zeroed() };
+        assert_eq!(unsafe { list_iterator(&list) }.count(), 0);
+
+        let mut elts: [ngx_table_elt_t; 1] = unsafe { core::mem::zeroed() };
+        elts[0].key = crate::ngx_string!("Host");
+        elts[0].value = crate::ngx_string!("example.com");
+
+        // an empty first part followed by a part with an element
+        let mut next: ngx_list_part_t = unsafe { core::mem::zeroed() };
+        next.elts = elts.as_mut_ptr().cast();
+        next.nelts = 1;
+
+        let mut list: ngx_list_t = unsafe { core::mem::zeroed() };
+        list.part.next = &mut next;
+
+        let mut it = unsafe { list_iterator(&list) };
+        assert_eq!(it.next(), Some(("Host".into(), "example.com".into())));
+        assert_eq!(it.next(), None);
+    }
+}
@@ -82,7 +82,7 @@ macro_rules! http_status_codes {
     (
         $(
             $(#[$docs:meta])*
-            ($num:expr, $konst:ident, $phrase:expr);
+            ($num:expr, $konst:ident $(, $phrase:expr)?);
         )+
     ) => {
         impl HTTPStatus {
@@ -91,30 +91,56 @@ macro_rules! http_status_codes {
             pub const $konst: HTTPStatus = HTTPStatus($num);
         )+
 
+            /// Returns the canonical reason phrase for the status code.
+            ///
+            /// The phrases match the status line table of nginx where it has one, e.g.
+            /// `"Moved Temporarily"` for 302. An empty string is returned for unknown codes and
+            /// for nginx-specific codes that are never sent to the client as is.
+            pub fn reason_phrase(&self) -> &'static str {
+                match self.0 {
+                    $($($num => $phrase,)?)+
+                    _ => "",
+                }
+            }
         }
     }
 }
 
 http_status_codes! {
-    /// 100 CONTINUE
+    /// 100 Continue
     (100, CONTINUE, "Continue");
-    /// 101 SWITCHING_PROTOCOLS
+    /// 101 Switching Protocols
     (101, SWITCHING_PROTOCOLS, "Switching Protocols");
-    /// 102 PROCESSING
+    /// 102 Processing
     (102, PROCESSING, "Processing");
+    /// 103 Early Hints
+    (103, EARLY_HINTS, "Early Hints");
+
     /// 200 OK
     (200, OK, "OK");
     /// 201 Created
     (201, CREATED, "Created");
     /// 202 Accepted
     (202, ACCEPTED, "Accepted");
+    /// 203 Non-Authoritative Information
+    (203, NON_AUTHORITATIVE_INFORMATION, "Non-Authoritative Information");
     /// 204 No Content
     (204, NO_CONTENT, "No Content");
+    /// 205 Reset Content
+    (205, RESET_CONTENT, "Reset Content");
     /// 206 Partial Content
     (206, PARTIAL_CONTENT, "Partial Content");
+    /// 207 Multi-Status
+    (207, MULTI_STATUS, "Multi-Status");
+    /// 208 Already Reported
+    (208, ALREADY_REPORTED, "Already Reported");
+    /// 226 IM Used
+    (226, IM_USED, "IM Used");
 
-    /// 300 SPECIAL_RESPONSE
-    (300, SPECIAL_RESPONSE, "SPECIAL_RESPONSE");
+    /// 300 Multiple Choices
+    (300, MULTIPLE_CHOICES, "Multiple Choices");
+    /// 300 SPECIAL_RESPONSE: An alias for the first code of the special responses
+    (300, SPECIAL_RESPONSE);
     /// 301 Moved Permanently
     (301, MOVED_PERMANENTLY, "Moved Permanently");
     /// 302 Moved Temporarily
@@ -123,6 +149,8 @@ http_status_codes! {
     (303, SEE_OTHER, "See Other");
     /// 304 Not Modified
     (304, NOT_MODIFIED, "Not Modified");
+    /// 305 Use Proxy
+    (305, USE_PROXY, "Use Proxy");
     /// 307 Temporary Redirect
     (307, TEMPORARY_REDIRECT, "Temporary Redirect");
     /// 308 Permanent Redirect
@@ -132,71 +160,117 @@ http_status_codes! {
     (400, BAD_REQUEST, "Bad Request");
     /// 401 Unauthorized
     (401, UNAUTHORIZED, "Unauthorized");
+    /// 402 Payment Required
+    (402, PAYMENT_REQUIRED, "Payment Required");
     /// 403 Forbidden
     (403, FORBIDDEN, "Forbidden");
     /// 404 Not Found
     (404, NOT_FOUND, "Not Found");
-    /// 405 Method Not Allowed
-    (405, NOT_ALLOWED, "Method Not Allowed");
-    /// 408 Request Time Out
-    (408, REQUEST_TIME_OUT, "Request Time Out");
+    /// 405 Not Allowed
+    (405, NOT_ALLOWED, "Not Allowed");
+    /// 406 Not Acceptable
+    (406, NOT_ACCEPTABLE, "Not Acceptable");
+    /// 407 Proxy Authentication Required
+    (407, PROXY_AUTHENTICATION_REQUIRED, "Proxy Authentication Required");
+    /// 408 Request Time-out
+    (408, REQUEST_TIME_OUT, "Request Time-out");
     /// 409 Conflict
     (409, CONFLICT, "Conflict");
+    /// 410 Gone
+    (410, GONE, "Gone");
     /// 411 Length Required
     (411, LENGTH_REQUIRED, "Length Required");
     /// 412 Precondition Failed
     (412, PRECONDITION_FAILED, "Precondition Failed");
-    /// 413 Payload Too Large
-    (413, REQUEST_ENTITY_TOO_LARGE, "Payload Too Large");
-    /// 414 Request Uri Too Large
-    (414, REQUEST_URI_TOO_LARGE, "Request Uri Too Large");
+    /// 413 Request Entity Too Large
+    (413, REQUEST_ENTITY_TOO_LARGE, "Request Entity Too Large");
+    /// 414 Request-URI Too Large
+    (414, REQUEST_URI_TOO_LARGE, "Request-URI Too Large");
     /// 415 Unsupported Media Type
     (415, UNSUPPORTED_MEDIA_TYPE, "Unsupported Media Type");
-    /// 416 Range Not Satisfiable
-    (416, RANGE_NOT_SATISFIABLE, "Range Not Satisfiable");
+    /// 416 Requested Range Not Satisfiable
+    (416, RANGE_NOT_SATISFIABLE, "Requested Range Not Satisfiable");
+    /// 417 Expectation Failed
+    (417, EXPECTATION_FAILED, "Expectation Failed");
+    /// 418 I'm a teapot
+    (418, IM_A_TEAPOT, "I'm a teapot");
     /// 421 Misdirected Request
     (421, MISDIRECTED_REQUEST, "Misdirected Request");
+    /// 422 Unprocessable Content
+    (422, UNPROCESSABLE_CONTENT, "Unprocessable Content");
+    /// 423 Locked
+    (423, LOCKED, "Locked");
+    /// 424 Failed Dependency
+    (424, FAILED_DEPENDENCY, "Failed Dependency");
+    /// 425 Too Early
+    (425, TOO_EARLY, "Too Early");
+    /// 426 Upgrade Required
+    (426, UPGRADE_REQUIRED, "Upgrade Required");
+    /// 428 Precondition Required
+    (428, PRECONDITION_REQUIRED, "Precondition Required");
     /// 429 Too Many Requests
     (429, TOO_MANY_REQUESTS, "Too Many Requests");
+    /// 431 Request Header Fields Too Large
+    (431, REQUEST_HEADER_FIELDS_TOO_LARGE, "Request Header Fields Too Large");
+    /// 451 Unavailable For Legal Reasons
+    (451, UNAVAILABLE_FOR_LEGAL_REASONS, "Unavailable For Legal Reasons");
+
+    // Our own HTTP codes
+    /// 444 CLOSE: The special code to close connection without any response
+    (444, CLOSE);
+    /// 494 NGINX_CODES: The first of the nginx-specific codes
+    (494, NGINX_CODES);
+    /// 494 REQUEST_HEADER_TOO_LARGE: Request header is too large, sent as 400 Bad Request
+    (494, REQUEST_HEADER_TOO_LARGE);
+    /// 495 HTTPS_CERT_ERROR: Client certificate verification error, sent as 400 Bad Request
+    (495, HTTPS_CERT_ERROR);
+    /// 496 HTTPS_NO_CERT: Client certificate is required, sent as 400 Bad Request
+    (496, HTTPS_NO_CERT);
+    /// 497 TO_HTTPS: Plain HTTP request sent to HTTPS port, sent as 400 Bad Request
+    (497, TO_HTTPS);
+    /// 499 CLIENT_CLOSED_REQUEST: Client closed the connection before the response was sent
+    (499, CLIENT_CLOSED_REQUEST);
+
+    /// 500 Internal Server Error
+    (500, INTERNAL_SERVER_ERROR, "Internal Server Error");
+    /// 501 Not Implemented
+    (501, NOT_IMPLEMENTED, "Not Implemented");
+    /// 502 Bad Gateway
+    (502, BAD_GATEWAY, "Bad Gateway");
+    /// 503 Service Temporarily Unavailable
+    (503, SERVICE_UNAVAILABLE, "Service Temporarily Unavailable");
+    /// 504 Gateway Time-out
+    (504, GATEWAY_TIME_OUT, "Gateway Time-out");
+    /// 505 HTTP Version Not Supported
+    (505, VERSION_NOT_SUPPORTED, "HTTP Version Not Supported");
+    /// 506 Variant Also Negotiates
+    (506, VARIANT_ALSO_NEGOTIATES, "Variant Also Negotiates");
+    /// 507 Insufficient Storage
+    (507, INSUFFICIENT_STORAGE, "Insufficient Storage");
+    /// 508 Loop Detected
+    (508, LOOP_DETECTED, "Loop Detected");
+    /// 510 Not Extended
+    (510, NOT_EXTENDED, "Not Extended");
+    /// 511 Network Authentication Required
+    (511, NETWORK_AUTHENTICATION_REQUIRED, "Network Authentication Required");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    // /* Our own HTTP codes */
-    // /* The special code to close connection without any response */
-    /// 444 CLOSE
-    (444, CLOSE, "CLOSE");
-
-    /// 494 NGINX_CODES
-    (494, NGINX_CODES, "NGINX_CODES");
-
-    /// 494 REQUEST_HEADER_TOO_LARGE
-    (494, REQUEST_HEADER_TOO_LARGE, "REQUEST_HEADER_TOO_LARGE");
-
-    /// 495 NGX_HTTPS_CERT_ERROR
-    (495, HTTPS_CERT_ERROR, "NGX_HTTPS_CERT_ERROR");
-    /// 496 NGX_HTTPS_NO_CERT
-    (496, HTTPS_NO_CERT, "NGX_HTTPS_NO_CERT");
-
-    // /*
-    //  * We use the special code for the plain HTTP requests that are sent to
-    //  * HTTPS port to distinguish it from 4XX in an error page redirection
-    //  */
-    /// 497 TO_HTTPS
-    (497, TO_HTTPS, "TO_HTTPS");
-
-    /// 499 CLIENT_CLOSED_REQUEST
-    (499, CLIENT_CLOSED_REQUEST, "CLIENT_CLOSED_REQUEST");
-
-    /// 500 INTERNAL_SERVER_ERROR
-    (500, INTERNAL_SERVER_ERROR, "INTERNAL_SERVER_ERROR");
-    /// 501 NOT_IMPLEMENTED
-    (501, NOT_IMPLEMENTED, "NOT_IMPLEMENTED");
-    /// 502 BAD_GATEWAY
-    (502, BAD_GATEWAY, "BAD_GATEWAY");
-    /// 503 SERVICE_UNAVAILABLE
-    (503, SERVICE_UNAVAILABLE, "SERVICE_UNAVAILABLE");
-    /// 504 GATEWAY_TIME_OUT
-    (504, GATEWAY_TIME_OUT, "GATEWAY_TIME_OUT");
-    /// 505 VERSION_NOT_SUPPORTED
-    (505, VERSION_NOT_SUPPORTED, "VERSION_NOT_SUPPORTED");
-    /// 507 INSUFFICIENT_STORAGE
-    (507, INSUFFICIENT_STORAGE, "INSUFFICIENT_STORAGE");
+    #[test]
+    fn test_reason_phrase() {
+        assert_eq!(HTTPStatus::NOT_FOUND.reason_phrase(), "Not Found");
+        assert_eq!(
+            HTTPStatus::MOVED_TEMPORARILY.reason_phrase(),
+            "Moved Temporarily"
+        );
+        assert_eq!(
+            HTTPStatus::SPECIAL_RESPONSE.reason_phrase(),
+            "Multiple Choices"
+        );
+        assert_eq!(HTTPStatus::CLIENT_CLOSED_REQUEST.reason_phrase(), "");
+        assert_eq!(HTTPStatus(599).reason_phrase(), "");
+    }
 }
@@ -1,3 +1,21 @@
+//! Helpers for the upstream load balancing modules.
+//!
+//! See <https://nginx.org/en/docs/dev/development_guide.html#http_load_balancing>
+
+use core::ffi::c_void;
+use core::ptr;
+
+use crate::core::{NgxConfRef, Status};
+use crate::ffi::{
+    ngx_conf_t, ngx_http_request_t, ngx_http_upstream_free_round_robin_peer,
+    ngx_http_upstream_get_round_robin_peer, ngx_http_upstream_init_round_robin,
+    ngx_http_upstream_init_round_robin_peer, ngx_http_upstream_rr_peer_data_t,
+    ngx_http_upstream_srv_conf_t, ngx_int_t, ngx_peer_connection_t, ngx_uint_t,
+};
+#[cfg(ngx_feature = "ssl")]
+use crate::ffi::{ngx_event_save_peer_session_pt, ngx_event_set_peer_session_pt};
+use crate::http::Request;
+
 /// Define a static upstream peer initializer
 ///
 /// Initializes the upstream 'get', 'free', and 'session' callbacks and gives the module writer an
@@ -24,3 +42,291 @@ macro_rules! http_upstream_init_peer_pt {
         }
     };
 }
+
+/// A custom load balancing policy for the `upstream` blocks.
+///
+/// The balancer is layered on top of the round-robin balancer: the upstream servers are
+/// initialized by `ngx_http_upstream_init_round_robin`, and the default implementations of
+/// [`Balancer::get_peer`] and [`Balancer::free_peer`] delegate to the round-robin peer selection.
+///
+/// An instance of the type is created for each request with [`Balancer::init_peer`] and is
+/// dropped with the request pool.
+///
+/// Use [`set_balancer`] from a directive handler in the `upstream` context to enable the policy.
+///
+/// See <https://nginx.org/en/docs/dev/development_guide.html#http_load_balancing>
+pub trait Balancer: Sized + 'static {
+    /// Initializes the upstream configuration, after the round-robin initialization.
+    fn init(_cf: &mut NgxConfRef, _us: &mut ngx_http_upstream_srv_conf_t) -> Result<(), Status> {
+        Ok(())
+    }
+
+    /// Creates the per-request balancer state.
+    fn init_peer(
+        request: &mut Request,
+        us: &mut ngx_http_upstream_srv_conf_t,
+    ) -> Result<Self, Status>;
+
+    /// Selects a peer for the connection, filling the `sockaddr`, `socklen` and `name` fields of
+    /// `pc`.
+    ///
+    /// Returns `NGX_OK` if the peer is selected, `NGX_BUSY` if all the peers are unavailable, or
+    /// `NGX_DONE` if a cached connection was set to `pc.connection`.
+    fn get_peer(&mut self, pc: &mut ngx_peer_connection_t, rr: &mut RoundRobin) -> Status {
+        rr.get_peer(pc)
+    }
+
+    /// Releases the peer selected by [`Balancer::get_peer`], with the `NGX_PEER_FAILED` flag
+    /// set in `state` if the connection or the request failed.
+    fn free_peer(
+        &mut self,
+        pc: &mut ngx_peer_connection_t,
+        rr: &mut RoundRobin,
+        state: ngx_uint_t,
+    ) {
+        rr.free_peer(pc, state)
+    }
+
+    /// Receives a notification from the upstream module, such as
+    /// `NGX_HTTP_UPSTREAM_NOTIFY_CONNECT`.
+    fn notify(&mut self, _pc: &mut ngx_peer_connection_t, _ty: ngx_uint_t) {}
+}
+
+/// Wrapper for the per-request state of the round-robin balancer.
+#[repr(transparent)]
+pub struct RoundRobin(ngx_http_upstream_rr_peer_data_t);
+
+impl RoundRobin {
+    /// Selects a peer with the round-robin balancer.
+    pub fn get_peer(&mut self, pc: &mut ngx_peer_connection_t) -> Status {
+        Status(unsafe { ngx_http_upstream_get_round_robin_peer(pc, ptr::from_mut(self).cast()) })
+    }
+
+    /// Releases a peer selected with [`RoundRobin::get_peer`].
+    pub fn free_peer(&mut self, pc: &mut ngx_peer_connection_t, state: ngx_uint_t) {
+        unsafe { ngx_http_upstream_free_round_robin_peer(pc, ptr::from_mut(self).cast(), state) }
+    }
+}
+
+impl AsRef<ngx_http_upstream_rr_peer_data_t> for RoundRobin {
+    fn as_ref(&self) -> &ngx_http_upstream_rr_peer_data_t {
+        &self.0
+    }
+}
+
+impl AsMut<ngx_http_upstream_rr_peer_data_t> for RoundRobin {
+    fn as_mut(&mut self) -> &mut ngx_http_upstream_rr_peer_data_t {
+        &mut self.0
+    }
+}
+
+/// Enables the balancer `B` for the upstream block.
+///
+/// The function replaces any previously configured load balancing method, and should be called
+/// from a directive handler in the `upstream` context.
+pub fn set_balancer<B: Balancer>(us: &mut ngx_http_upstream_srv_conf_t) {
+    us.peer.init_upstream = Some(balancer_init_upstream::<B>);
+}
+
+struct BalancerPeerData<B> {
+    balancer: B,
+    rr: *mut RoundRobin,
+    // The round-robin SSL session callbacks expect the round-robin state as `data`.
+    #[cfg(ngx_feature = "ssl")]
+    set_session: ngx_event_set_peer_session_pt,
+    #[cfg(ngx_feature = "ssl")]
+    save_session: ngx_event_save_peer_session_pt,
+}
+
+extern "C" fn balancer_init_upstream<B: Balancer>(
+    cf: *mut ngx_conf_t,
+    us: *mut ngx_http_upstream_srv_conf_t,
+) -> ngx_int_t {
+    // SAFETY: this function is called with non-NULL cf and us always
+    let (cf, us) = unsafe { (NgxConfRef::from_ptr_mut(cf), &mut *us) };
+
+    if unsafe { ngx_http_upstream_init_round_robin(cf.as_ptr(), us) } != Status::NGX_OK.0 {
+        return Status::NGX_ERROR.0;
+    }
+
+    if let Err(err) = B::init(cf, us) {
+        return err.0;
+    }
+
+    us.peer.init = Some(balancer_init_peer::<B>);
+    Status::NGX_OK.0
+}
+
+extern "C" fn balancer_init_peer<B: Balancer>(
+    r: *mut ngx_http_request_t,
+    us: *mut ngx_http_upstream_srv_conf_t,
+) -> ngx_int_t {
+    // SAFETY: this function is called with non-NULL r and us always
+    let (request, us) = unsafe { (Request::from_ngx_http_request(r), &mut *us) };
+
+    if unsafe { ngx_http_upstream_init_round_robin_peer(r, us) } != Status::NGX_OK.0 {
+        return Status::NGX_ERROR.0;
+    }
+
+    let balancer = match B::init_peer(request, us) {
+        Ok(x) => x,
+        Err(err) => return err.0,
+    };
+
+    let Some(upstream) = request.upstream() else {
+        return Status::NGX_ERROR.0;
+    };
+    // SAFETY: the upstream is initialized before calling the peer.init
+    let peer = unsafe { &mut (*upstream).peer };
+
+    let data = request.pool().allocate(BalancerPeerData {
+        balancer,
+        rr: peer.data.cast(),
+        #[cfg(ngx_feature = "ssl")]
+        set_session: peer.set_session,
+        #[cfg(ngx_feature = "ssl")]
+        save_session: peer.save_session,
+    });
+    if data.is_null() {
+        return Status::NGX_ERROR.0;
+    }
+
+    peer.data = data.cast();
+    peer.get = Some(balancer_get_peer::<B>);
+    peer.free = Some(balancer_free_peer::<B>);
+    peer.notify = Some(balancer_notify_peer::<B>);
+
+    #[cfg(ngx_feature = "ssl")]
+    {
+        if peer.set_session.is_some() {
+            peer.set_session = Some(balancer_set_session::<B>);
+        }
+        if peer.save_session.is_some() {
+            peer.save_session = Some(balancer_save_session::<B>);
+        }
+    }
+
+    Status::NGX_OK.0
+}
+
+extern "C" fn balancer_get_peer<B: Balancer>(
+    pc: *mut ngx_peer_connection_t,
+    data: *mut c_void,
+) -> ngx_int_t {
+    // SAFETY: `data` is the state allocated in `balancer_init_peer`
+    let (pc, data) = unsafe { (&mut *pc, &mut *data.cast::<BalancerPeerData<B>>()) };
+    let rr = unsafe { &mut *data.rr };
+    data.balancer.get_peer(pc, rr).0
+}
+
+extern "C" fn balancer_free_peer<B: Balancer>(
+    pc: *mut ngx_peer_connection_t,
+    data: *mut c_void,
+    state: ngx_uint_t,
+) {
+    // SAFETY: `data` is the state allocated in `balancer_init_peer`
+    let (pc, data) = unsafe { (&mut *pc, &mut *data.cast::<BalancerPeerData<B>>()) };
+    let rr = unsafe { &mut *data.rr };
+    data.balancer.free_peer(pc, rr, state)
+}
+
+extern "C" fn balancer_notify_peer<B: Balancer>(
+    pc: *mut ngx_peer_connection_t,
+    data: *mut c_void,
+    ty: ngx_uint_t,
+) {
+    // SAFETY: `data` is the state allocated in `balancer_init_peer`
+    let (pc, data) = unsafe { (&mut *pc, &mut *data.cast::<BalancerPeerData<B>>()) };
+    data.balancer.notify(pc, ty)
+}
+
+#[cfg(ngx_feature = "ssl")]
+extern "C" fn balancer_set_session<B: Balancer>(
+    pc: *mut ngx_peer_connection_t,
+    data: *mut c_void,
+) -> ngx_int_t {
+    // SAFETY: `data` is the state allocated in `balancer_init_peer`
+    let data = unsafe { &mut *data.cast::<BalancerPeerData<B>>() };
+    match data.set_session {
+        Some(set_session) => unsafe { set_session(pc, data.rr.cast()) },
+        None => Status::NGX_OK.0,
+    }
+}
+
+#[cfg(ngx_feature = "ssl")]
+extern "C" fn balancer_save_session<B: Balancer>(
+    pc: *mut ngx_peer_connection_t,
+    data: *mut c_void,
+) {
+    // SAFETY: `data` is the state allocated in `balancer_init_peer`
+    let data = unsafe { &mut *data.cast::<BalancerPeerData<B>>() };
+    if let Some(save_session) = data.save_session {
+        unsafe { save_session(pc, data.rr.cast()) }
+    }
+}
+
+#[cfg(all(test, ngx_feature = "ssl"))]
+mod tests {
+    use core::mem;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    struct TestBalancer;
+
+    impl Balancer for TestBalancer {
+        fn init_peer(
+            _request: &mut Request,
+            _us: &mut ngx_http_upstream_srv_conf_t,
+        ) -> Result<Self, Status> {
+            Ok(Self)
+        }
+    }
+
+    static SESSION_DATA: AtomicUsize = AtomicUsize::new(0);
+
+    unsafe extern "C" fn set_session(
+        _pc: *mut ngx_peer_connection_t,
+        data: *mut c_void,
+    ) -> ngx_int_t {
+        SESSION_DATA.store(data as usize, Ordering::Relaxed);
+        Status::NGX_OK.0
+    }
+
+    unsafe extern "C" fn save_session(_pc: *mut ngx_peer_connection_t, data: *mut c_void) {
+        SESSION_DATA.store(data as usize, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn test_session_callbacks() {
+        let mut rr: RoundRobin = unsafe { mem::zeroed() };
+        let rr_ptr = ptr::from_mut(&mut rr);
+        let mut pc: ngx_peer_connection_t = unsafe { mem::zeroed() };
+
+        let mut data = BalancerPeerData {
+            balancer: TestBalancer,
+            rr: rr_ptr,
+            set_session: Some(set_session),
+            save_session: Some(save_session),
+        };
+        let data_ptr: *mut c_void = ptr::from_mut(&mut data).cast();
+
+        // the round-robin callbacks receive the round-robin state
+        let rc = balancer_set_session::<TestBalancer>(&mut pc, data_ptr);
+        assert_eq!(rc, Status::NGX_OK.0);
+        assert_eq!(SESSION_DATA.swap(0, Ordering::Relaxed), rr_ptr as usize);
+
+        balancer_save_session::<TestBalancer>(&mut pc, data_ptr);
+        assert_eq!(SESSION_DATA.swap(0, Ordering::Relaxed), rr_ptr as usize);
+
+        // missing callbacks are skipped
+        data.set_session = None;
+        data.save_session = None;
+        let data_ptr: *mut c_void = ptr::from_mut(&mut data).cast();
+
+        let rc = balancer_set_session::<TestBalancer>(&mut pc, data_ptr);
+        assert_eq!(rc, Status::NGX_OK.0);
+        balancer_save_session::<TestBalancer>(&mut pc, data_ptr);
+        assert_eq!(SESSION_DATA.load(Ordering::Relaxed), 0);
+    }
+}
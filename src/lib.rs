@@ -39,6 +39,9 @@
 extern crate alloc;
 #[cfg(feature = "std")]
 extern crate std;
+// Allows using the derive macros within the crate tests.
+#[cfg(all(test, feature = "derive"))]
+extern crate self as ngx;
 
 pub mod allocator;
 #[cfg(feature = "async")]
@@ -74,9 +77,30 @@ pub mod sync;
 ///
 /// These are normally generated by the Nginx module system, but need to be
 /// defined when building modules outside of it.
+///
+/// An optional `order` list populates the `ngx_module_order` table, which controls the position
+/// of the dynamically loaded modules in the list of modules. Each module from the library is
+/// inserted before any of the already loaded modules listed _after_ it in the `order`; otherwise,
+/// it is appended to the end of the list.
+///
+/// The order matters for the HTTP filter modules: the filters are initialized in the order of
+/// the modules list, and every filter is added to the head of the chain, thus the modules placed
+/// earlier in the list process the response later. The `auto/module` script of nginx uses the
+/// following order for an HTTP filter module by default:
+///
+/// ```rust,ignore
+/// ngx_modules!(ngx_http_my_filter_module;
+///     order = ["ngx_http_my_filter_module", "ngx_http_copy_filter_module"]);
+/// ```
+///
+/// which places the module before the `ngx_http_copy_filter_module`, so it receives the response
+/// body after all the standard body filters, except for the copy filter, were applied.
 #[macro_export]
 macro_rules! ngx_modules {
-    ($( $mod:ident ),+) => {
+    ($( $mod:ident ),+ $(,)?) => {
+        $crate::ngx_modules!($( $mod ),+; order = []);
+    };
+    ($( $mod:ident ),+ ; order = [ $( $order:literal ),* $(,)? ]) => {
         #[no_mangle]
         #[allow(non_upper_case_globals)]
         pub static mut ngx_modules: [*const $crate::ffi::ngx_module_t; $crate::count!($( $mod, )+) + 1] = [
@@ -93,15 +117,100 @@ macro_rules! ngx_modules {
 
         #[no_mangle]
         #[allow(non_upper_case_globals)]
-        pub static mut ngx_module_order: [*const ::core::ffi::c_char; 1] = [
+        pub static mut ngx_module_order: [*const ::core::ffi::c_char; $crate::count!($( $order, )*) + 1] = [
+            $( concat!($order, "\0").as_ptr() as *const ::core::ffi::c_char, )*
             ::core::ptr::null()
         ];
     };
 }
 
+/// Define a null-terminated static array of module directives.
+///
+/// Each directive lists the name, the type flags, the handler, the configuration level and the
+/// offset of the field in the configuration structure, with an optional `post` pointer. The
+/// array size and the terminating `ngx_command_t::empty()` element are generated.
+///
+/// ```rust,ignore
+/// ngx::commands! {
+///     static mut NGX_HTTP_CURL_COMMANDS = [
+///         {
+///             name: "curl",
+///             type: NGX_HTTP_LOC_CONF | NGX_CONF_TAKE1,
+///             set: ngx_http_curl_commands_set_enable,
+///             conf: NGX_HTTP_LOC_CONF_OFFSET,
+///             offset: 0,
+///         },
+///     ];
+/// }
+/// ```
+///
+/// See <https://nginx.org/en/docs/dev/development_guide.html#config_directives>
+#[macro_export]
+macro_rules! commands {
+    (@post) => { ::core::ptr::null_mut() };
+    (@post $post:expr) => { $post };
+    (
+        $vis:vis static mut $name:ident = [
+            $({
+                name: $cmd:literal,
+                type: $type:expr,
+                set: $set:expr,
+                conf: $conf:expr,
+                offset: $offset:expr
+                $(, post: $post:expr)?
+                $(,)?
+            }),* $(,)?
+        ];
+    ) => {
+        $vis static mut $name: [$crate::ffi::ngx_command_t; $crate::count!($( $cmd, )*) + 1] = [
+            $(
+                $crate::ffi::ngx_command_t {
+                    name: $crate::ngx_string!($cmd),
+                    type_: ($type) as $crate::ffi::ngx_uint_t,
+                    set: Some($set),
+                    conf: $conf,
+                    offset: $offset,
+                    post: $crate::commands!(@post $( $post )?),
+                },
+            )*
+            $crate::ffi::ngx_command_t::empty(),
+        ];
+    };
+}
+
 /// Count number of arguments
 #[macro_export]
 macro_rules! count {
     () => { 0usize };
-    ($x:tt, $( $xs:tt ),*) => { 1usize + $crate::count!($( $xs, )*) };
+    ($x:tt, $( $xs:tt ),* $(,)?) => { 1usize + $crate::count!($( $xs, )*) };
+}
+
+/// Compiles the enclosed items only if nginx was configured for the specified operating system.
+///
+/// This is a shorthand for `#[cfg(ngx_os = "...")]` on each item. The `ngx_os` cfg is derived
+/// from the `DEP_NGINX_OS` variable by the build script of the crate using the macro; see the
+/// [build script] of this crate and the `nginx-sys` documentation for an example.
+///
+/// The recognized values are `darwin`, `freebsd`, `gnu_hurd`, `hpux`, `linux`, `solaris`,
+/// `tru64` and `win32`.
+///
+/// ```rust,ignore
+/// ngx::target_nginx_os!("linux", {
+///     use ngx::ffi::in_pktinfo;
+///
+///     fn pktinfo_addr(pi: &in_pktinfo) -> u32 {
+///         pi.ipi_addr.s_addr
+///     }
+/// });
+/// ```
+///
+/// [build script]: https://github.com/nginx/ngx-rust/blob/main/build.rs
+#[macro_export]
+macro_rules! target_nginx_os {
+    ($os:literal, { $( $item:item )* }) => {
+        $(
+            #[cfg(ngx_os = $os)]
+            $item
+        )*
+    };
 }
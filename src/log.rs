@@ -1,7 +1,7 @@
 use core::cmp;
 use core::fmt::{self, Write};
 use core::mem::MaybeUninit;
-use core::ptr::NonNull;
+use core::ptr::{self, NonNull};
 
 use crate::ffi::{self, ngx_err_t, ngx_log_t, ngx_uint_t, NGX_MAX_ERROR_STR};
 
@@ -25,7 +25,131 @@ pub const LOG_BUFFER_SIZE: usize =
 /// The function may panic if you call it before the main() in nginx creates an initial cycle.
 #[inline(always)]
 pub fn ngx_cycle_log() -> NonNull<ngx_log_t> {
-    NonNull::new(unsafe { (*nginx_sys::ngx_cycle).log }).expect("global logger")
+    NonNull::new(crate::core::Cycle::current().log_ptr()).expect("global logger")
+}
+
+/// Wrapper for a borrowed [`ngx_log_t`].
+///
+/// See <https://nginx.org/en/docs/dev/development_guide.html#logging>
+#[repr(transparent)]
+pub struct LogRef(ngx_log_t);
+
+impl LogRef {
+    /// Creates a `LogRef` reference from an `ngx_log_t` pointer.
+    ///
+    /// # Safety
+    ///
+    /// The caller must provide a valid non-null pointer to an `ngx_log_t`, which remains valid for
+    /// the lifetime of the returned reference.
+    pub unsafe fn from_ptr<'a>(log: *mut ngx_log_t) -> &'a Self {
+        &*log.cast()
+    }
+
+    /// Creates a mutable `LogRef` reference from an `ngx_log_t` pointer.
+    ///
+    /// # Safety
+    ///
+    /// The caller must provide a valid non-null pointer to an `ngx_log_t`, which remains valid for
+    /// the lifetime of the returned reference.
+    pub unsafe fn from_ptr_mut<'a>(log: *mut ngx_log_t) -> &'a mut Self {
+        &mut *log.cast()
+    }
+
+    /// Returns a raw pointer to the underlying `ngx_log_t`.
+    #[inline]
+    pub fn as_ptr(&self) -> *mut ngx_log_t {
+        ptr::from_ref(&self.0).cast_mut()
+    }
+
+    /// Returns the configured log level.
+    ///
+    /// For the debug levels, the value also contains the enabled [`DebugMask`] bits.
+    #[inline]
+    pub fn level(&self) -> ngx_uint_t {
+        self.0.log_level
+    }
+
+    /// Writes a message at the specified level, if the level is enabled.
+    ///
+    /// `err` is an optional system error code (`errno`) to append to the message, or 0.
+    pub fn error(&self, level: ngx_uint_t, err: ngx_err_t, args: fmt::Arguments<'_>) {
+        if level <= self.level() {
+            let mut buf = [const { MaybeUninit::<u8>::uninit() }; LOG_BUFFER_SIZE];
+            let message = write_fmt(&mut buf, args);
+            unsafe { log_error(level, self.as_ptr(), err, message) };
+        }
+    }
+
+    /// Writes a message at the debug level, if debug logging is enabled for the `mask`.
+    pub fn debug(&self, mask: DebugMask, args: fmt::Arguments<'_>) {
+        if DEBUG && check_mask(mask, self.level()) {
+            let mut buf = [const { MaybeUninit::<u8>::uninit() }; LOG_BUFFER_SIZE];
+            let message = write_fmt(&mut buf, args);
+            unsafe { log_debug(self.as_ptr(), 0, message) };
+        }
+    }
+}
+
+impl AsRef<ngx_log_t> for LogRef {
+    fn as_ref(&self) -> &ngx_log_t {
+        &self.0
+    }
+}
+
+/// Writes to the log at the `NGX_LOG_NOTICE` level.
+///
+/// Each [`write!`] or [`writeln!`] invocation results in a single log entry, with the trailing
+/// newline removed.
+impl fmt::Write for LogRef {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write_fmt(format_args!("{s}"))
+    }
+
+    fn write_fmt(&mut self, args: fmt::Arguments<'_>) -> fmt::Result {
+        let level = ffi::NGX_LOG_NOTICE as ngx_uint_t;
+        if level <= self.level() {
+            let mut buf = [const { MaybeUninit::<u8>::uninit() }; LOG_BUFFER_SIZE];
+            let message = write_fmt(&mut buf, args);
+            let message = message.strip_suffix(b"\n").unwrap_or(message);
+            unsafe { log_error(level, self.as_ptr(), 0, message) };
+        }
+        Ok(())
+    }
+}
+
+/// Conversion into a raw log pointer, used to accept both raw pointers and [`LogRef`] in the
+/// logging macros.
+pub trait IntoLogPtr {
+    /// Returns a raw pointer to the `ngx_log_t`.
+    fn into_log_ptr(self) -> *mut ngx_log_t;
+}
+
+impl IntoLogPtr for *mut ngx_log_t {
+    #[inline(always)]
+    fn into_log_ptr(self) -> *mut ngx_log_t {
+        self
+    }
+}
+
+impl IntoLogPtr for NonNull<ngx_log_t> {
+    #[inline(always)]
+    fn into_log_ptr(self) -> *mut ngx_log_t {
+        self.as_ptr()
+    }
+}
+
+impl IntoLogPtr for &LogRef {
+    #[inline(always)]
+    fn into_log_ptr(self) -> *mut ngx_log_t {
+        self.as_ptr()
+    }
+}
+
+impl IntoLogPtr for &mut LogRef {
+    #[inline(always)]
+    fn into_log_ptr(self) -> *mut ngx_log_t {
+        self.as_ptr()
+    }
 }
 
 /// Utility function to provide typed checking of the mask's field state.
@@ -90,12 +214,14 @@ pub unsafe fn log_debug(log: *mut ngx_log_t, err: ngx_err_t, buf: &[u8]) {
 
 /// Write to logger at a specified level.
 ///
+/// The logger can be specified as a raw `ngx_log_t` pointer or a [`LogRef`] reference.
+///
 /// See [Logging](https://nginx.org/en/docs/dev/development_guide.html#logging)
 /// for available log levels.
 #[macro_export]
 macro_rules! ngx_log_error {
     ( $level:expr, $log:expr, $($arg:tt)+ ) => {
-        let log = $log;
+        let log = $crate::log::IntoLogPtr::into_log_ptr($log);
         let level = $level as $crate::ffi::ngx_uint_t;
         if level < unsafe { (*log).log_level } {
             let mut buf =
@@ -131,10 +257,12 @@ macro_rules! ngx_conf_log_error {
 }
 
 /// Write to logger at debug level.
+///
+/// The logger can be specified as a raw `ngx_log_t` pointer or a [`LogRef`] reference.
 #[macro_export]
 macro_rules! ngx_log_debug {
     ( mask: $mask:expr, $log:expr, $($arg:tt)+ ) => {
-        let log = $log;
+        let log = $crate::log::IntoLogPtr::into_log_ptr($log);
         if $crate::log::DEBUG && $crate::log::check_mask($mask, unsafe { (*log).log_level }) {
             let mut buf =
                 [const { ::core::mem::MaybeUninit::<u8>::uninit() }; $crate::log::LOG_BUFFER_SIZE];
@@ -149,12 +277,16 @@ macro_rules! ngx_log_debug {
 
 /// Log to request connection log at level [`NGX_LOG_DEBUG_HTTP`].
 ///
+/// Accepts a [`Request`](crate::http::Request) or a [`LogRef`] reference, e.g. the one returned
+/// by `Request::log`. The reference is reborrowed, so a `&mut Request` remains usable after the
+/// macro. Raw log pointers should be passed to [`ngx_log_debug_mask`] with [`DebugMask::Http`]
+/// instead.
+///
 /// [`NGX_LOG_DEBUG_HTTP`]: https://nginx.org/en/docs/dev/development_guide.html#logging
 #[macro_export]
 macro_rules! ngx_log_debug_http {
     ( $request:expr, $($arg:tt)+ ) => {
-        let log = unsafe { (*$request.connection()).log };
-        $crate::ngx_log_debug!(mask: $crate::log::DebugMask::Http, log, $($arg)+);
+        $crate::ngx_log_debug!(mask: $crate::log::DebugMask::Http, &*$request, $($arg)+);
     }
 }
 
@@ -22,9 +22,15 @@
 //! > shared between two processes. — end note]
 //!
 //! In practice, this recommendation is applied in all the implementations that matter to us.
+use core::alloc::Layout;
+use core::mem;
+use core::ptr::NonNull;
 use core::sync::atomic::{self, Ordering};
 
-use nginx_sys::ngx_sched_yield;
+use nginx_sys::{ngx_atomic_t, ngx_sched_yield};
+
+use crate::allocator::{AllocError, Allocator};
+use crate::core::SlabPool;
 
 const NGX_RWLOCK_SPIN: usize = 2048;
 const NGX_RWLOCK_WLOCK: usize = usize::MAX;
@@ -121,3 +127,97 @@ unsafe impl lock_api::RawRwLock for RawSpinlock {
         self.0.store(0, Ordering::Release)
     }
 }
+
+/// Counter over an [`ngx_atomic_t`] variable in shared memory, e.g. for statistics shared between
+/// the worker processes.
+///
+/// The counter uses the same memory layout as the nginx counters, such as `ngx_stat_requests`,
+/// but the operations are implemented with [core::sync::atomic]. See the [module
+/// documentation](self) for the limitations.
+#[derive(Clone, Copy, Debug)]
+pub struct AtomicCounter(NonNull<ngx_atomic_t>);
+
+// SAFETY: the counter is only accessed with atomic operations
+unsafe impl Send for AtomicCounter {}
+unsafe impl Sync for AtomicCounter {}
+
+const _: () = assert!(
+    mem::size_of::<ngx_atomic_t>() == mem::size_of::<NgxAtomic>()
+        && mem::align_of::<ngx_atomic_t>() == mem::align_of::<NgxAtomic>()
+);
+
+impl AtomicCounter {
+    /// Allocates a zero-initialized counter in the slab pool.
+    ///
+    /// The memory is not released automatically; use [`AtomicCounter::free`] if the counter is
+    /// no longer needed.
+    pub fn new_in(pool: &SlabPool) -> Result<Self, AllocError> {
+        let ptr = pool.allocate_zeroed(Layout::new::<ngx_atomic_t>())?;
+        Ok(Self(ptr.cast()))
+    }
+
+    /// Creates a counter over an existing `ngx_atomic_t` variable.
+    ///
+    /// # Safety
+    ///
+    /// The pointer must be non-null, properly aligned and valid for reads and writes for as long
+    /// as the counter or any of its copies is used. All the concurrent accesses to the variable
+    /// must be atomic.
+    pub unsafe fn from_ptr(ptr: *mut ngx_atomic_t) -> Self {
+        Self(NonNull::new_unchecked(ptr))
+    }
+
+    /// Returns a raw pointer to the underlying variable.
+    pub fn as_ptr(&self) -> *mut ngx_atomic_t {
+        self.0.as_ptr()
+    }
+
+    /// Increments the counter, returning the previous value.
+    #[inline]
+    pub fn inc(&self) -> usize {
+        self.add(1)
+    }
+
+    /// Adds `n` to the counter, wrapping around on overflow, and returns the previous value.
+    #[inline]
+    pub fn add(&self, n: usize) -> usize {
+        self.atomic().fetch_add(n, Ordering::AcqRel)
+    }
+
+    /// Returns the current value of the counter.
+    #[inline]
+    pub fn get(&self) -> usize {
+        self.atomic().load(Ordering::Acquire)
+    }
+
+    /// Releases the memory allocated with [`AtomicCounter::new_in`].
+    ///
+    /// # Safety
+    ///
+    /// The counter must be allocated from the same pool, and neither the counter nor any of its
+    /// copies may be used afterwards.
+    pub unsafe fn free(self, pool: &SlabPool) {
+        pool.deallocate(self.0.cast(), Layout::new::<ngx_atomic_t>())
+    }
+
+    fn atomic(&self) -> &NgxAtomic {
+        // SAFETY: the pointer is valid and aligned, the layouts are checked above, and all the
+        // accesses are atomic
+        unsafe { NgxAtomic::from_ptr(self.0.as_ptr().cast()) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_atomic_counter() {
+        let mut value: ngx_atomic_t = 0;
+        let counter = unsafe { AtomicCounter::from_ptr(&mut value) };
+
+        assert_eq!(counter.inc(), 0);
+        assert_eq!(counter.add(10), 1);
+        assert_eq!(counter.get(), 11);
+    }
+}
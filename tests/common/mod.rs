@@ -0,0 +1,244 @@
+//! Test harness for running nginx with a generated configuration.
+#![allow(dead_code)]
+
+use std::env;
+use std::fs;
+use std::io::{self, Read, Result, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+#[cfg(unix)]
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::process::Output;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const NGINX_BINARY_NAME: &str = "nginx";
+
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Convert a CStr to a PathBuf
+pub fn cstr_to_path(val: &std::ffi::CStr) -> Option<PathBuf> {
+    if val.is_empty() {
+        return None;
+    }
+
+    #[cfg(unix)]
+    let str = std::ffi::OsStr::from_bytes(val.to_bytes());
+    #[cfg(not(unix))]
+    let str = std::str::from_utf8(val.to_bytes()).ok()?;
+
+    Some(PathBuf::from(str))
+}
+
+/// Find nginx binary in the build directory
+pub fn find_nginx_binary() -> io::Result<PathBuf> {
+    let path = [
+        // TEST_NGINX_BINARY is specified for tests
+        env::var("TEST_NGINX_BINARY").ok().map(PathBuf::from),
+        // The module is built against an external NGINX source tree
+        env::var("NGINX_BUILD_DIR")
+            .map(PathBuf::from)
+            .map(|x| x.join(NGINX_BINARY_NAME))
+            .ok(),
+        env::var("NGINX_SOURCE_DIR")
+            .map(PathBuf::from)
+            .map(|x| x.join("objs").join(NGINX_BINARY_NAME))
+            .ok(),
+        // Fallback to the build directory exposed by nginx-sys
+        option_env!("DEP_NGINX_BUILD_DIR")
+            .map(PathBuf::from)
+            .map(|x| x.join(NGINX_BINARY_NAME)),
+    ]
+    .into_iter()
+    .flatten()
+    .find(|x| x.is_file())
+    .ok_or(io::ErrorKind::NotFound)?;
+
+    Ok(path)
+}
+
+/// Find a dynamic module in the `TEST_NGINX_MODULES` directory, as used by the example tests
+pub fn find_module(name: &str) -> Option<PathBuf> {
+    let file = format!("{name}{}", env::consts::DLL_SUFFIX);
+    let path = PathBuf::from(env::var_os("TEST_NGINX_MODULES")?).join(file);
+    path.is_file().then_some(path)
+}
+
+/// harness to test nginx
+pub struct Nginx {
+    pub prefix: tempfile::TempDir,
+    pub bin_path: PathBuf,
+    pub config_path: PathBuf,
+    pub port: u16,
+    running: bool,
+}
+
+impl Default for Nginx {
+    /// create nginx with default
+    fn default() -> Nginx {
+        let binary = find_nginx_binary().expect("nginx binary");
+        Nginx::new(binary).expect("test harness")
+    }
+}
+
+impl Nginx {
+    pub fn new(binary: impl AsRef<Path>) -> io::Result<Nginx> {
+        let prefix = tempfile::tempdir()?;
+        let config = prefix.path().join("nginx.conf");
+
+        fs::create_dir(prefix.path().join("logs"))?;
+
+        // Reserve an ephemeral port; it is released before nginx binds to it
+        let port = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?
+            .local_addr()?
+            .port();
+
+        Ok(Nginx {
+            prefix,
+            bin_path: binary.as_ref().to_owned(),
+            config_path: config,
+            port,
+            running: false,
+        })
+    }
+
+    /// start nginx process with arguments
+    pub fn cmd(&self, args: &[&str]) -> Result<Output> {
+        let prefix = self.prefix.path().to_string_lossy();
+        let config_path = self.config_path.to_string_lossy();
+        let args = [&["-p", &prefix, "-c", &config_path], args].concat();
+        let result = Command::new(&self.bin_path).args(args).output();
+
+        match result {
+            Err(e) => Err(e),
+
+            Ok(output) => {
+                println!("status: {}", output.status);
+                println!("stdout: {}", String::from_utf8_lossy(&output.stdout));
+                println!("stderr: {}", String::from_utf8_lossy(&output.stderr));
+                Ok(output)
+            }
+        }
+    }
+
+    /// complete stop the nginx binary
+    pub fn stop(&mut self) -> Result<Output> {
+        let output = self.cmd(&["-s", "stop"])?;
+        if output.status.success() {
+            self.running = false;
+        }
+        Ok(output)
+    }
+
+    /// start the nginx binary
+    pub fn start(&mut self) -> Result<Output> {
+        let output = self.cmd(&[])?;
+        if output.status.success() {
+            self.running = true;
+        }
+        Ok(output)
+    }
+
+    // make sure we stop existing nginx and start new master process
+    // intentinally ignore failure in stop
+    pub fn restart(&mut self) -> Result<Output> {
+        let _ = self.stop();
+        self.start()
+    }
+
+    // replace config with another config
+    pub fn replace_config<P: AsRef<Path>>(&mut self, from: P) -> Result<u64> {
+        println!(
+            "copying config from: {:?} to: {:?}",
+            from.as_ref(),
+            self.config_path
+        ); // replace with logging
+        fs::copy(from, &self.config_path)
+    }
+
+    /// Write the config from a template.
+    ///
+    /// The following placeholders are expanded:
+    ///  - `%%PORT%%` - the port reserved for the test server
+    ///  - `%%TEST_GLOBALS%%` - the `TEST_NGINX_GLOBALS` environment variable, e.g. the
+    ///    `load_module` directives
+    ///  - `%%TEST_GLOBALS_HTTP%%` - the `TEST_NGINX_GLOBALS_HTTP` environment variable
+    pub fn write_config(&mut self, template: &str) -> Result<()> {
+        let config = template
+            .replace("%%PORT%%", &self.port.to_string())
+            .replace(
+                "%%TEST_GLOBALS%%",
+                &env::var("TEST_NGINX_GLOBALS").unwrap_or_default(),
+            )
+            .replace(
+                "%%TEST_GLOBALS_HTTP%%",
+                &env::var("TEST_NGINX_GLOBALS_HTTP").unwrap_or_default(),
+            );
+
+        fs::write(&self.config_path, config)
+    }
+
+    /// Start nginx with the config generated from a template and wait until the test server
+    /// accepts connections.
+    pub fn run(&mut self, template: &str) -> Result<()> {
+        self.write_config(template)?;
+
+        let output = self.start()?;
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "nginx failed to start: {}",
+                String::from_utf8_lossy(&output.stderr)
+            )));
+        }
+
+        let started = Instant::now();
+        while TcpStream::connect((Ipv4Addr::LOCALHOST, self.port)).is_err() {
+            if started.elapsed() > STARTUP_TIMEOUT {
+                return Err(io::ErrorKind::TimedOut.into());
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+
+        Ok(())
+    }
+
+    /// Send a raw HTTP request to the test server and return the whole response.
+    ///
+    /// The request should use `Connection: close` or HTTP/1.0, as the response is read until the
+    /// connection is closed.
+    pub fn http(&self, request: &str) -> Result<String> {
+        let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, self.port))?;
+        stream.set_read_timeout(Some(STARTUP_TIMEOUT))?;
+        stream.write_all(request.as_bytes())?;
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response)?;
+        Ok(String::from_utf8_lossy(&response).into_owned())
+    }
+
+    /// Send a GET request with the specified extra header lines to the test server.
+    pub fn http_get(&self, uri: &str, headers: &[&str]) -> Result<String> {
+        let mut request = format!("GET {uri} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n");
+        for header in headers {
+            request.push_str(header);
+            request.push_str("\r\n");
+        }
+        request.push_str("\r\n");
+
+        self.http(&request)
+    }
+
+    /// Read the error log of the test server.
+    pub fn error_log(&self) -> Result<String> {
+        fs::read_to_string(self.prefix.path().join("logs").join("error.log"))
+    }
+}
+
+impl Drop for Nginx {
+    fn drop(&mut self) {
+        if self.running {
+            let _ = self.stop();
+        }
+    }
+}
@@ -0,0 +1,92 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+
+    const HTTP_CONFIG: &str = r#"
+%%TEST_GLOBALS%%
+
+error_log logs/error.log info;
+
+events {
+}
+
+http {
+    %%TEST_GLOBALS_HTTP%%
+
+    access_log off;
+
+    server {
+        listen       127.0.0.1:%%PORT%%;
+        server_name  localhost;
+
+        location / {
+            return 200 "hello\n";
+        }
+
+        location /missing {
+            return 404;
+        }
+    }
+}
+"#;
+
+    const CURL_CONFIG: &str = r#"
+load_module %%MODULE%%;
+
+error_log logs/error.log info;
+
+events {
+}
+
+http {
+    access_log off;
+
+    server {
+        listen       127.0.0.1:%%PORT%%;
+        server_name  localhost;
+
+        location / {
+            curl on;
+            empty_gif;
+        }
+    }
+}
+"#;
+
+    #[test]
+    fn test_http() {
+        let mut nginx = Nginx::default();
+        nginx.run(HTTP_CONFIG).expect("Unable to start NGINX");
+
+        let response = nginx.http_get("/", &[]).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+        assert!(response.ends_with("\r\n\r\nhello\n"), "{response}");
+
+        let response = nginx.http_get("/missing", &[]).unwrap();
+        assert!(response.starts_with("HTTP/1.1 404 Not Found"), "{response}");
+
+        let output = nginx.stop().expect("Unable to stop NGINX");
+        assert!(output.status.success());
+    }
+
+    /// Exercises the `curl` example module, if built as a dynamic module.
+    #[test]
+    fn test_curl_module() {
+        let Some(module) = find_module("ngx_http_curl_module") else {
+            println!("skipped: ngx_http_curl_module not found in TEST_NGINX_MODULES");
+            return;
+        };
+
+        let mut nginx = Nginx::default();
+        let config = CURL_CONFIG.replace("%%MODULE%%", &module.to_string_lossy());
+        nginx.run(&config).expect("Unable to start NGINX");
+
+        let response = nginx.http_get("/", &["User-Agent: curl/1.2.3"]).unwrap();
+        assert!(response.starts_with("HTTP/1.1 403 Forbidden"), "{response}");
+
+        let response = nginx.http_get("/", &["User-Agent: MSIE 6.0"]).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+    }
+}
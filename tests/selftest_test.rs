@@ -0,0 +1,183 @@
+mod common;
+
+/// Runs the tests of the `selftest` example module, if built as a dynamic module.
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+
+    const SELFTEST_CONFIG: &str = r#"
+load_module %%MODULE%%;
+
+%%TEST_GLOBALS%%
+
+error_log logs/error.log info;
+
+events {
+}
+
+http {
+    %%TEST_GLOBALS_HTTP%%
+
+    access_log off;
+
+    server {
+        listen       127.0.0.1:%%PORT%%;
+        server_name  localhost;
+
+        location / {
+            client_body_buffer_size 1k;
+            client_max_body_size 16k;
+            tcp_nopush on;
+            selftest;
+        }
+    }
+}
+"#;
+
+    /// Starts nginx with the `selftest` module, or returns `None` if the module is not found.
+    fn start() -> Option<Nginx> {
+        let Some(module) = find_module("ngx_http_selftest_module") else {
+            println!("skipped: ngx_http_selftest_module not found in TEST_NGINX_MODULES");
+            return None;
+        };
+
+        let mut nginx = Nginx::default();
+        let config = SELFTEST_CONFIG.replace("%%MODULE%%", &module.to_string_lossy());
+        nginx.run(&config).expect("Unable to start NGINX");
+        Some(nginx)
+    }
+
+    /// Runs the named test and checks the response.
+    fn selftest(name: &str) {
+        let Some(nginx) = start() else {
+            return;
+        };
+
+        let response = nginx.http_get(&format!("/?name={name}"), &[]).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+    }
+
+    #[test]
+    fn test_array_growth() {
+        selftest("array_growth");
+    }
+
+    /// Sends a request with the body generated as a repeated alphabet.
+    fn post_body(nginx: &Nginx, name: &str, len: usize, chunked: bool) -> String {
+        let body: String = (0..len).map(|i| (b'a' + (i % 26) as u8) as char).collect();
+        let request = if chunked {
+            format!(
+                "POST /?name={name} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\
+                 Transfer-Encoding: chunked\r\n\r\n{len:x}\r\n{body}\r\n0\r\n\r\n"
+            )
+        } else {
+            format!(
+                "POST /?name={name} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\
+                 Content-Length: {len}\r\n\r\n{body}"
+            )
+        };
+        nginx.http(&request).unwrap()
+    }
+
+    #[test]
+    fn test_body_bytes() {
+        let Some(nginx) = start() else {
+            return;
+        };
+
+        // larger than `client_body_buffer_size`, the rest is written to a temporary file
+        let response = post_body(&nginx, "body_bytes", 8000, false);
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+    }
+
+    #[test]
+    fn test_body_bytes_too_large() {
+        let Some(nginx) = start() else {
+            return;
+        };
+
+        // the chunked body size is only known after reading it
+        let response = post_body(&nginx, "body_bytes", 20000, true);
+        assert!(
+            response.starts_with("HTTP/1.1 413 Request Entity Too Large"),
+            "{response}"
+        );
+    }
+
+    #[test]
+    fn test_buffer_from_vec() {
+        selftest("buffer_from_vec");
+    }
+
+    #[test]
+    fn test_content_type() {
+        selftest("content_type");
+    }
+
+    #[test]
+    fn test_hash() {
+        selftest("hash");
+    }
+
+    #[test]
+    fn test_header_parser() {
+        selftest("header_parser");
+    }
+
+    #[test]
+    fn test_rbtree_drain() {
+        selftest("rbtree_drain");
+    }
+
+    #[test]
+    fn test_rbtree_insert_panic() {
+        selftest("rbtree_insert_panic");
+    }
+
+    #[test]
+    fn test_request_body() {
+        let Some(nginx) = start() else {
+            return;
+        };
+
+        // larger than `client_body_buffer_size`, the rest is written to a temporary file
+        let response = post_body(&nginx, "request_body", 8000, false);
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+    }
+
+    #[test]
+    fn test_request_guard() {
+        let Some(nginx) = start() else {
+            return;
+        };
+
+        let request = "POST /?name=request_guard HTTP/1.1\r\nHost: localhost\r\n\
+                       Connection: close\r\nContent-Length: 5\r\n\r\nhello";
+        let response = nginx.http(request).unwrap();
+        assert!(
+            response.starts_with("HTTP/1.1 500 Internal Server Error"),
+            "{response}"
+        );
+    }
+
+    #[test]
+    fn test_scheduler_fairness() {
+        selftest("scheduler_fairness");
+    }
+
+    #[test]
+    fn test_sleep_reset() {
+        selftest("sleep_reset");
+    }
+
+    #[test]
+    fn test_shared_dict() {
+        selftest("shared_dict");
+    }
+
+    #[test]
+    #[cfg(any(ngx_os = "linux", ngx_os = "freebsd"))]
+    fn test_tcp_nopush() {
+        selftest("tcp_nopush");
+    }
+}